@@ -1,3 +1,15 @@
 fn main() {
+  // The headless CLI resolves the app data directory without a Tauri context,
+  // so hand it the bundle identifier at compile time
+  println!("cargo:rerun-if-changed=tauri.conf.json");
+  let config =
+    std::fs::read_to_string("tauri.conf.json").expect("failed to read tauri.conf.json");
+  let config: serde_json::Value =
+    serde_json::from_str(&config).expect("tauri.conf.json is not valid JSON");
+  let identifier = config["identifier"]
+    .as_str()
+    .expect("tauri.conf.json has no identifier");
+  println!("cargo:rustc-env=MONIGHT_APP_IDENTIFIER={}", identifier);
+
   tauri_build::build()
 }
@@ -0,0 +1,676 @@
+use lopdf::{Dictionary, Document, IncrementalDocument, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::document_kind::DocumentKind;
+use crate::error::MonightError;
+use crate::form_data::{form_value, target_href, write_file, FieldValue, FormValue};
+use crate::pdf::{dict_get, invalid_pdf, numbers, parse_document, resolve, text_string};
+use crate::{fdf, xfdf};
+
+/// Deepest field hierarchy we follow.
+const MAX_DEPTH: usize = 64;
+
+// Field flags (`/Ff`)
+const READ_ONLY: i64 = 1;
+const RADIO: i64 = 1 << 15;
+const PUSH_BUTTON: i64 = 1 << 16;
+
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    Text,
+    Checkbox,
+    Radio,
+    Choice,
+    Signature,
+    /// Push button, which has no value
+    Button,
+}
+
+/// Where one widget of a field is drawn.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct FieldWidget {
+    /// Zero-based page index, if the widget is on a page
+    pub page: Option<u32>,
+    pub rect: [f64; 4],
+}
+
+/// A terminal field of the document's interactive form.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct FormField {
+    /// Fully qualified field name, e.g. `address.city`
+    pub name: String,
+    pub kind: FieldKind,
+    pub value: Option<FormValue>,
+    /// Choice options, or the on-states of checkbox and radio widgets
+    pub options: Vec<String>,
+    pub read_only: bool,
+    pub widgets: Vec<FieldWidget>,
+}
+
+struct FieldNode {
+    id: ObjectId,
+    widget_ids: Vec<ObjectId>,
+    field: FormField,
+}
+
+/// Attributes a field takes from its ancestors when it does not set them.
+#[derive(Clone, Copy, Default)]
+struct Inherited<'a> {
+    kind: Option<&'a [u8]>,
+    flags: i64,
+    value: Option<&'a Object>,
+}
+
+struct FieldReader<'a> {
+    doc: &'a Document,
+    page_indices: HashMap<ObjectId, u32>,
+    /// Page of every annotation listed in a page's `/Annots`
+    annotation_pages: HashMap<ObjectId, u32>,
+    visited: HashSet<ObjectId>,
+    nodes: Vec<FieldNode>,
+}
+
+impl<'a> FieldReader<'a> {
+    fn new(doc: &'a Document) -> Self {
+        let mut page_indices = HashMap::new();
+        let mut annotation_pages = HashMap::new();
+        for (number, id) in doc.get_pages() {
+            page_indices.insert(id, number - 1);
+            let annots = doc
+                .get_dictionary(id)
+                .ok()
+                .and_then(|page| dict_get(doc, page, b"Annots"))
+                .and_then(|annots| annots.as_array().ok());
+            for annot in annots.into_iter().flatten() {
+                if let Ok(annot_id) = annot.as_reference() {
+                    annotation_pages.insert(annot_id, number - 1);
+                }
+            }
+        }
+
+        FieldReader {
+            doc,
+            page_indices,
+            annotation_pages,
+            visited: HashSet::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    fn read(
+        &mut self,
+        fields: &[Object],
+        parent: Option<&str>,
+        inherited: Inherited<'a>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        for field in fields {
+            let Ok(id) = field.as_reference() else {
+                continue;
+            };
+            if !self.visited.insert(id) {
+                continue;
+            }
+            let Ok(dict) = self.doc.get_dictionary(id) else {
+                continue;
+            };
+
+            let name = match (parent, dict_get(self.doc, dict, b"T").and_then(text_string)) {
+                (Some(parent), Some(partial)) => format!("{}.{}", parent, partial),
+                (None, Some(partial)) => partial,
+                (Some(parent), None) => parent.to_string(),
+                (None, None) => continue,
+            };
+            let inherited = Inherited {
+                kind: dict_get(self.doc, dict, b"FT")
+                    .and_then(|kind| kind.as_name().ok())
+                    .or(inherited.kind),
+                flags: dict_get(self.doc, dict, b"Ff")
+                    .and_then(|flags| flags.as_i64().ok())
+                    .unwrap_or(inherited.flags),
+                value: dict_get(self.doc, dict, b"V").or(inherited.value),
+            };
+
+            let kids: Vec<&Object> = dict_get(self.doc, dict, b"Kids")
+                .and_then(|kids| kids.as_array().ok())
+                .map(|kids| kids.iter().collect())
+                .unwrap_or_default();
+            // Kids with a partial name are fields; the rest are widgets
+            let (field_kids, widget_kids): (Vec<&Object>, Vec<&Object>) =
+                kids.into_iter().partition(|kid| {
+                    resolve(self.doc, kid)
+                        .and_then(|kid| kid.as_dict().ok())
+                        .is_some_and(|kid| kid.has(b"T"))
+                });
+            if !field_kids.is_empty() {
+                let field_kids: Vec<Object> = field_kids.into_iter().cloned().collect();
+                self.read(&field_kids, Some(&name), inherited, depth + 1);
+                continue;
+            }
+
+            let widget_ids = if widget_kids.is_empty() {
+                vec![id]
+            } else {
+                widget_kids
+                    .iter()
+                    .filter_map(|kid| kid.as_reference().ok())
+                    .collect()
+            };
+            if let Some(field) = self.field(name, dict, inherited, &widget_ids) {
+                self.nodes.push(FieldNode {
+                    id,
+                    widget_ids,
+                    field,
+                });
+            }
+        }
+    }
+
+    fn field(
+        &self,
+        name: String,
+        dict: &Dictionary,
+        inherited: Inherited,
+        widget_ids: &[ObjectId],
+    ) -> Option<FormField> {
+        let kind = match inherited.kind? {
+            b"Tx" => FieldKind::Text,
+            b"Ch" => FieldKind::Choice,
+            b"Sig" => FieldKind::Signature,
+            b"Btn" if inherited.flags & PUSH_BUTTON != 0 => FieldKind::Button,
+            b"Btn" if inherited.flags & RADIO != 0 => FieldKind::Radio,
+            b"Btn" => FieldKind::Checkbox,
+            _ => return None,
+        };
+        let widgets: Vec<(ObjectId, &Dictionary)> = widget_ids
+            .iter()
+            .filter_map(|&id| Some((id, self.doc.get_dictionary(id).ok()?)))
+            .collect();
+
+        let options = match kind {
+            FieldKind::Choice => dict_get(self.doc, dict, b"Opt")
+                .and_then(|options| options.as_array().ok())
+                .map(|options| {
+                    options
+                        .iter()
+                        .filter_map(|option| match resolve(self.doc, option)? {
+                            // `[export display]` pairs
+                            Object::Array(pair) => resolve(self.doc, pair.first()?),
+                            other => Some(other),
+                        })
+                        .filter_map(text_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            FieldKind::Checkbox | FieldKind::Radio => {
+                let mut states: Vec<String> = Vec::new();
+                for state in widgets
+                    .iter()
+                    .flat_map(|(_, widget)| on_states(self.doc, widget))
+                {
+                    if !states.contains(&state) {
+                        states.push(state);
+                    }
+                }
+                states
+            }
+            _ => Vec::new(),
+        };
+
+        Some(FormField {
+            name,
+            kind,
+            value: inherited
+                .value
+                .and_then(|value| form_value(self.doc, value))
+                .filter(|_| kind != FieldKind::Button),
+            options,
+            read_only: inherited.flags & READ_ONLY != 0,
+            widgets: widgets
+                .iter()
+                .filter_map(|(id, widget)| {
+                    Some(FieldWidget {
+                        page: self.widget_page(*id, widget),
+                        rect: numbers(self.doc, dict_get(self.doc, widget, b"Rect"))
+                            .try_into()
+                            .ok()?,
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    fn widget_page(&self, id: ObjectId, widget: &Dictionary) -> Option<u32> {
+        self.annotation_pages.get(&id).copied().or_else(|| {
+            let page = widget.get(b"P").ok()?.as_reference().ok()?;
+            self.page_indices.get(&page).copied()
+        })
+    }
+}
+
+/// Appearance states of a button widget other than `Off`.
+fn on_states(doc: &Document, widget: &Dictionary) -> Vec<String> {
+    dict_get(doc, widget, b"AP")
+        .and_then(|ap| ap.as_dict().ok())
+        .and_then(|ap| dict_get(doc, ap, b"N"))
+        .and_then(|normal| normal.as_dict().ok())
+        .map(|normal| {
+            normal
+                .iter()
+                .map(|(state, _)| String::from_utf8_lossy(state).to_string())
+                .filter(|state| state != "Off")
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_fields(doc: &Document) -> Vec<FieldNode> {
+    let Some(fields) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dict_get(doc, catalog, b"AcroForm"))
+        .and_then(|form| form.as_dict().ok())
+        .and_then(|form| dict_get(doc, form, b"Fields"))
+        .and_then(|fields| fields.as_array().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut reader = FieldReader::new(doc);
+    reader.read(fields, None, Inherited::default(), 0);
+    reader.nodes
+}
+
+/// List the terminal fields of a document's AcroForm.
+pub fn form_fields(doc: &Document) -> Vec<FormField> {
+    read_fields(doc)
+        .into_iter()
+        .map(|node| node.field)
+        .collect()
+}
+
+fn field_error(name: &str, message: &str) -> MonightError {
+    MonightError::FormField {
+        name: name.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Copy an object into the update and set `key` on it.
+pub(crate) fn set_entry(
+    update: &mut IncrementalDocument,
+    id: ObjectId,
+    key: &[u8],
+    value: Object,
+) -> lopdf::Result<()> {
+    update.opt_clone_object_to_new_document(id)?;
+    update
+        .new_document
+        .get_object_mut(id)?
+        .as_dict_mut()?
+        .set(key, value);
+    Ok(())
+}
+
+/// Record new field values in an incremental update.
+fn fill_fields(
+    update: &mut IncrementalDocument,
+    path: &Path,
+    values: &[FieldValue],
+) -> Result<(), MonightError> {
+    let nodes = read_fields(update.get_prev_documents());
+    let mut changes: Vec<(ObjectId, &[u8], Object)> = Vec::new();
+
+    for value in values {
+        let node = nodes
+            .iter()
+            .find(|node| node.field.name == value.name)
+            .ok_or_else(|| field_error(&value.name, "no such field"))?;
+        let field = &node.field;
+        if field.read_only {
+            return Err(field_error(&field.name, "the field is read-only"));
+        }
+
+        let new_value = match (field.kind, &value.value) {
+            (FieldKind::Text | FieldKind::Choice, FormValue::Single(text)) => {
+                lopdf::text_string(text)
+            }
+            (FieldKind::Choice, FormValue::Multiple(items)) => {
+                Object::Array(items.iter().map(|item| lopdf::text_string(item)).collect())
+            }
+            (FieldKind::Checkbox | FieldKind::Radio, FormValue::Single(state)) => {
+                if state != "Off" && !field.options.contains(state) {
+                    return Err(field_error(
+                        &field.name,
+                        &format!("no state named {}", state),
+                    ));
+                }
+                // Each widget shows the chosen state if it has one, otherwise Off
+                for &widget_id in &node.widget_ids {
+                    let has_state = update
+                        .get_prev_documents()
+                        .get_dictionary(widget_id)
+                        .map(|widget| {
+                            on_states(update.get_prev_documents(), widget).contains(state)
+                        })
+                        .unwrap_or(false);
+                    let shown = if has_state { state.as_str() } else { "Off" };
+                    changes.push((widget_id, b"AS", Object::Name(shown.as_bytes().to_vec())));
+                }
+                Object::Name(state.as_bytes().to_vec())
+            }
+            (FieldKind::Signature | FieldKind::Button, _) => {
+                return Err(field_error(&field.name, "the field cannot be filled in"));
+            }
+            (_, FormValue::Multiple(_)) => {
+                return Err(field_error(&field.name, "the field takes a single value"));
+            }
+        };
+        changes.push((node.id, b"V", new_value));
+    }
+
+    for (id, key, value) in changes {
+        set_entry(update, id, key, value).map_err(|e| invalid_pdf(path, e))?;
+    }
+
+    // Ask viewers to redraw text appearances from the new values
+    if !values.is_empty() {
+        let doc = update.get_prev_documents();
+        let root = doc
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .map_err(|e| invalid_pdf(path, e))?;
+        let form = doc
+            .get_dictionary(root)
+            .and_then(|catalog| catalog.get(b"AcroForm"))
+            .map_err(|e| invalid_pdf(path, e))?;
+        match form.as_reference() {
+            Ok(form_id) => set_entry(update, form_id, b"NeedAppearances", Object::Boolean(true)),
+            Err(_) => {
+                let mut form = form.as_dict().map_err(|e| invalid_pdf(path, e))?.clone();
+                form.set("NeedAppearances", true);
+                set_entry(update, root, b"AcroForm", Object::Dictionary(form))
+            }
+        }
+        .map_err(|e| invalid_pdf(path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Fill in fields of `source` and write the result to `destination` as an
+/// incremental update, leaving the original bytes untouched.
+pub fn save_form_as(
+    source: &Path,
+    destination: &Path,
+    values: &[FieldValue],
+) -> Result<(), MonightError> {
+    let bytes = std::fs::read(source).map_err(|e| MonightError::from_io(source, e))?;
+    let doc = parse_document(source, &bytes)?;
+    if doc.is_encrypted() {
+        return Err(invalid_pdf(source, "encrypted forms cannot be saved"));
+    }
+
+    let version = doc.version.clone();
+    let mut update = IncrementalDocument::create_from(bytes, doc);
+    update.new_document.version = version;
+    fill_fields(&mut update, source, values)?;
+
+    let mut out = Vec::new();
+    update
+        .save_to(&mut out)
+        .map_err(|e| invalid_pdf(destination, e))?;
+    write_file(destination, &out)
+}
+
+/// The values `source` would have after applying `updates`, for export.
+fn current_values(doc: &Document, updates: &[FieldValue]) -> (Vec<FieldValue>, HashSet<String>) {
+    let mut values = Vec::new();
+    let mut button_states = HashSet::new();
+    for field in form_fields(doc) {
+        if matches!(field.kind, FieldKind::Signature | FieldKind::Button) {
+            continue;
+        }
+        let value = updates
+            .iter()
+            .find(|update| update.name == field.name)
+            .map(|update| update.value.clone())
+            .or(field.value);
+        if let Some(value) = value {
+            if matches!(field.kind, FieldKind::Checkbox | FieldKind::Radio) {
+                button_states.insert(field.name.clone());
+            }
+            values.push(FieldValue {
+                name: field.name,
+                value,
+            });
+        }
+    }
+    (values, button_states)
+}
+
+/// Export the field values of `source`, with `updates` applied, to an FDF or
+/// XFDF file chosen by the extension of `destination`.
+pub fn export_form_data(
+    source: &Path,
+    destination: &Path,
+    updates: &[FieldValue],
+) -> Result<(), MonightError> {
+    let bytes = std::fs::read(source).map_err(|e| MonightError::from_io(source, e))?;
+    let doc = parse_document(source, &bytes)?;
+    let (values, button_states) = current_values(&doc, updates);
+    let target = target_href(source, destination);
+
+    let out = match DocumentKind::from_extension(destination) {
+        Some(DocumentKind::Fdf) => {
+            fdf::write_fdf(&target, &values, |name| button_states.contains(name))
+        }
+        Some(DocumentKind::Xfdf) => xfdf::write_xfdf(&target, None, &values, &[]).into_bytes(),
+        _ => {
+            return Err(MonightError::UnsupportedType {
+                ext: destination
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("")
+                    .to_string(),
+            })
+        }
+    };
+    write_file(destination, &out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form_data::load_form_data;
+    use crate::test_support::{copy_fixture, temp_dir};
+    use std::path::PathBuf;
+
+    fn fixture() -> PathBuf {
+        crate::test_support::fixture("forms/fields.pdf")
+    }
+
+    fn single(value: &str) -> Option<FormValue> {
+        Some(FormValue::Single(value.to_string()))
+    }
+
+    fn update(name: &str, value: &str) -> FieldValue {
+        FieldValue {
+            name: name.to_string(),
+            value: FormValue::Single(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_lists_fields_with_kinds_values_and_widgets() {
+        let doc = Document::load(fixture()).unwrap();
+        let fields = form_fields(&doc);
+
+        let summary: Vec<(&str, FieldKind, Option<FormValue>, bool)> = fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.kind, f.value.clone(), f.read_only))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("name", FieldKind::Text, single("Ada Lovelace"), false),
+                (
+                    "address.street",
+                    FieldKind::Text,
+                    single("12 Main St № 4"),
+                    false
+                ),
+                ("address.city", FieldKind::Text, None, true),
+                ("plan", FieldKind::Radio, single("basic"), false),
+                ("subscribe", FieldKind::Checkbox, single("Off"), false),
+                (
+                    "colors",
+                    FieldKind::Choice,
+                    Some(FormValue::Multiple(vec!["red".to_string()])),
+                    false
+                ),
+                ("signature", FieldKind::Signature, None, false),
+            ]
+        );
+
+        assert_eq!(fields[3].options, vec!["basic", "premium"]);
+        assert_eq!(fields[4].options, vec!["Yes"]);
+        assert_eq!(fields[5].options, vec!["red", "green", "blu"]);
+
+        assert_eq!(
+            fields[3].widgets,
+            vec![
+                FieldWidget {
+                    page: Some(0),
+                    rect: [72.0, 600.0, 84.0, 612.0],
+                },
+                FieldWidget {
+                    page: Some(0),
+                    rect: [100.0, 600.0, 112.0, 612.0],
+                },
+            ]
+        );
+        assert_eq!(fields[4].widgets[0].page, Some(1));
+        // Found through the widget's /P rather than the page's /Annots
+        assert_eq!(fields[6].widgets[0].page, Some(1));
+    }
+
+    #[test]
+    fn test_save_form_as_appends_an_incremental_update() {
+        let dir = temp_dir("acroform", "save");
+        let destination = dir.join("filled.pdf");
+        let original = std::fs::read(fixture()).unwrap();
+
+        save_form_as(
+            &fixture(),
+            &destination,
+            &[
+                update("name", "Grace Hopper"),
+                update("plan", "premium"),
+                update("subscribe", "Yes"),
+                FieldValue {
+                    name: "colors".to_string(),
+                    value: FormValue::Multiple(vec!["green".to_string(), "blu".to_string()]),
+                },
+            ],
+        )
+        .unwrap();
+
+        let saved = std::fs::read(&destination).unwrap();
+        assert!(saved.starts_with(&original));
+        let doc = Document::load_mem(&saved).unwrap();
+        let fields = form_fields(&doc);
+        assert_eq!(fields[0].value, single("Grace Hopper"));
+        assert_eq!(fields[3].value, single("premium"));
+        assert_eq!(fields[4].value, single("Yes"));
+        assert_eq!(
+            fields[5].value,
+            Some(FormValue::Multiple(vec![
+                "green".to_string(),
+                "blu".to_string()
+            ]))
+        );
+
+        // The radio widgets follow the new state
+        let states: Vec<&[u8]> = [(15, 0), (16, 0)]
+            .iter()
+            .map(|id| {
+                doc.get_dictionary(*id)
+                    .unwrap()
+                    .get(b"AS")
+                    .unwrap()
+                    .as_name()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(states, vec![b"Off".as_slice(), b"premium".as_slice()]);
+        let form = doc.get_dictionary((20, 0)).unwrap();
+        assert_eq!(
+            form.get(b"NeedAppearances").unwrap(),
+            &Object::Boolean(true)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_form_as_rejects_invalid_updates() {
+        let dir = temp_dir("acroform", "reject");
+        let destination = dir.join("filled.pdf");
+        let code = |values: &[FieldValue]| {
+            save_form_as(&fixture(), &destination, values)
+                .unwrap_err()
+                .code()
+        };
+
+        assert_eq!(code(&[update("missing", "x")]), "FORM_FIELD");
+        assert_eq!(code(&[update("address.city", "London")]), "FORM_FIELD");
+        assert_eq!(code(&[update("plan", "gold")]), "FORM_FIELD");
+        assert_eq!(code(&[update("signature", "me")]), "FORM_FIELD");
+        assert!(!destination.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_round_trips_through_fdf_and_xfdf() {
+        let dir = temp_dir("acroform", "export");
+        let source = copy_fixture("forms/fields.pdf", &dir.join("fields.pdf"));
+        let updates = [update("subscribe", "Yes")];
+
+        for (name, kind) in [
+            ("values.fdf", DocumentKind::Fdf),
+            ("values.xfdf", DocumentKind::Xfdf),
+        ] {
+            let destination = dir.join(name);
+            export_form_data(&source, &destination, &updates).unwrap();
+
+            let data = load_form_data(&destination, kind).unwrap();
+            assert_eq!(
+                data.target.map(PathBuf::from),
+                Some(std::fs::canonicalize(&source).unwrap())
+            );
+            let exported: Vec<(&str, &FormValue)> = data
+                .fields
+                .iter()
+                .map(|field| (field.name.as_str(), &field.value))
+                .collect();
+            assert_eq!(exported.len(), 5, "{}", name);
+            assert!(exported.contains(&("subscribe", &FormValue::Single("Yes".to_string()))));
+            assert!(exported.contains(&("plan", &FormValue::Single("basic".to_string()))));
+        }
+
+        let err = export_form_data(&source, &dir.join("values.txt"), &updates).unwrap_err();
+        assert_eq!(err.code(), "UNSUPPORTED_TYPE");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
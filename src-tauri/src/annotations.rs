@@ -0,0 +1,849 @@
+use lopdf::{Dictionary, IncrementalDocument, Object, ObjectId};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::acroform::set_entry;
+use crate::error::MonightError;
+use crate::form_data::{replace_file, target_href, write_file, Annotation};
+use crate::pdf::{invalid_pdf, now_iso, parse_document, text_string, to_pdf_date};
+use crate::reading_position::FingerprintCache;
+use crate::xfdf::{parse_xfdf, write_xfdf, ANNOTATION_ELEMENTS};
+
+/// Folder in the app data directory for sidecars that cannot sit next to their document.
+pub const ANNOTATIONS_DIR: &str = "annotations";
+/// OS error for writes to a read-only file system (`EROFS`, `ERROR_WRITE_PROTECT`).
+#[cfg(not(windows))]
+const READ_ONLY_FILESYSTEM: i32 = 30;
+#[cfg(windows)]
+const READ_ONLY_FILESYSTEM: i32 = 19;
+
+/// Annotations stored for one document.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct DocumentAnnotations {
+    pub annotations: Vec<Annotation>,
+    /// The document changed since the annotations were saved, so they may be misplaced
+    pub stale: bool,
+}
+
+/// SHA-256 of `bytes` as lowercase hex.
+pub(crate) fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Sidecar next to the document: `report.pdf` → `report.pdf.xfdf`.
+pub fn sidecar_path(document: &Path) -> PathBuf {
+    let mut path = document.as_os_str().to_owned();
+    path.push(".xfdf");
+    PathBuf::from(path)
+}
+
+/// Whether a write failed because the folder cannot be written to at all,
+/// as opposed to a problem that writing elsewhere would not solve.
+fn is_unwritable(err: &std::io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::PermissionDenied
+        || err.raw_os_error() == Some(READ_ONLY_FILESYSTEM)
+}
+
+fn annotation_error(message: impl Into<String>) -> MonightError {
+    MonightError::Annotation {
+        message: message.into(),
+    }
+}
+
+/// A name for a new annotation that is unique within this process and unlikely
+/// to clash with one written by another.
+fn new_annotation_name() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!(
+        "monight-{:x}-{:x}",
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Annotations kept in XFDF sidecars, keyed by the document's canonical path
+/// and tagged with a fingerprint of its contents.
+pub struct AnnotationStore {
+    /// Where sidecars go when the document's folder is not writable
+    fallback_dir: PathBuf,
+    /// Writes sidecars next to documents; tests swap it to act out read-only folders
+    replace_adjacent: fn(&Path, &[u8]) -> std::io::Result<()>,
+    fingerprints: FingerprintCache,
+    /// Serializes read-modify-write cycles
+    lock: Mutex<()>,
+}
+
+impl AnnotationStore {
+    pub fn new(fallback_dir: PathBuf) -> Self {
+        AnnotationStore {
+            fallback_dir,
+            replace_adjacent: replace_file,
+            fingerprints: FingerprintCache::default(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn fallback_path(&self, document: &Path) -> PathBuf {
+        let key = content_hash(document.to_string_lossy().as_bytes());
+        self.fallback_dir.join(format!("{}.xfdf", key))
+    }
+
+    /// The sidecar saved last. A folder that turned read-only keeps its old
+    /// sidecar while edits go to app data, so the newer file wins, and app data
+    /// wins a tie because it is only written after the folder refused.
+    fn existing_sidecar(&self, document: &Path) -> Option<PathBuf> {
+        [sidecar_path(document), self.fallback_path(document)]
+            .into_iter()
+            .filter_map(|path| {
+                let modified = std::fs::metadata(&path)
+                    .ok()
+                    .filter(|metadata| metadata.is_file())?
+                    .modified()
+                    .ok();
+                Some((modified, path))
+            })
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, path)| path)
+    }
+
+    fn read(&self, document: &Path, hash: &str) -> Result<DocumentAnnotations, MonightError> {
+        let Some(sidecar) = self.existing_sidecar(document) else {
+            return Ok(DocumentAnnotations::default());
+        };
+        let bytes = std::fs::read(&sidecar).map_err(|e| MonightError::from_io(&sidecar, e))?;
+        let data = parse_xfdf(&sidecar, &bytes)?;
+
+        Ok(DocumentAnnotations {
+            stale: data.document_id.as_deref() != Some(hash),
+            annotations: data.annotations,
+        })
+    }
+
+    fn write(
+        &self,
+        document: &Path,
+        hash: &str,
+        annotations: &[Annotation],
+    ) -> Result<(), MonightError> {
+        // Keep writing where the annotations were last read from
+        let fallback = self.fallback_path(document);
+        let sidecar = sidecar_path(document);
+        if self.existing_sidecar(document).as_ref() != Some(&fallback) {
+            let xml = write_xfdf(
+                &target_href(document, &sidecar),
+                Some(hash),
+                &[],
+                annotations,
+            );
+            match (self.replace_adjacent)(&sidecar, xml.as_bytes()) {
+                Ok(()) => return Ok(()),
+                Err(e) if !is_unwritable(&e) => return Err(MonightError::from_io(&sidecar, e)),
+                Err(_) => {}
+            }
+        }
+
+        // Read-only folder, e.g. a shared drive: keep the sidecar in app data instead
+        std::fs::create_dir_all(&self.fallback_dir)
+            .map_err(|e| MonightError::from_io(&self.fallback_dir, e))?;
+        let xml = write_xfdf(
+            &target_href(document, &fallback),
+            Some(hash),
+            &[],
+            annotations,
+        );
+        write_file(&fallback, xml.as_bytes())
+    }
+
+    /// Identifies the document's current contents without reading all of it.
+    fn document_hash(&self, document: &Path) -> Result<String, MonightError> {
+        Ok(self.fingerprints.fingerprint(document)?.content)
+    }
+
+    /// Annotations saved for `document` (a canonical path).
+    pub fn load(&self, document: &Path) -> Result<DocumentAnnotations, MonightError> {
+        let _guard = self.lock.lock().unwrap();
+        self.read(document, &self.document_hash(document)?)
+    }
+
+    /// Change the annotations of `document` and save them against its current contents.
+    ///
+    /// Saving would mark annotations made on an earlier version as fitting this
+    /// one, so stale annotations must be re-anchored or cleared first.
+    pub fn modify<T>(
+        &self,
+        document: &Path,
+        f: impl FnOnce(&mut Vec<Annotation>) -> Result<T, MonightError>,
+    ) -> Result<T, MonightError> {
+        let _guard = self.lock.lock().unwrap();
+        let hash = self.document_hash(document)?;
+        let stored = self.read(document, &hash)?;
+        if stored.stale && !stored.annotations.is_empty() {
+            return Err(annotation_error(
+                "the document changed since it was annotated; re-anchor or clear its annotations first",
+            ));
+        }
+        let mut annotations = stored.annotations;
+        let result = f(&mut annotations)?;
+        self.write(document, &hash, &annotations)?;
+        Ok(result)
+    }
+
+    /// Accept the stored annotations as placed correctly on the current contents.
+    pub fn reanchor(&self, document: &Path) -> Result<(), MonightError> {
+        let _guard = self.lock.lock().unwrap();
+        let hash = self.document_hash(document)?;
+        let annotations = self.read(document, &hash)?.annotations;
+        self.write(document, &hash, &annotations)
+    }
+
+    /// Forget every annotation stored for `document`.
+    pub fn clear(&self, document: &Path) -> Result<(), MonightError> {
+        let _guard = self.lock.lock().unwrap();
+        let hash = self.document_hash(document)?;
+        self.write(document, &hash, &[])
+    }
+}
+
+fn check_subtype(annotation: &Annotation) -> Result<(), MonightError> {
+    if ANNOTATION_ELEMENTS
+        .iter()
+        .any(|(_, subtype)| *subtype == annotation.subtype)
+    {
+        Ok(())
+    } else {
+        Err(annotation_error(format!(
+            "unsupported annotation type {}",
+            annotation.subtype
+        )))
+    }
+}
+
+/// Store a new annotation, naming and timestamping it.
+pub fn add_annotation(
+    store: &AnnotationStore,
+    document: &Path,
+    mut annotation: Annotation,
+) -> Result<Annotation, MonightError> {
+    check_subtype(&annotation)?;
+    store.modify(document, |annotations| {
+        let taken = |name: &String| annotations.iter().any(|a| a.name.as_ref() == Some(name));
+        if annotation.name.as_ref().map_or(true, taken) {
+            annotation.name = Some(new_annotation_name());
+        }
+        annotation.modified = Some(now_iso());
+        annotations.push(annotation.clone());
+        Ok(annotation)
+    })
+}
+
+/// Replace the annotation with the same name.
+pub fn update_annotation(
+    store: &AnnotationStore,
+    document: &Path,
+    mut annotation: Annotation,
+) -> Result<Annotation, MonightError> {
+    check_subtype(&annotation)?;
+    store.modify(document, |annotations| {
+        let existing = annotations
+            .iter_mut()
+            .find(|existing| existing.name.is_some() && existing.name == annotation.name)
+            .ok_or_else(|| annotation_error("no annotation with that name"))?;
+        annotation.modified = Some(now_iso());
+        *existing = annotation.clone();
+        Ok(annotation)
+    })
+}
+
+/// Remove an annotation by name, returning whether it existed.
+pub fn delete_annotation(
+    store: &AnnotationStore,
+    document: &Path,
+    name: &str,
+) -> Result<bool, MonightError> {
+    store.modify(document, |annotations| {
+        let before = annotations.len();
+        annotations.retain(|annotation| annotation.name.as_deref() != Some(name));
+        Ok(annotations.len() != before)
+    })
+}
+
+/// Subtypes that `embed_annotations` can write, and whether they carry `/QuadPoints`.
+const EMBEDDABLE: &[(&str, bool)] = &[
+    ("Text", false),
+    ("FreeText", false),
+    ("Square", false),
+    ("Circle", false),
+    ("Highlight", true),
+    ("Underline", true),
+    ("Squiggly", true),
+    ("StrikeOut", true),
+    ("Ink", false),
+];
+
+fn reals(values: &[f64]) -> Object {
+    Object::Array(
+        values
+            .iter()
+            .map(|value| Object::Real(*value as f32))
+            .collect(),
+    )
+}
+
+/// A standard `/Annot` dictionary for `annotation` on the page `page_id`.
+///
+/// No appearance stream is written; viewers generate one for these subtypes.
+fn annotation_dictionary(
+    annotation: &Annotation,
+    page_id: ObjectId,
+) -> Result<Dictionary, MonightError> {
+    let Some(&(_, has_quads)) = EMBEDDABLE
+        .iter()
+        .find(|(subtype, _)| *subtype == annotation.subtype)
+    else {
+        return Err(annotation_error(format!(
+            "{} annotations cannot be embedded",
+            annotation.subtype
+        )));
+    };
+
+    let [x1, y1, x2, y2] = annotation.rect;
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::Name(b"Annot".to_vec()));
+    dict.set(
+        "Subtype",
+        Object::Name(annotation.subtype.as_bytes().to_vec()),
+    );
+    dict.set("Rect", reals(&annotation.rect));
+    dict.set("P", Object::Reference(page_id));
+    // Print flag, so the comments show up on paper too
+    dict.set("F", 4);
+    if let Some(name) = &annotation.name {
+        dict.set("NM", lopdf::text_string(name));
+    }
+    if let Some(date) = annotation.modified.as_deref().and_then(to_pdf_date) {
+        dict.set("M", Object::string_literal(date));
+    }
+    if let Some(author) = &annotation.author {
+        dict.set("T", lopdf::text_string(author));
+    }
+    if let Some(contents) = &annotation.contents {
+        dict.set("Contents", lopdf::text_string(contents));
+    }
+    if let Some(color) = &annotation.color {
+        dict.set("C", reals(color));
+    }
+
+    if has_quads {
+        if annotation.quad_points.is_empty() {
+            // A single quadrilateral covering the rectangle
+            dict.set("QuadPoints", reals(&[x1, y2, x2, y2, x1, y1, x2, y1]));
+        } else if annotation.quad_points.len() % 8 == 0 {
+            dict.set("QuadPoints", reals(&annotation.quad_points));
+        } else {
+            return Err(annotation_error(
+                "quad points must come in groups of eight numbers",
+            ));
+        }
+    }
+    match annotation.subtype.as_str() {
+        "Ink" if annotation.ink_list.is_empty() => {
+            return Err(annotation_error("ink annotations need at least one stroke"));
+        }
+        "Ink" => {
+            let strokes = annotation.ink_list.iter().map(|stroke| reals(stroke));
+            dict.set("InkList", Object::Array(strokes.collect()));
+        }
+        "FreeText" => dict.set("DA", Object::string_literal("/Helv 12 Tf 0 g")),
+        _ => {}
+    }
+
+    Ok(dict)
+}
+
+/// Add annotations to one page, replacing any it already has with the same name.
+fn embed_on_page(
+    update: &mut IncrementalDocument,
+    page_id: ObjectId,
+    annotations: Vec<Dictionary>,
+) -> lopdf::Result<()> {
+    let prev = update.get_prev_documents();
+    let page = prev.get_dictionary(page_id)?;
+    let (array_id, mut entries) = match page.get(b"Annots") {
+        Ok(Object::Reference(id)) => (Some(*id), prev.get_object(*id)?.as_array()?.clone()),
+        Ok(Object::Array(items)) => (None, items.clone()),
+        _ => (None, Vec::new()),
+    };
+    let named: Vec<(ObjectId, String)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.as_reference().ok()?;
+            let name = text_string(prev.get_dictionary(id).ok()?.get(b"NM").ok()?)?;
+            Some((id, name))
+        })
+        .collect();
+
+    for dict in annotations {
+        let name = dict.get(b"NM").ok().and_then(text_string);
+        let existing = named
+            .iter()
+            .find(|(_, existing)| Some(existing) == name.as_ref())
+            .map(|(id, _)| *id);
+        match existing {
+            Some(id) => {
+                update
+                    .new_document
+                    .objects
+                    .insert(id, Object::Dictionary(dict));
+            }
+            None => {
+                let id = update.new_document.add_object(dict);
+                entries.push(Object::Reference(id));
+            }
+        }
+    }
+
+    match array_id {
+        Some(id) => {
+            update
+                .new_document
+                .objects
+                .insert(id, Object::Array(entries));
+            Ok(())
+        }
+        None => set_entry(update, page_id, b"Annots", Object::Array(entries)),
+    }
+}
+
+/// Write `annotations` into a copy of `source` at `destination` as an incremental
+/// update. The original bytes are kept as they are, so existing signatures stay valid.
+pub fn embed_annotations(
+    source: &Path,
+    destination: &Path,
+    annotations: &[Annotation],
+) -> Result<(), MonightError> {
+    let bytes = std::fs::read(source).map_err(|e| MonightError::from_io(source, e))?;
+    let doc = parse_document(source, &bytes)?;
+    if doc.is_encrypted() {
+        return Err(invalid_pdf(
+            source,
+            "annotations cannot be embedded in encrypted documents",
+        ));
+    }
+
+    let pages = doc.get_pages();
+    let mut by_page: BTreeMap<ObjectId, Vec<Dictionary>> = BTreeMap::new();
+    for annotation in annotations {
+        let page_id = *pages.get(&(annotation.page + 1)).ok_or_else(|| {
+            annotation_error(format!("page {} does not exist", annotation.page + 1))
+        })?;
+        let dict = annotation_dictionary(annotation, page_id)?;
+        by_page.entry(page_id).or_default().push(dict);
+    }
+
+    let version = doc.version.clone();
+    let mut update = IncrementalDocument::create_from(bytes, doc);
+    update.new_document.version = version;
+    for (page_id, dicts) in by_page {
+        embed_on_page(&mut update, page_id, dicts).map_err(|e| invalid_pdf(source, e))?;
+    }
+
+    let mut out = Vec::new();
+    update
+        .save_to(&mut out)
+        .map_err(|e| invalid_pdf(destination, e))?;
+    write_file(destination, &out)
+}
+
+/// Embed the annotations stored for `source` into a copy at `destination`.
+/// Annotations made on an earlier version of the document may no longer sit
+/// where they belong, so those are only embedded when `force` is set.
+pub fn embed_stored_annotations(
+    store: &AnnotationStore,
+    source: &Path,
+    destination: &Path,
+    force: bool,
+) -> Result<(), MonightError> {
+    let stored = store.load(source)?;
+    if stored.stale && !force {
+        return Err(annotation_error(
+            "the document changed since it was annotated, so the annotations may be misplaced",
+        ));
+    }
+    embed_annotations(source, destination, &stored.annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_kind::DocumentKind;
+    use crate::form_data::load_form_data;
+    use crate::test_support::{copy_fixture, temp_dir};
+
+    fn highlight() -> Annotation {
+        Annotation {
+            subtype: "Highlight".to_string(),
+            page: 0,
+            rect: [72.0, 500.0, 200.0, 515.0],
+            contents: Some("Key point".to_string()),
+            color: Some([1.0, 1.0, 0.0]),
+            quad_points: vec![72.0, 515.0, 200.0, 515.0, 72.0, 500.0, 200.0, 500.0],
+            ..Annotation::default()
+        }
+    }
+
+    #[test]
+    fn test_crud_round_trips_through_the_sidecar() {
+        let dir = temp_dir("annotations", "crud");
+        let document = copy_fixture("sample.pdf", &dir.join("sample.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+
+        let added = add_annotation(&store, &document, highlight()).unwrap();
+        let name = added.name.clone().unwrap();
+        assert!(added.modified.is_some());
+        assert!(sidecar_path(&document).is_file());
+
+        let mut changed = added.clone();
+        changed.contents = Some("Revised".to_string());
+        update_annotation(&store, &document, changed).unwrap();
+
+        let loaded = store.load(&document).unwrap();
+        assert!(!loaded.stale);
+        assert_eq!(loaded.annotations.len(), 1);
+        assert_eq!(loaded.annotations[0].contents.as_deref(), Some("Revised"));
+        assert_eq!(loaded.annotations[0].quad_points, highlight().quad_points);
+
+        assert!(delete_annotation(&store, &document, &name).unwrap());
+        assert!(!delete_annotation(&store, &document, &name).unwrap());
+        assert!(store.load(&document).unwrap().annotations.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sidecar_opens_like_any_xfdf_file() {
+        let dir = temp_dir("annotations", "open");
+        let document = copy_fixture("sample.pdf", &dir.join("sample.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+        add_annotation(&store, &document, highlight()).unwrap();
+
+        let data = load_form_data(&sidecar_path(&document), DocumentKind::Xfdf).unwrap();
+
+        assert_eq!(
+            data.target.as_deref(),
+            Some(document.to_string_lossy().as_ref())
+        );
+        assert_eq!(data.annotations.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_changed_document_marks_annotations_stale() {
+        let dir = temp_dir("annotations", "stale");
+        let document = copy_fixture("sample.pdf", &dir.join("sample.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+        add_annotation(&store, &document, highlight()).unwrap();
+
+        let mut bytes = std::fs::read(&document).unwrap();
+        bytes.extend_from_slice(b"\n% edited\n");
+        std::fs::write(&document, bytes).unwrap();
+
+        let loaded = store.load(&document).unwrap();
+        assert!(loaded.stale);
+        assert_eq!(loaded.annotations.len(), 1);
+
+        // Editing must not pass the old annotations off as fitting the new contents
+        let err = add_annotation(&store, &document, highlight()).unwrap_err();
+        assert_eq!(err.code(), "ANNOTATION");
+        let loaded = store.load(&document).unwrap();
+        assert!(loaded.stale);
+        assert_eq!(loaded.annotations.len(), 1);
+
+        store.reanchor(&document).unwrap();
+        assert!(!store.load(&document).unwrap().stale);
+        add_annotation(&store, &document, highlight()).unwrap();
+        assert_eq!(store.load(&document).unwrap().annotations.len(), 2);
+
+        std::fs::write(&document, b"%PDF-1.4\n% replaced\n").unwrap();
+        store.clear(&document).unwrap();
+        let loaded = store.load(&document).unwrap();
+        assert!(!loaded.stale);
+        assert!(loaded.annotations.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_falls_back_to_app_data_only_when_the_folder_is_not_writable() {
+        use std::io::{Error, ErrorKind};
+        assert!(is_unwritable(&Error::from(ErrorKind::PermissionDenied)));
+        assert!(is_unwritable(&Error::from_raw_os_error(
+            READ_ONLY_FILESYSTEM
+        )));
+        assert!(!is_unwritable(&Error::from(ErrorKind::NotFound)));
+
+        let dir = temp_dir("annotations", "fallback");
+        let document = copy_fixture("sample.pdf", &dir.join("sample.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+        // A directory in the sidecar's place is not something app data would fix
+        std::fs::create_dir_all(sidecar_path(&document).with_extension("xfdf.tmp")).unwrap();
+
+        assert!(add_annotation(&store, &document, highlight()).is_err());
+        assert!(!sidecar_path(&document).exists());
+        assert!(!store.fallback_path(&document).exists());
+        std::fs::remove_dir(sidecar_path(&document).with_extension("xfdf.tmp")).unwrap();
+
+        // The folder turns read-only after a sidecar was saved next to the document
+        let first = add_annotation(&store, &document, highlight()).unwrap();
+        let mut store = store;
+        store.replace_adjacent = |_, _| Err(Error::from(ErrorKind::PermissionDenied));
+        let second = add_annotation(&store, &document, highlight()).unwrap();
+        assert!(store.fallback_path(&document).is_file());
+
+        let names = |store: &AnnotationStore| -> Vec<Option<String>> {
+            let loaded = store.load(&document).unwrap();
+            loaded.annotations.into_iter().map(|a| a.name).collect()
+        };
+        assert_eq!(names(&store), vec![first.name.clone(), second.name.clone()]);
+
+        // Later edits stay in app data even once the folder is writable again
+        store.replace_adjacent = replace_file;
+        assert!(delete_annotation(&store, &document, first.name.as_deref().unwrap()).unwrap());
+        assert_eq!(names(&store), vec![second.name]);
+        let adjacent = std::fs::read(sidecar_path(&document)).unwrap();
+        assert_eq!(
+            parse_xfdf(&document, &adjacent).unwrap().annotations.len(),
+            1
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unknown_types_and_names() {
+        let dir = temp_dir("annotations", "reject");
+        let document = copy_fixture("sample.pdf", &dir.join("sample.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+
+        let link = Annotation {
+            subtype: "Link".to_string(),
+            ..highlight()
+        };
+        assert_eq!(
+            add_annotation(&store, &document, link).unwrap_err().code(),
+            "ANNOTATION"
+        );
+        let unknown = Annotation {
+            name: Some("missing".to_string()),
+            ..highlight()
+        };
+        assert_eq!(
+            update_annotation(&store, &document, unknown)
+                .unwrap_err()
+                .code(),
+            "ANNOTATION"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// The dictionaries of the annotations on a page, skipping form widgets.
+    fn page_annotations(doc: &lopdf::Document, page: u32) -> Vec<Dictionary> {
+        let page_id = doc.get_pages()[&page];
+        doc.get_page_annotations(page_id)
+            .unwrap()
+            .into_iter()
+            .filter(|annot| annot.get(b"Subtype").unwrap().as_name().unwrap() != b"Widget")
+            .cloned()
+            .collect()
+    }
+
+    fn numbers_of(dict: &Dictionary, key: &[u8]) -> Vec<f64> {
+        crate::pdf::numbers(&lopdf::Document::new(), dict.get(key).ok())
+    }
+
+    #[test]
+    fn test_embed_appends_standard_annotation_dictionaries() {
+        let dir = temp_dir("annotations", "embed");
+        let source = copy_fixture("forms/fields.pdf", &dir.join("fields.pdf"));
+        let destination = dir.join("reviewed.pdf");
+        let original = std::fs::read(&source).unwrap();
+        let annotations = vec![
+            Annotation {
+                name: Some("hl-1".to_string()),
+                author: Some("Zo\u{eb}".to_string()),
+                modified: Some("2024-03-15T09:30:00Z".to_string()),
+                ..highlight()
+            },
+            Annotation {
+                subtype: "Text".to_string(),
+                page: 1,
+                rect: [10.0, 10.0, 30.0, 30.0],
+                contents: Some("See page one".to_string()),
+                ..Annotation::default()
+            },
+            Annotation {
+                subtype: "FreeText".to_string(),
+                rect: [50.0, 50.0, 150.0, 70.0],
+                contents: Some("Typed note".to_string()),
+                ..Annotation::default()
+            },
+            Annotation {
+                subtype: "Ink".to_string(),
+                rect: [0.0, 0.0, 20.0, 20.0],
+                ink_list: vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0, 6.0]],
+                ..Annotation::default()
+            },
+        ];
+
+        embed_annotations(&source, &destination, &annotations).unwrap();
+
+        // The original bytes, and so any signature's byte range, are untouched
+        let saved = std::fs::read(&destination).unwrap();
+        assert!(saved.starts_with(&original));
+        assert_eq!(std::fs::read(&source).unwrap(), original);
+
+        let doc = lopdf::Document::load_mem(&saved).unwrap();
+        let first = page_annotations(&doc, 1);
+        assert_eq!(first.len(), 3);
+        let subtypes: Vec<&[u8]> = first
+            .iter()
+            .map(|annot| annot.get(b"Subtype").unwrap().as_name().unwrap())
+            .collect();
+        assert_eq!(subtypes, vec![b"Highlight".as_slice(), b"FreeText", b"Ink"]);
+
+        let highlight_dict = &first[0];
+        assert_eq!(
+            highlight_dict.get(b"Type").unwrap().as_name().unwrap(),
+            b"Annot"
+        );
+        assert_eq!(
+            highlight_dict.get(b"P").unwrap().as_reference().unwrap(),
+            doc.get_pages()[&1]
+        );
+        assert_eq!(
+            numbers_of(highlight_dict, b"QuadPoints"),
+            highlight().quad_points
+        );
+        assert_eq!(numbers_of(highlight_dict, b"C"), vec![1.0, 1.0, 0.0]);
+        let text = |key: &[u8]| text_string(highlight_dict.get(key).unwrap()).unwrap();
+        assert_eq!(text(b"Contents"), "Key point");
+        assert_eq!(text(b"T"), "Zo\u{eb}");
+        assert_eq!(text(b"NM"), "hl-1");
+        assert_eq!(text(b"M"), "D:20240315093000Z");
+
+        assert!(first[1].has(b"DA"));
+        let strokes = first[2].get(b"InkList").unwrap().as_array().unwrap();
+        assert_eq!(strokes.len(), 2);
+
+        let second = page_annotations(&doc, 2);
+        assert_eq!(second.len(), 1);
+        assert_eq!(
+            text_string(second[0].get(b"Contents").unwrap()).as_deref(),
+            Some("See page one")
+        );
+        // Form widgets are still there
+        assert_eq!(crate::acroform::form_fields(&doc).len(), 7);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_embedding_again_replaces_annotations_with_the_same_name() {
+        let dir = temp_dir("annotations", "reembed");
+        let source = copy_fixture("forms/fields.pdf", &dir.join("fields.pdf"));
+        let once = dir.join("once.pdf");
+        let twice = dir.join("twice.pdf");
+        let named = Annotation {
+            name: Some("hl-1".to_string()),
+            ..highlight()
+        };
+        embed_annotations(&source, &once, std::slice::from_ref(&named)).unwrap();
+
+        let revised = Annotation {
+            contents: Some("Revised".to_string()),
+            quad_points: Vec::new(),
+            ..named
+        };
+        embed_annotations(&once, &twice, &[revised]).unwrap();
+
+        let doc = lopdf::Document::load(&twice).unwrap();
+        let annotations = page_annotations(&doc, 1);
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(
+            text_string(annotations[0].get(b"Contents").unwrap()).as_deref(),
+            Some("Revised")
+        );
+        // Without quad points the highlight covers its rectangle
+        assert_eq!(
+            numbers_of(&annotations[0], b"QuadPoints"),
+            vec![72.0, 515.0, 200.0, 515.0, 72.0, 500.0, 200.0, 500.0]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_annotations_are_embedded_only_when_forced() {
+        let dir = temp_dir("annotations", "embed-stale");
+        let source = copy_fixture("forms/fields.pdf", &dir.join("fields.pdf"));
+        let destination = dir.join("reviewed.pdf");
+        let store = AnnotationStore::new(dir.join("app-data"));
+        add_annotation(&store, &source, highlight()).unwrap();
+
+        let mut bytes = std::fs::read(&source).unwrap();
+        bytes.extend_from_slice(b"\n% edited\n");
+        std::fs::write(&source, bytes).unwrap();
+
+        let err = embed_stored_annotations(&store, &source, &destination, false).unwrap_err();
+        assert_eq!(err.code(), "ANNOTATION");
+        assert!(!destination.exists());
+
+        embed_stored_annotations(&store, &source, &destination, true).unwrap();
+        let doc = lopdf::Document::load(&destination).unwrap();
+        assert_eq!(page_annotations(&doc, 1).len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_embed_rejects_annotations_it_cannot_write() {
+        let dir = temp_dir("annotations", "embed-reject");
+        let source = copy_fixture("forms/fields.pdf", &dir.join("fields.pdf"));
+        let destination = dir.join("reviewed.pdf");
+        let code = |annotation: Annotation| {
+            embed_annotations(&source, &destination, &[annotation])
+                .unwrap_err()
+                .code()
+        };
+
+        let sound = Annotation {
+            subtype: "Sound".to_string(),
+            ..highlight()
+        };
+        assert_eq!(code(sound), "ANNOTATION");
+        assert_eq!(
+            code(Annotation {
+                page: 9,
+                ..highlight()
+            }),
+            "ANNOTATION"
+        );
+        let bare_ink = Annotation {
+            subtype: "Ink".to_string(),
+            ..Annotation::default()
+        };
+        assert_eq!(code(bare_ink), "ANNOTATION");
+        assert!(!destination.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+use lopdf::encryption::DecryptionError;
+use lopdf::{Dictionary, Document, Stream};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::MonightError;
+use crate::form_data::write_file;
+use crate::pdf::{dict_get, format_pdf_date, invalid_pdf, load_document, resolve, text_string};
+use crate::security::document_security;
+
+/// Deepest `/Kids` nesting followed in the `/EmbeddedFiles` name tree.
+const MAX_TREE_DEPTH: usize = 32;
+/// Name for files whose file specification does not give one.
+const UNNAMED: &str = "attachment";
+
+/// A file embedded in a PDF, from the `/EmbeddedFiles` name tree or a
+/// FileAttachment annotation.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub name: String,
+    pub description: Option<String>,
+    /// Uncompressed size in bytes
+    pub size: Option<u64>,
+    pub mime_type: Option<String>,
+    pub modified: Option<String>,
+    /// One-based page of the annotation carrying the file; `None` for files
+    /// attached to the document itself
+    pub page: Option<u32>,
+}
+
+/// An attachment and the stream holding its contents.
+struct Embedded<'a> {
+    attachment: Attachment,
+    stream: &'a Stream,
+}
+
+/// Collect the file specifications of a name tree node and its `/Kids`.
+fn collect_names<'a>(
+    doc: &'a Document,
+    node: &'a Dictionary,
+    depth: usize,
+    out: &mut Vec<(String, &'a Dictionary)>,
+) {
+    if depth > MAX_TREE_DEPTH {
+        return;
+    }
+
+    if let Some(names) = dict_get(doc, node, b"Names").and_then(|n| n.as_array().ok()) {
+        for pair in names.chunks(2) {
+            if let [key, value] = pair {
+                let key = resolve(doc, key).and_then(text_string);
+                let spec = resolve(doc, value).and_then(|value| value.as_dict().ok());
+                if let (Some(key), Some(spec)) = (key, spec) {
+                    out.push((key, spec));
+                }
+            }
+        }
+    }
+
+    if let Some(kids) = dict_get(doc, node, b"Kids").and_then(|k| k.as_array().ok()) {
+        for kid in kids {
+            if let Some(kid) = resolve(doc, kid).and_then(|kid| kid.as_dict().ok()) {
+                collect_names(doc, kid, depth + 1, out);
+            }
+        }
+    }
+}
+
+/// The last component of a path-like name, as some producers include folders.
+fn final_component(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or_default()
+}
+
+/// The file name of a file specification, without the folders some producers include.
+fn spec_file_name(doc: &Document, spec: &Dictionary) -> Option<String> {
+    [b"UF".as_slice(), b"F"]
+        .into_iter()
+        .filter_map(|key| dict_get(doc, spec, key).and_then(text_string))
+        .map(|name| final_component(&name).to_string())
+        .find(|name| !name.is_empty())
+}
+
+/// Read the embedded file of a file specification.
+fn embedded<'a>(
+    doc: &'a Document,
+    spec: &'a Dictionary,
+    fallback_name: Option<String>,
+    fallback_description: Option<String>,
+    page: Option<u32>,
+) -> Option<Embedded<'a>> {
+    let files = dict_get(doc, spec, b"EF")?.as_dict().ok()?;
+    let stream = [b"UF".as_slice(), b"F"]
+        .into_iter()
+        .find_map(|key| dict_get(doc, files, key).and_then(|file| file.as_stream().ok()))?;
+    let params = dict_get(doc, &stream.dict, b"Params").and_then(|params| params.as_dict().ok());
+    let param = |key: &[u8]| params.and_then(|params| dict_get(doc, params, key));
+
+    let name = spec_file_name(doc, spec)
+        .or_else(|| fallback_name.map(|name| final_component(&name).to_string()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| UNNAMED.to_string());
+    let description = dict_get(doc, spec, b"Desc")
+        .and_then(text_string)
+        .or(fallback_description)
+        .filter(|description| !description.is_empty());
+    let size = param(b"Size")
+        .and_then(|size| size.as_i64().ok())
+        .and_then(|size| u64::try_from(size).ok())
+        .or_else(|| contents(stream).ok().map(|bytes| bytes.len() as u64));
+    let mime_type = dict_get(doc, &stream.dict, b"Subtype")
+        .and_then(|subtype| subtype.as_name().ok())
+        .map(|subtype| String::from_utf8_lossy(subtype).to_string());
+    let modified = param(b"ModDate")
+        .and_then(text_string)
+        .map(|raw| format_pdf_date(&raw).unwrap_or(raw));
+
+    Some(Embedded {
+        attachment: Attachment {
+            name,
+            description,
+            size,
+            mime_type,
+            modified,
+            page,
+        },
+        stream,
+    })
+}
+
+/// Attachments of the document, then those of FileAttachment annotations in page order.
+fn embedded_files(doc: &Document) -> Vec<Embedded<'_>> {
+    let mut names = Vec::new();
+    let tree = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dict_get(doc, catalog, b"Names"))
+        .and_then(|names| names.as_dict().ok())
+        .and_then(|names| dict_get(doc, names, b"EmbeddedFiles"))
+        .and_then(|tree| tree.as_dict().ok());
+    if let Some(tree) = tree {
+        collect_names(doc, tree, 0, &mut names);
+    }
+
+    let mut files: Vec<Embedded> = names
+        .into_iter()
+        .filter_map(|(key, spec)| embedded(doc, spec, Some(key), None, None))
+        .collect();
+
+    for (number, id) in doc.get_pages() {
+        let annots = doc
+            .get_dictionary(id)
+            .ok()
+            .and_then(|page| dict_get(doc, page, b"Annots"))
+            .and_then(|annots| annots.as_array().ok());
+        for annot in annots.into_iter().flatten() {
+            let Some(annot) = resolve(doc, annot).and_then(|annot| annot.as_dict().ok()) else {
+                continue;
+            };
+            let is_attachment = dict_get(doc, annot, b"Subtype")
+                .and_then(|subtype| subtype.as_name().ok())
+                == Some(b"FileAttachment");
+            let spec = dict_get(doc, annot, b"FS").and_then(|spec| spec.as_dict().ok());
+            if let (true, Some(spec)) = (is_attachment, spec) {
+                let contents = dict_get(doc, annot, b"Contents").and_then(text_string);
+                files.extend(embedded(doc, spec, None, contents, Some(number)));
+            }
+        }
+    }
+    files
+}
+
+/// Decompressed contents of an embedded file stream.
+fn contents(stream: &Stream) -> Result<Vec<u8>, lopdf::Error> {
+    if stream.dict.has(b"Filter") {
+        stream.decompressed_content()
+    } else {
+        Ok(stream.content.clone())
+    }
+}
+
+/// Load `path`, decrypting it when it opens without a password so that
+/// embedded files can be read. Documents that cannot be decrypted are an
+/// error rather than a source of ciphertext.
+fn load(path: &Path) -> Result<Document, MonightError> {
+    let mut doc = load_document(path)?;
+    if !doc.is_encrypted() {
+        return Ok(doc);
+    }
+
+    match doc.decrypt("") {
+        Ok(()) => Ok(doc),
+        Err(lopdf::Error::Decryption(DecryptionError::IncorrectPassword)) => {
+            Err(MonightError::IncorrectPassword {
+                path: path.to_string_lossy().to_string(),
+            })
+        }
+        Err(_) => Err(MonightError::UnsupportedEncryption {
+            path: path.to_string_lossy().to_string(),
+            handler: document_security(&doc)
+                .map_or_else(|| "unknown".to_string(), |security| security.handler),
+        }),
+    }
+}
+
+/// Files attached to the PDF at `path`.
+pub fn list_attachments(path: &Path) -> Result<Vec<Attachment>, MonightError> {
+    let doc = load(path)?;
+    Ok(embedded_files(&doc)
+        .into_iter()
+        .map(|file| file.attachment)
+        .collect())
+}
+
+/// Write the first attachment called `name` on `page` to `destination`;
+/// a `page` of `None` picks among the files attached to the document itself.
+pub fn save_attachment(
+    path: &Path,
+    name: &str,
+    page: Option<u32>,
+    destination: &Path,
+) -> Result<(), MonightError> {
+    let doc = load(path)?;
+    let files = embedded_files(&doc);
+    let file = files
+        .iter()
+        .find(|file| file.attachment.name == name && file.attachment.page == page)
+        .ok_or_else(|| MonightError::AttachmentNotFound {
+            path: path.to_string_lossy().to_string(),
+            name: name.to_string(),
+        })?;
+    let bytes = contents(file.stream).map_err(|e| invalid_pdf(path, e))?;
+    write_file(destination, &bytes)
+}
+
+/// Whether `name` is a plain file name that stays inside the folder it is
+/// joined to: not absolute, without separators and without a drive prefix.
+fn is_plain_file_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..")
+        && !Path::new(name).is_absolute()
+        && !name.contains(['/', '\\', ':'])
+}
+
+/// A file name in `dir` for `name` that is not taken yet, so attachments
+/// sharing a name do not overwrite each other.
+fn unused_path(dir: &Path, name: &str) -> PathBuf {
+    // Names come from the document; never let one leave `dir`
+    let name = final_component(name);
+    let name = if is_plain_file_name(name) {
+        name
+    } else {
+        UNNAMED
+    };
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered name is free")
+}
+
+/// Write every attachment of the PDF at `path` into `dir` and return where each went.
+pub fn extract_attachments(path: &Path, dir: &Path) -> Result<Vec<PathBuf>, MonightError> {
+    let doc = load(path)?;
+    std::fs::create_dir_all(dir).map_err(|e| MonightError::from_io(dir, e))?;
+
+    embedded_files(&doc)
+        .iter()
+        .map(|file| {
+            let bytes = contents(file.stream).map_err(|e| invalid_pdf(path, e))?;
+            let destination = unused_path(dir, &file.attachment.name);
+            write_file(&destination, &bytes)?;
+            Ok(destination)
+        })
+        .collect()
+}
+
+impl fmt::Display for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = self
+            .size
+            .map_or_else(|| "-".to_string(), |size| size.to_string());
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.name,
+            size,
+            self.mime_type.as_deref().unwrap_or("-"),
+            self.modified.as_deref().unwrap_or("-"),
+            self.description.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use lopdf::{dictionary, Object};
+
+    fn file_spec(doc: &mut Document, name: &str, bytes: &[u8], extra: Dictionary) -> Dictionary {
+        let mut stream = Stream::new(
+            dictionary! {
+                "Type" => "EmbeddedFile",
+                "Subtype" => "text/csv",
+                "Params" => dictionary! {
+                    "ModDate" => Object::string_literal("D:20240315093000+01'00'"),
+                },
+            },
+            bytes.to_vec(),
+        );
+        stream.compress().unwrap();
+        let file = doc.add_object(stream);
+        let mut spec = dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::string_literal(name),
+            "EF" => dictionary! { "F" => file },
+        };
+        spec.extend(&extra);
+        spec
+    }
+
+    /// A one-page PDF with `report.csv` in the name tree (split over two
+    /// kids) and `run.log` attached to the page.
+    fn document_with_attachments(path: &Path) {
+        let mut doc = Document::with_version("1.7");
+        let report = file_spec(
+            &mut doc,
+            "results/report.csv",
+            b"sensor,value\nA,1\n",
+            dictionary! { "Desc" => Object::string_literal("Raw measurements") },
+        );
+        let log = file_spec(
+            &mut doc,
+            "run.log",
+            b"started\nfinished\n",
+            Dictionary::new(),
+        );
+        let duplicate = file_spec(&mut doc, "run.log", b"second run\n", Dictionary::new());
+
+        let first = doc.add_object(dictionary! {
+            "Names" => vec![Object::string_literal("report"), report.into()],
+        });
+        let second = doc.add_object(dictionary! {
+            "Names" => vec![Object::string_literal("duplicate"), duplicate.into()],
+        });
+
+        let pages_id = doc.new_object_id();
+        let annot = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "FileAttachment",
+            "Rect" => vec![0.into(), 0.into(), 20.into(), 20.into()],
+            "Contents" => Object::string_literal("Log of the test run"),
+            "FS" => log,
+        });
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Annots" => vec![annot.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Names" => dictionary! {
+                "EmbeddedFiles" => dictionary! { "Kids" => vec![first.into(), second.into()] },
+            },
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_lists_name_tree_and_annotation_attachments() {
+        let dir = temp_dir("attachments", "list");
+        let path = dir.join("report.pdf");
+        document_with_attachments(&path);
+
+        let attachments = list_attachments(&path).unwrap();
+        assert_eq!(
+            attachments[0],
+            Attachment {
+                name: "report.csv".to_string(),
+                description: Some("Raw measurements".to_string()),
+                size: Some(17),
+                mime_type: Some("text/csv".to_string()),
+                modified: Some("2024-03-15T09:30:00+01:00".to_string()),
+                page: None,
+            }
+        );
+        let names: Vec<(&str, Option<u32>)> = attachments
+            .iter()
+            .map(|attachment| (attachment.name.as_str(), attachment.page))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("report.csv", None),
+                ("run.log", None),
+                ("run.log", Some(1))
+            ]
+        );
+        assert_eq!(
+            attachments[2].description.as_deref(),
+            Some("Log of the test run")
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_saves_decompressed_attachments() {
+        let dir = temp_dir("attachments", "save");
+        let path = dir.join("report.pdf");
+        document_with_attachments(&path);
+
+        let destination = dir.join("saved.csv");
+        save_attachment(&path, "report.csv", None, &destination).unwrap();
+        assert_eq!(std::fs::read(&destination).unwrap(), b"sensor,value\nA,1\n");
+
+        // The page tells apart attachments sharing a name
+        let log = dir.join("saved.log");
+        save_attachment(&path, "run.log", Some(1), &log).unwrap();
+        assert_eq!(std::fs::read(&log).unwrap(), b"started\nfinished\n");
+        save_attachment(&path, "run.log", None, &log).unwrap();
+        assert_eq!(std::fs::read(&log).unwrap(), b"second run\n");
+
+        let err = save_attachment(&path, "missing.txt", None, &destination).unwrap_err();
+        assert!(matches!(err, MonightError::AttachmentNotFound { .. }));
+        let err = save_attachment(&path, "report.csv", Some(1), &destination).unwrap_err();
+        assert!(matches!(err, MonightError::AttachmentNotFound { .. }));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_extraction_keeps_attachments_with_the_same_name() {
+        let dir = temp_dir("attachments", "extract");
+        let path = dir.join("report.pdf");
+        document_with_attachments(&path);
+
+        let written = extract_attachments(&path, &dir.join("out")).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                dir.join("out/report.csv"),
+                dir.join("out/run.log"),
+                dir.join("out/run (2).log"),
+            ]
+        );
+        assert_eq!(std::fs::read(&written[1]).unwrap(), b"second run\n");
+        assert_eq!(std::fs::read(&written[2]).unwrap(), b"started\nfinished\n");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_undecryptable_documents_are_an_error() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/encrypted/rc4.pdf");
+        let err = list_attachments(&path).unwrap_err();
+        assert!(matches!(
+            err,
+            MonightError::IncorrectPassword { .. } | MonightError::UnsupportedEncryption { .. }
+        ));
+    }
+
+    #[test]
+    fn test_hostile_names_stay_inside_the_output_folder() {
+        let dir = temp_dir("attachments", "hostile");
+        let path = dir.join("hostile.pdf");
+
+        // Name tree keys are the only name when the specification has none
+        let mut doc = Document::with_version("1.7");
+        let mut names = Vec::new();
+        for key in ["../../.bashrc", "/home/u/.profile", "C:evil.bat", ".."] {
+            let file = doc.add_object(Stream::new(dictionary! {}, key.as_bytes().to_vec()));
+            let spec = dictionary! { "EF" => dictionary! { "F" => file } };
+            names.push(Object::string_literal(key));
+            names.push(spec.into());
+        }
+        let pages_id = doc.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => Vec::<Object>::new(),
+            "Count" => 0,
+        });
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Names" => dictionary! { "EmbeddedFiles" => dictionary! { "Names" => names } },
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(&path).unwrap();
+
+        let listed: Vec<String> = list_attachments(&path)
+            .unwrap()
+            .into_iter()
+            .map(|attachment| attachment.name)
+            .collect();
+        assert_eq!(listed, vec![".bashrc", ".profile", "C:evil.bat", ".."]);
+
+        let out = dir.join("out");
+        let written = extract_attachments(&path, &out).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                out.join(".bashrc"),
+                out.join(".profile"),
+                out.join("attachment"),
+                out.join("attachment (2)"),
+            ]
+        );
+        assert_eq!(
+            std::fs::read(&written[0]).unwrap(),
+            b"../../.bashrc".to_vec()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
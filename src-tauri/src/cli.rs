@@ -0,0 +1,168 @@
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+use crate::annotations::{AnnotationStore, ANNOTATIONS_DIR};
+use crate::error::MonightError;
+use crate::form_data::write_file;
+use crate::{attachments, info, notes, scan};
+
+/// Bundle identifier from `tauri.conf.json`, which names the app data directory;
+/// `build.rs` reads it from there.
+pub(crate) const APP_IDENTIFIER: &str = env!("MONIGHT_APP_IDENTIFIER");
+
+/// Headless subcommands; these print their result and exit without creating a window.
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Print page count, version, metadata and security details of a PDF
+    Info {
+        /// PDF file to inspect
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Export a PDF's annotations and highlights as Markdown notes
+    ExportNotes {
+        /// PDF file whose annotations to export
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Write the notes to this file instead of standard output
+        #[arg(short, long, value_name = "OUTPUT")]
+        output: Option<PathBuf>,
+    },
+
+    /// List the files attached to a PDF, or extract them
+    Attachments {
+        /// PDF file whose attachments to list
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Write every attachment into this directory instead of listing them
+        #[arg(long, value_name = "DIR")]
+        extract: Option<PathBuf>,
+
+        /// Print machine-readable JSON instead of text
+        #[arg(long, conflicts_with = "extract")]
+        json: bool,
+    },
+
+    /// Report JavaScript, launch actions, attachments and other active content in PDFs
+    Scan {
+        /// PDF files to scan
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn print_info(file: &Path, json: bool) -> Result<(), MonightError> {
+    let info = info::document_info(file)?;
+    if json {
+        let output =
+            serde_json::to_string_pretty(&info).expect("document info is always serializable");
+        println!("{}", output);
+    } else {
+        println!("{}", info);
+    }
+    Ok(())
+}
+
+/// Scan each file and print the reports that could be made. Files that could
+/// not be scanned are reported on standard error and make the exit code 1,
+/// without stopping the rest of a batch.
+fn print_scan(files: &[PathBuf], json: bool) -> i32 {
+    let mut reports = Vec::new();
+    let mut code = 0;
+    for file in files {
+        match scan::scan_file(file) {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                eprintln!("monight: {}", e);
+                code = 1;
+            }
+        }
+    }
+
+    if json {
+        let output =
+            serde_json::to_string_pretty(&reports).expect("risk reports are always serializable");
+        println!("{}", output);
+    } else {
+        let reports: Vec<String> = reports.iter().map(ToString::to_string).collect();
+        if !reports.is_empty() {
+            println!("{}", reports.join("\n\n"));
+        }
+    }
+    code
+}
+
+fn print_attachments(file: &Path, extract: Option<&Path>, json: bool) -> Result<(), MonightError> {
+    if let Some(dir) = extract {
+        for path in attachments::extract_attachments(file, dir)? {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+
+    let attachments = attachments::list_attachments(file)?;
+    if json {
+        let output = serde_json::to_string_pretty(&attachments)
+            .expect("attachments are always serializable");
+        println!("{}", output);
+    } else {
+        for attachment in attachments {
+            println!("{}", attachment);
+        }
+    }
+    Ok(())
+}
+
+/// Where the app keeps its data, resolved the same way Tauri does for the window.
+fn app_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(APP_IDENTIFIER)
+}
+
+fn export_notes(file: &Path, output: Option<&Path>) -> Result<(), MonightError> {
+    // Annotations are keyed by canonical path, as the viewer stores them
+    let file = std::fs::canonicalize(file).map_err(|e| MonightError::from_io(file, e))?;
+    let store = AnnotationStore::new(app_data_dir().join(ANNOTATIONS_DIR));
+    let notes = notes::export_notes(&store, &file)?;
+    match output {
+        Some(output) => write_file(output, notes.as_bytes()),
+        None => {
+            print!("{}", notes);
+            Ok(())
+        }
+    }
+}
+
+/// Run a subcommand and return the process exit code.
+pub fn run_command(command: Command) -> i32 {
+    let result = match command {
+        Command::Info { file, json } => print_info(&file, json),
+        Command::ExportNotes { file, output } => export_notes(&file, output.as_deref()),
+        Command::Attachments {
+            file,
+            extract,
+            json,
+        } => print_attachments(&file, extract.as_deref(), json),
+        Command::Scan { files, json } => return print_scan(&files, json),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("monight: {}", e);
+            1
+        }
+    }
+}
@@ -1,12 +1,41 @@
-use std::path::Path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use tauri::{
     command, AppHandle, Manager, PhysicalPosition, PhysicalSize, State, WebviewUrl,
     WebviewWindowBuilder,
 };
 use tauri_plugin_opener::OpenerExt;
-use url::Url;
 
-use crate::{is_supported_extension, take_cli_payload_inner, CliPayload, PendingCliPayload};
+use crate::acroform::FormField;
+use crate::annotations::{AnnotationStore, DocumentAnnotations};
+use crate::attachments::Attachment;
+use crate::document_kind::DocumentKind;
+use crate::document_links::resolve_document_link;
+use crate::error::MonightError;
+use crate::form_data::{
+    stash_form_data, write_file, Annotation, FieldValue, FormData, PendingFormData,
+};
+use crate::library::{IndexStats, Library, SearchHit};
+use crate::link_policy::{load_policy, LinkDecision, LinkOutcome};
+use crate::outline::{document_outline, OutlineItem};
+use crate::pdf::{check_document_kind, load_document, read_pdf_bytes};
+use crate::protocol::{
+    document_url, register_document_inner, unregister_document_inner, DocumentRegistry,
+};
+use crate::reading_position::{ReadingPosition, ReadingPositionStore};
+use crate::recent::{RecentDocument, RecentStore};
+use crate::scan::{RiskReport, ScanCache};
+use crate::security::{read_security, unlock, DocumentPermissions, DocumentSecurity, Permissions};
+use crate::settings::setting;
+use crate::synctex::{
+    editor_command, synctex_error, SourceLocation, SynctexData, SynctexEditor, SynctexPosition,
+};
+use crate::watcher::DocumentWatcher;
+use crate::xdp::{read_xdp, XdpPdf};
+use crate::{
+    dispatch_open_payload, payload_from_file_paths, payload_from_open_requests,
+    take_cli_payload_inner, CliPayload, PendingCliPayload,
+};
 
 const PDF_VIEW_MIN_WIDTH: f64 = 1000.0;
 const PDF_VIEW_MAX_WIDTH: f64 = 1320.0;
@@ -52,68 +81,163 @@ fn calculate_pdf_window_frame(
     }
 }
 
-/// Read and validate a PDF file, returning raw bytes.
-/// This is the pure, testable core — no Tauri dependencies.
-pub(crate) fn read_pdf_bytes(path: String) -> Result<Vec<u8>, String> {
-    // Validate file exists
-    let file_path = Path::new(&path);
-    if !file_path.exists() {
-        return Err(format!("File not found: {}", path));
-    }
+/// Read a PDF file and return raw bytes via Tauri's binary response mechanism.
+/// The bytes are scanned for active content on the way, so `scan_document`
+/// answers from the cache afterwards.
+#[command]
+pub async fn read_pdf_file(
+    scans: State<'_, ScanCache>,
+    path: String,
+) -> Result<tauri::ipc::Response, MonightError> {
+    let bytes = read_pdf_bytes(path.clone())?;
 
-    // Validate file extension
-    if !is_supported_extension(file_path) {
-        let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-        let ext_label = if ext.is_empty() {
-            "no extension".to_string()
-        } else {
-            format!(".{}", ext)
-        };
-        return Err(format!(
-            "Unsupported file type {}. Only PDF, XDP, FDF, and XFDF files are supported.",
-            ext_label
-        ));
+    let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    if let Err(e) = scans.report(&canonical, Some(&bytes)) {
+        eprintln!("Failed to scan {}: {}", path, e);
     }
 
-    // Read file contents
-    std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))
+    Ok(tauri::ipc::Response::new(bytes))
 }
 
-/// Read a PDF file and return raw bytes via Tauri's binary response mechanism.
+/// Report the JavaScript, launch actions, attachments and other active content in a PDF.
 #[command]
-pub async fn read_pdf_file(path: String) -> Result<tauri::ipc::Response, String> {
-    let bytes = read_pdf_bytes(path)?;
-    Ok(tauri::ipc::Response::new(bytes))
+pub async fn scan_document(
+    scans: State<'_, ScanCache>,
+    path: String,
+) -> Result<RiskReport, MonightError> {
+    let path = validate_open_path_inner(path)?;
+    scans.report(Path::new(&path), None)
 }
 
-pub(crate) fn validate_external_url(raw_url: &str) -> Result<Url, String> {
-    let url = Url::parse(raw_url).map_err(|_| "Invalid external link URL".to_string())?;
+/// Form data and PDF location of an XDP package.
+#[derive(Serialize, Debug)]
+pub struct XdpContents {
+    /// Canonical path of the referenced PDF; `None` when the PDF is embedded
+    pdf_path: Option<String>,
+    /// `<xfa:datasets>` data as nested objects, arrays and strings
+    data: Option<serde_json::Value>,
+}
 
-    match url.scheme() {
-        "http" | "https" => {
-            if url.host_str().is_none() {
-                return Err("External web links must include a host".to_string());
-            }
+/// Unpack an XDP package. The PDF itself is read with `read_pdf_file`.
+#[command]
+pub async fn read_xdp_package(path: String) -> Result<XdpContents, MonightError> {
+    let package = read_xdp(Path::new(&path))?;
+    let pdf_path = match package.pdf {
+        XdpPdf::Embedded(_) => None,
+        XdpPdf::Referenced(path) => Some(path.to_string_lossy().to_string()),
+    };
+    Ok(XdpContents {
+        pdf_path,
+        data: package.data,
+    })
+}
+
+/// A document exposed through the `monight://` scheme for ranged loading.
+#[derive(Serialize, Debug)]
+pub struct DocumentStream {
+    token: String,
+    url: String,
+    /// Size of the document in bytes, which PDF.js needs before its first range request
+    length: u64,
+    /// Active content found in the document; `None` when it could not be scanned
+    report: Option<RiskReport>,
+}
+
+/// Serve a document by token so the viewer can load it with HTTP range requests
+/// instead of copying the whole file over IPC, and report its active content.
+#[command]
+pub async fn open_document_stream(
+    registry: State<'_, DocumentRegistry>,
+    scans: State<'_, ScanCache>,
+    path: String,
+) -> Result<DocumentStream, MonightError> {
+    let canonical = PathBuf::from(validate_open_path_inner(path)?);
+    // XDP packages wrap their PDF, so only plain PDFs can be served as they are
+    if check_document_kind(&canonical)? != DocumentKind::Pdf {
+        return Err(MonightError::ContentMismatch {
+            file: canonical.to_string_lossy().to_string(),
+            expected: DocumentKind::Pdf,
+        });
+    }
+    let length = std::fs::metadata(&canonical)
+        .map_err(|e| MonightError::from_io(&canonical, e))?
+        .len();
+    // The viewer can still render what lopdf cannot parse
+    let report = match scans.report(&canonical, None) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            eprintln!("Failed to scan {}: {}", canonical.display(), e);
+            None
         }
-        "mailto" => {
-            if url.path().trim().is_empty() {
-                return Err("Email links must include an address".to_string());
-            }
+    };
+    let token = register_document_inner(registry.inner(), canonical);
+    let url = document_url(&token);
+    Ok(DocumentStream {
+        token,
+        url,
+        length,
+        report,
+    })
+}
+
+/// Release one `open_document_stream` of a document; it is served until every tab
+/// that opened it has closed
+#[command]
+pub fn close_document_stream(registry: State<DocumentRegistry>, token: String) -> bool {
+    unregister_document_inner(registry.inner(), &token)
+}
+
+/// Open a link from a document as the link policy in the settings allows.
+///
+/// Links the policy wants confirmed come back as `Confirm` with their real
+/// target; call again with `confirmed` once the user agrees.
+#[command]
+pub async fn open_external_url(
+    app: AppHandle,
+    url: String,
+    confirmed: Option<bool>,
+) -> Result<LinkOutcome, MonightError> {
+    let (url, decision) = load_policy(&app).evaluate(&url)?;
+    match decision {
+        LinkDecision::Block => {
+            return Err(MonightError::BlockedLink {
+                url: url.to_string(),
+            })
         }
-        _ => {
-            return Err("Blocked unsupported PDF link scheme".to_string());
+        LinkDecision::Confirm if confirmed != Some(true) => {
+            return Ok(LinkOutcome::Confirm {
+                url: url.to_string(),
+            })
         }
+        _ => {}
     }
 
-    Ok(url)
+    app.opener()
+        .open_url(url.as_str(), None::<&str>)
+        .map_err(|e| MonightError::OpenFailed {
+            message: e.to_string(),
+        })?;
+    Ok(LinkOutcome::Opened)
 }
 
+/// Open the document a cross-document link in `path` points at, in a new tab
+/// at the page or destination the link names. Targets must stay within the
+/// linking document's folder or a library folder.
 #[command]
-pub async fn open_external_url(app: AppHandle, url: String) -> Result<(), String> {
-    let url = validate_external_url(&url)?;
-    app.opener()
-        .open_url(url.as_str(), None::<&str>)
-        .map_err(|e| format!("Failed to open external link: {}", e))
+pub fn open_document_link(
+    app: AppHandle,
+    library: State<Library>,
+    path: String,
+    target: String,
+) -> Result<(), MonightError> {
+    let roots: Vec<PathBuf> =
+        library.with_index(|index| index.folders().iter().map(PathBuf::from).collect());
+    let request = resolve_document_link(Path::new(&path), &target, &roots)?;
+    let file = request.path.to_string_lossy().to_string();
+    let payload =
+        payload_from_open_requests([request], None).ok_or(MonightError::NotFound { path: file })?;
+    dispatch_open_payload(&app, payload);
+    Ok(())
 }
 
 /// Extract filename from full path
@@ -138,17 +262,17 @@ pub fn get_file_directory(path: String) -> String {
 
 /// Open settings window
 #[command]
-pub async fn open_settings(app: AppHandle) -> Result<(), String> {
+pub async fn open_settings(app: AppHandle) -> Result<(), MonightError> {
     // Check if settings window already exists
     if let Some(window) = app.get_webview_window("settings") {
-        window.set_focus().map_err(|e| e.to_string())?;
+        window.set_focus()?;
         return Ok(());
     }
 
     // Get main window to use as parent
     let main_window = app
         .get_webview_window("main")
-        .ok_or("Main window not found")?;
+        .ok_or_else(|| MonightError::window_missing("main"))?;
 
     // Determine the URL based on whether we're in development or production
     #[cfg(debug_assertions)]
@@ -160,20 +284,25 @@ pub async fn open_settings(app: AppHandle) -> Result<(), String> {
     // Create settings window
     WebviewWindowBuilder::new(&app, "settings", url)
         .title("Settings - Monight")
-        .parent(&main_window)
-        .map_err(|e| e.to_string())?
+        .parent(&main_window)?
         .inner_size(700.0, 500.0)
         .resizable(false)
         .center()
-        .build()
-        .map_err(|e| e.to_string())?;
+        .build()?;
 
     Ok(())
 }
 
-/// Enable or disable the Print menu item
+/// Enable or disable the Print menu item. With the `path` of the active
+/// document, printing also stays disabled when the document forbids it.
 #[command]
-pub fn set_print_enabled(app: AppHandle, enabled: bool) {
+pub fn set_print_enabled(
+    app: AppHandle,
+    permissions: State<DocumentPermissions>,
+    enabled: bool,
+    path: Option<String>,
+) {
+    let enabled = enabled && path.map_or(true, |path| permissions.get(Path::new(&path)).print);
     if let Some(menu) = app.menu() {
         if let Some(item) = menu.get("print") {
             if let Some(menu_item) = item.as_menuitem() {
@@ -185,14 +314,17 @@ pub fn set_print_enabled(app: AppHandle, enabled: bool) {
 
 /// Fit the main window for comfortable PDF reading.
 #[command]
-pub fn fit_main_window_for_pdf(app: AppHandle, fill_available_height: bool) -> Result<(), String> {
+pub fn fit_main_window_for_pdf(
+    app: AppHandle,
+    fill_available_height: bool,
+) -> Result<(), MonightError> {
     let window = app
         .get_webview_window("main")
-        .ok_or("Main window not found")?;
-    let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
+        .ok_or_else(|| MonightError::window_missing("main"))?;
+    let scale_factor = window.scale_factor()?;
 
     if !fill_available_height {
-        let size = window.inner_size().map_err(|e| e.to_string())?;
+        let size = window.inner_size()?;
         let width = size
             .width
             .max(scaled_pixels(PDF_VIEW_MIN_WIDTH, scale_factor));
@@ -201,17 +333,15 @@ pub fn fit_main_window_for_pdf(app: AppHandle, fill_available_height: bool) -> R
             .max(scaled_pixels(PDF_VIEW_MIN_HEIGHT, scale_factor));
 
         if width != size.width || height != size.height {
-            window
-                .set_size(PhysicalSize::new(width, height))
-                .map_err(|e| e.to_string())?;
-            window.center().map_err(|e| e.to_string())?;
+            window.set_size(PhysicalSize::new(width, height))?;
+            window.center()?;
         }
 
         return Ok(());
     }
 
-    let Some(monitor) = window.current_monitor().map_err(|e| e.to_string())? else {
-        window.maximize().map_err(|e| e.to_string())?;
+    let Some(monitor) = window.current_monitor()? else {
+        window.maximize()?;
         return Ok(());
     };
 
@@ -224,12 +354,8 @@ pub fn fit_main_window_for_pdf(app: AppHandle, fill_available_height: bool) -> R
         monitor.scale_factor(),
     );
 
-    window
-        .set_size(PhysicalSize::new(frame.width, frame.height))
-        .map_err(|e| e.to_string())?;
-    window
-        .set_position(PhysicalPosition::new(frame.x, frame.y))
-        .map_err(|e| e.to_string())?;
+    window.set_size(PhysicalSize::new(frame.width, frame.height))?;
+    window.set_position(PhysicalPosition::new(frame.x, frame.y))?;
 
     Ok(())
 }
@@ -241,35 +367,464 @@ pub fn take_cli_payload(state: State<PendingCliPayload>) -> Option<CliPayload> {
 }
 
 /// Validate and canonicalize a file path for opening
-#[command]
-pub fn validate_open_path(path: String) -> Result<String, String> {
+pub(crate) fn validate_open_path_inner(path: String) -> Result<String, MonightError> {
     let raw_path = Path::new(&path);
     if !raw_path.exists() {
-        return Err(format!("File not found: {}", path));
+        return Err(MonightError::NotFound { path });
     }
 
     let canonical =
-        std::fs::canonicalize(raw_path).map_err(|e| format!("Invalid path: {} ({})", path, e))?;
-
-    if !is_supported_extension(&canonical) {
-        let ext = canonical.extension().and_then(|e| e.to_str()).unwrap_or("");
-        let ext_label = if ext.is_empty() {
-            "no extension".to_string()
-        } else {
-            format!(".{}", ext)
-        };
-        return Err(format!(
-            "Unsupported file type {}. Only PDF, XDP, FDF, and XFDF files are supported.",
-            ext_label
-        ));
-    }
+        std::fs::canonicalize(raw_path).map_err(|e| MonightError::from_io(raw_path, e))?;
+
+    check_document_kind(&canonical)?;
 
     Ok(canonical.to_string_lossy().to_string())
 }
 
+/// A document that passed `validate_open_path`.
+#[derive(Serialize, Debug)]
+pub struct ValidatedDocument {
+    /// Canonical path of the PDF to load
+    path: String,
+    /// `pdf` documents load through `open_document_stream`, `xdp` packages
+    /// through `read_pdf_file`
+    kind: DocumentKind,
+    /// Encryption of the PDF; when a password is required, check it with
+    /// `unlock_document` before loading
+    security: Option<DocumentSecurity>,
+}
+
+/// What the open document at `path` allows; printing, copying and changing
+/// are only restricted by encrypted documents.
+#[command]
+pub fn document_permissions(permissions: State<DocumentPermissions>, path: String) -> Permissions {
+    permissions.get(Path::new(&path))
+}
+
+/// Drop the permissions kept for a document whose tab was closed.
+#[command]
+pub fn forget_document_permissions(permissions: State<DocumentPermissions>, path: String) {
+    permissions.remove(Path::new(&path));
+}
+
+/// Validate and canonicalize a file path for opening, start watching it for changes
+/// and add it to the recent documents.
+///
+/// FDF and XFDF files resolve to the PDF they fill in; their values wait in
+/// `take_form_data` under the returned path.
+#[command]
+pub fn validate_open_path(
+    app: AppHandle,
+    watcher: State<DocumentWatcher>,
+    form_data: State<PendingFormData>,
+    permissions: State<DocumentPermissions>,
+    path: String,
+) -> Result<ValidatedDocument, MonightError> {
+    let canonical = validate_open_path_inner(path)?;
+    let (document, kind) = match check_document_kind(Path::new(&canonical))? {
+        kind @ (DocumentKind::Fdf | DocumentKind::Xfdf) => (
+            stash_form_data(form_data.inner(), Path::new(&canonical), kind)?,
+            DocumentKind::Pdf,
+        ),
+        kind => (canonical.clone(), kind),
+    };
+
+    // The viewer may still render what lopdf cannot parse, so that is no reason to refuse
+    let security = match (kind == DocumentKind::Pdf).then(|| read_security(Path::new(&document))) {
+        Some(Ok(security)) => security,
+        Some(Err(e)) => {
+            eprintln!("Failed to read the security of {}: {}", document, e);
+            None
+        }
+        None => None,
+    };
+    if let Some(security) = &security {
+        permissions.set(PathBuf::from(&document), security.permissions);
+    }
+
+    // A document that cannot be watched is still perfectly readable
+    if let Err(e) = watcher.watch(Path::new(&document)) {
+        eprintln!("Failed to watch {}: {}", document, e);
+    }
+    // Reopening a data file from the recent list fills the form in again
+    if let Err(e) = crate::recent::change(&app, |documents| documents.record(&canonical)) {
+        eprintln!("Failed to update recent documents: {}", e);
+    }
+
+    Ok(ValidatedDocument {
+        path: document,
+        kind,
+        security,
+    })
+}
+
+/// Check the password of an encrypted PDF before the viewer loads it. The
+/// owner password lifts the document's restrictions on printing and copying.
+#[command]
+pub fn unlock_document(
+    permissions: State<DocumentPermissions>,
+    path: String,
+    password: String,
+) -> Result<Option<DocumentSecurity>, MonightError> {
+    let canonical = validate_open_path_inner(path)?;
+    let security = unlock(Path::new(&canonical), &password)?;
+    if let Some(security) = &security {
+        permissions.set(PathBuf::from(canonical), security.permissions);
+    }
+    Ok(security)
+}
+
+/// Field values and annotations from the FDF or XFDF file that opened `path`
+#[command]
+pub fn take_form_data(form_data: State<PendingFormData>, path: String) -> Option<FormData> {
+    form_data.0.lock().unwrap().remove(&path)
+}
+
+/// Start emitting `document-changed` events for a document
+#[command]
+pub fn watch_document(watcher: State<DocumentWatcher>, path: String) -> Result<(), MonightError> {
+    let canonical = validate_open_path_inner(path)?;
+    watcher
+        .watch(Path::new(&canonical))
+        .map_err(|e| MonightError::Watch {
+            path: canonical,
+            message: e.to_string(),
+        })
+}
+
+/// Stop watching a document, e.g. when its tab is closed
+#[command]
+pub fn unwatch_document(watcher: State<DocumentWatcher>, path: String) -> Result<(), MonightError> {
+    // The file may already be gone, so fall back to the path as given
+    let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    watcher
+        .unwatch(&canonical)
+        .map_err(|e| MonightError::Watch {
+            path,
+            message: e.to_string(),
+        })
+}
+
+/// Read the document outline (bookmarks) as a nested tree
+#[command]
+pub async fn get_document_outline(path: String) -> Result<Vec<OutlineItem>, MonightError> {
+    let doc = load_document(Path::new(&path))?;
+    Ok(document_outline(&doc))
+}
+
+/// Fields of the document's interactive form
+#[command]
+pub async fn get_form_fields(path: String) -> Result<Vec<FormField>, MonightError> {
+    let doc = load_document(Path::new(&path))?;
+    Ok(crate::acroform::form_fields(&doc))
+}
+
+/// Write a copy of the document with the given field values filled in
+#[command]
+pub async fn save_form_as(
+    path: String,
+    destination: String,
+    values: Vec<FieldValue>,
+) -> Result<(), MonightError> {
+    crate::acroform::save_form_as(Path::new(&path), Path::new(&destination), &values)
+}
+
+/// Export the document's field values, with unsaved edits applied, to FDF or XFDF
+#[command]
+pub async fn export_form_data(
+    path: String,
+    destination: String,
+    values: Vec<FieldValue>,
+) -> Result<(), MonightError> {
+    crate::acroform::export_form_data(Path::new(&path), Path::new(&destination), &values)
+}
+
+/// Files attached to the document or to its FileAttachment annotations
+#[command]
+pub async fn list_attachments(path: String) -> Result<Vec<Attachment>, MonightError> {
+    crate::attachments::list_attachments(Path::new(&path))
+}
+
+/// Write the attachment called `name` on `page` (`None` for the document's
+/// own attachments) to `destination`, decompressed
+#[command]
+pub async fn save_attachment(
+    path: String,
+    name: String,
+    page: Option<u32>,
+    destination: String,
+) -> Result<(), MonightError> {
+    crate::attachments::save_attachment(Path::new(&path), &name, page, Path::new(&destination))
+}
+
+/// Annotations stored in the document's XFDF sidecar
+#[command]
+pub fn get_annotations(
+    store: State<AnnotationStore>,
+    path: String,
+) -> Result<DocumentAnnotations, MonightError> {
+    // Sidecars are keyed by the canonical path, however the document was named
+    let path = validate_open_path_inner(path)?;
+    store.load(Path::new(&path))
+}
+
+/// Save a new annotation, returning it with its assigned name
+#[command]
+pub fn add_annotation(
+    store: State<AnnotationStore>,
+    path: String,
+    annotation: Annotation,
+) -> Result<Annotation, MonightError> {
+    let path = validate_open_path_inner(path)?;
+    crate::annotations::add_annotation(store.inner(), Path::new(&path), annotation)
+}
+
+/// Replace a stored annotation, matched by name
+#[command]
+pub fn update_annotation(
+    store: State<AnnotationStore>,
+    path: String,
+    annotation: Annotation,
+) -> Result<Annotation, MonightError> {
+    let path = validate_open_path_inner(path)?;
+    crate::annotations::update_annotation(store.inner(), Path::new(&path), annotation)
+}
+
+/// Remove a stored annotation by name
+#[command]
+pub fn delete_annotation(
+    store: State<AnnotationStore>,
+    path: String,
+    name: String,
+) -> Result<bool, MonightError> {
+    let path = validate_open_path_inner(path)?;
+    crate::annotations::delete_annotation(store.inner(), Path::new(&path), &name)
+}
+
+/// Keep the stored annotations after the document changed, as placed on its current contents
+#[command]
+pub fn reanchor_annotations(
+    store: State<AnnotationStore>,
+    path: String,
+) -> Result<(), MonightError> {
+    let path = validate_open_path_inner(path)?;
+    store.reanchor(Path::new(&path))
+}
+
+/// Remove every stored annotation of the document
+#[command]
+pub fn clear_annotations(store: State<AnnotationStore>, path: String) -> Result<(), MonightError> {
+    let path = validate_open_path_inner(path)?;
+    store.clear(Path::new(&path))
+}
+
+/// Write the document's stored annotations into a copy of it at `destination`.
+/// Annotations made before the document changed need `force`.
+#[command]
+pub fn embed_annotations(
+    store: State<AnnotationStore>,
+    path: String,
+    destination: String,
+    force: Option<bool>,
+) -> Result<(), MonightError> {
+    let path = validate_open_path_inner(path)?;
+    crate::annotations::embed_stored_annotations(
+        store.inner(),
+        Path::new(&path),
+        Path::new(&destination),
+        force.unwrap_or(false),
+    )
+}
+
+/// Write the document's annotations to `destination` as Markdown notes
+#[command]
+pub fn export_notes(
+    store: State<AnnotationStore>,
+    path: String,
+    destination: String,
+) -> Result<(), MonightError> {
+    let path = validate_open_path_inner(path)?;
+    let notes = crate::notes::export_notes(store.inner(), Path::new(&path))?;
+    write_file(Path::new(&destination), notes.as_bytes())
+}
+
+/// Recent documents, pinned entries first
+#[command]
+pub fn get_recent_documents(recent: State<RecentStore>) -> Vec<RecentDocument> {
+    recent.snapshot().entries().to_vec()
+}
+
+/// Pin or unpin a recent document so it stays at the top of the list
+#[command]
+pub fn set_recent_pinned(
+    app: AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<Vec<RecentDocument>, MonightError> {
+    crate::recent::change(&app, |documents| {
+        documents.set_pinned(&path, pinned);
+        documents.entries().to_vec()
+    })
+}
+
+/// Forget all recent documents except pinned ones
+#[command]
+pub fn clear_recent_documents(app: AppHandle) -> Result<(), MonightError> {
+    crate::recent::change(&app, |documents| documents.clear())
+}
+
+/// Where a document was last read, found by its contents so moved files keep their place
+#[command]
+pub fn get_reading_position(
+    positions: State<ReadingPositionStore>,
+    path: String,
+) -> Result<Option<ReadingPosition>, MonightError> {
+    positions.get(Path::new(&path))
+}
+
+/// Remember where a document was left
+#[command]
+pub fn set_reading_position(
+    positions: State<ReadingPositionStore>,
+    path: String,
+    position: ReadingPosition,
+) -> Result<(), MonightError> {
+    positions.set(Path::new(&path), position)
+}
+
+/// List the folders covered by library search
+#[command]
+pub fn list_library_folders(library: State<Library>) -> Vec<String> {
+    library.with_index(|index| index.folders().to_vec())
+}
+
+/// Add a folder to library search; call `update_library_index` to index it
+#[command]
+pub fn add_library_folder(
+    library: State<Library>,
+    path: String,
+) -> Result<Vec<String>, MonightError> {
+    let raw_path = Path::new(&path);
+    let folder = std::fs::canonicalize(raw_path).map_err(|e| MonightError::from_io(raw_path, e))?;
+    if !folder.is_dir() {
+        return Err(MonightError::Io {
+            path,
+            message: "not a folder".to_string(),
+        });
+    }
+
+    library.modify(|index| {
+        index.add_folder(&folder);
+        index.folders().to_vec()
+    })
+}
+
+/// Remove a folder from library search along with its indexed documents
+#[command]
+pub fn remove_library_folder(
+    library: State<Library>,
+    path: String,
+) -> Result<Vec<String>, MonightError> {
+    library.modify(|index| {
+        index.remove_folder(Path::new(&path));
+        index.folders().to_vec()
+    })
+}
+
+/// Rescan the library folders, re-extracting only files that changed
+#[command]
+pub async fn update_library_index(app: AppHandle) -> Result<IndexStats, MonightError> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<Library>().update()).await?
+}
+
+/// Find library pages containing every word of the query
+#[command]
+pub fn search_library(
+    library: State<Library>,
+    query: String,
+    limit: Option<usize>,
+) -> Vec<SearchHit> {
+    library.with_index(|index| index.search(&query, limit))
+}
+
+/// Open a search hit at its page, the same way a CLI launch would
+#[command]
+pub fn open_search_hit(app: AppHandle, file: String, page: u32) -> Result<(), MonightError> {
+    let payload = payload_from_file_paths([PathBuf::from(&file)], Some(page))
+        .ok_or(MonightError::NotFound { path: file })?;
+    dispatch_open_payload(&app, payload);
+    Ok(())
+}
+
+/// Map a TeX source line to its location in a PDF
+#[command]
+pub fn synctex_forward(
+    path: String,
+    source: String,
+    line: u32,
+) -> Result<Option<SynctexPosition>, MonightError> {
+    let data = SynctexData::load_for_pdf(Path::new(&path))?;
+    Ok(data.forward(&source, line))
+}
+
+/// Map a point on a page (PDF points from the top-left) to the TeX source line
+#[command]
+pub fn synctex_inverse(
+    path: String,
+    page: u32,
+    x: f64,
+    y: f64,
+) -> Result<Option<SourceLocation>, MonightError> {
+    let data = SynctexData::load_for_pdf(Path::new(&path))?;
+    Ok(data.inverse(page, x, y))
+}
+
+/// Open the TeX source under a point in the editor from `--synctex-editor`,
+/// or else the one in the settings. The webview cannot choose the command.
+#[command]
+pub fn synctex_edit(
+    app: AppHandle,
+    editor: State<SynctexEditor>,
+    path: String,
+    page: u32,
+    x: f64,
+    y: f64,
+) -> Result<SourceLocation, MonightError> {
+    let path = validate_open_path_inner(path)?;
+    let data = SynctexData::load_for_pdf(Path::new(&path))?;
+    let location = data
+        .inverse(page, x, y)
+        .ok_or_else(|| synctex_error("No TeX source found at this position"))?;
+
+    let template = editor
+        .0
+        .lock()
+        .unwrap()
+        .clone()
+        .or_else(|| {
+            setting(&app, "synctexEditor")
+                .and_then(|value| value.as_str().map(str::to_string))
+                .filter(|template| !template.trim().is_empty())
+        })
+        .ok_or_else(|| synctex_error("No editor command configured (see --synctex-editor)"))?;
+    let mut child = editor_command(&template, &location)?
+        .spawn()
+        .map_err(|e| synctex_error(format!("Failed to start editor: {}", e)))?;
+    // Reap the editor once it exits so it is not left behind as a zombie
+    std::thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            eprintln!("Failed to wait for the editor: {}", e);
+        }
+    });
+
+    Ok(location)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::link_policy::LinkPolicy;
+    use url::Url;
 
     #[test]
     fn test_get_file_name() {
@@ -308,78 +863,178 @@ mod tests {
     }
 
     #[test]
-    fn test_read_pdf_bytes_returns_correct_content() {
-        let fixture =
-            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pdf");
-        let expected = std::fs::read(&fixture).expect("fixture should be readable");
-
-        let result = read_pdf_bytes(fixture.to_string_lossy().to_string());
+    fn test_validate_open_path_sniffs_content() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
 
         assert!(
-            result.is_ok(),
-            "read_pdf_bytes should succeed for a valid PDF"
+            validate_open_path_inner(fixtures.join("untitled").to_string_lossy().to_string())
+                .is_ok()
         );
-        let bytes = result.unwrap();
-        assert_eq!(bytes.len(), expected.len());
-        assert_eq!(bytes, expected);
+        assert!(matches!(
+            validate_open_path_inner(fixtures.join("not_a_pdf.pdf").to_string_lossy().to_string()),
+            Err(MonightError::ContentMismatch { .. })
+        ));
     }
 
-    #[test]
-    fn test_read_pdf_bytes_rejects_unsupported_extension() {
-        let fixture =
-            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/readme.txt");
+    /// URL checks under the default link policy.
+    fn validate_external_url(raw_url: &str) -> Result<Url, MonightError> {
+        LinkPolicy::default().validate(raw_url)
+    }
 
-        let result = read_pdf_bytes(fixture.to_string_lossy().to_string());
+    #[test]
+    fn test_validate_external_url_allows_safe_schemes() {
+        assert!(validate_external_url("https://example.com/report").is_ok());
+        assert!(validate_external_url("http://example.com/report").is_ok());
+        assert!(validate_external_url("mailto:user@example.com").is_ok());
+    }
 
-        assert!(result.is_err(), "read_pdf_bytes should reject .txt files");
-        let err = result.unwrap_err();
-        assert!(
-            err.contains("Unsupported file type"),
-            "error should mention unsupported type, got: {}",
-            err
-        );
-        assert!(
-            err.contains(".txt"),
-            "error should mention the actual extension, got: {}",
-            err
-        );
+    #[test]
+    fn test_validate_external_url_blocks_unsafe_schemes() {
+        for (url, expected) in [
+            ("file:///etc/passwd", "file"),
+            ("javascript:alert(1)", "javascript"),
+            ("data:text/html,hello", "data"),
+        ] {
+            assert!(
+                matches!(
+                    validate_external_url(url),
+                    Err(MonightError::BlockedScheme { ref scheme }) if scheme == expected
+                ),
+                "{} should be blocked",
+                url
+            );
+        }
     }
 
     #[test]
-    fn test_read_pdf_bytes_errors_for_missing_file() {
-        let missing = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
-            .join("tests/fixtures/does_not_exist.pdf");
+    fn test_validate_external_url_requires_web_host() {
+        assert!(matches!(
+            validate_external_url("https://"),
+            Err(MonightError::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            validate_external_url("not a url"),
+            Err(MonightError::InvalidUrl { .. })
+        ));
+    }
 
-        let result = read_pdf_bytes(missing.to_string_lossy().to_string());
+    fn decision(policy: &LinkPolicy, url: &str) -> LinkDecision {
+        policy.evaluate(url).unwrap().1
+    }
 
-        assert!(
-            result.is_err(),
-            "read_pdf_bytes should fail for missing files"
+    #[test]
+    fn test_link_policy_defaults_to_allowing_web_links() {
+        let policy = LinkPolicy::default();
+
+        assert_eq!(
+            decision(&policy, "https://example.com"),
+            LinkDecision::Allow
         );
-        let err = result.unwrap_err();
-        assert!(
-            err.contains("File not found"),
-            "error should mention file not found, got: {}",
-            err
+        assert_eq!(
+            decision(&policy, "mailto:a@example.com"),
+            LinkDecision::Allow
         );
+        assert!(matches!(
+            policy.evaluate("zotero://select/items/ABC"),
+            Err(MonightError::BlockedScheme { ref scheme }) if scheme == "zotero"
+        ));
     }
 
     #[test]
-    fn test_validate_external_url_allows_safe_schemes() {
-        assert!(validate_external_url("https://example.com/report").is_ok());
-        assert!(validate_external_url("http://example.com/report").is_ok());
-        assert!(validate_external_url("mailto:user@example.com").is_ok());
+    fn test_link_policy_strictest_matching_list_wins() {
+        let policy: LinkPolicy = serde_json::from_value(serde_json::json!({
+            "allow": ["*.example.com", "mailto:"],
+            "confirm": ["http:", "*.example.com"],
+            "block": ["ads.example.com", "mailto:*.spam.test"],
+            "fallback": "confirm",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            decision(&policy, "https://Docs.Example.com./a"),
+            LinkDecision::Confirm
+        );
+        assert_eq!(
+            decision(&policy, "https://ads.example.com/x"),
+            LinkDecision::Block
+        );
+        assert_eq!(
+            decision(&policy, "https://other.test"),
+            LinkDecision::Confirm
+        );
+        assert_eq!(
+            decision(&policy, "mailto:me@work.test"),
+            LinkDecision::Allow
+        );
+        assert_eq!(
+            decision(&policy, "mailto:me@work.test,you@mail.spam.test?subject=hi"),
+            LinkDecision::Block
+        );
+        // A suffix is not a subdomain
+        assert_eq!(
+            decision(&policy, "https://notexample.com"),
+            LinkDecision::Confirm
+        );
+
+        let policy = LinkPolicy {
+            allow: vec!["https://intranet.test".to_string()],
+            block: vec!["*".to_string()],
+            ..LinkPolicy::default()
+        };
+        assert_eq!(
+            decision(&policy, "https://intranet.test"),
+            LinkDecision::Block
+        );
     }
 
     #[test]
-    fn test_validate_external_url_blocks_unsafe_schemes() {
-        assert!(validate_external_url("file:///etc/passwd").is_err());
-        assert!(validate_external_url("javascript:alert(1)").is_err());
-        assert!(validate_external_url("data:text/html,hello").is_err());
+    fn test_link_policy_fallback_and_allowlist() {
+        let policy = LinkPolicy {
+            allow: vec!["https://*.example.com".to_string()],
+            fallback: LinkDecision::Block,
+            ..LinkPolicy::default()
+        };
+
+        assert_eq!(
+            decision(&policy, "https://example.com"),
+            LinkDecision::Allow
+        );
+        assert_eq!(decision(&policy, "http://example.com"), LinkDecision::Block);
+        assert_eq!(decision(&policy, "https://evil.test"), LinkDecision::Block);
+
+        // The confirmed URL is the normalized one the user is shown
+        let (url, _) = policy
+            .evaluate("HTTPS://B\u{fc}cher.example.com/a b")
+            .unwrap();
+        assert_eq!(url.as_str(), "https://xn--bcher-kva.example.com/a%20b");
     }
 
     #[test]
-    fn test_validate_external_url_requires_web_host() {
-        assert!(validate_external_url("https://").is_err());
+    fn test_link_policy_extra_schemes() {
+        let policy = LinkPolicy {
+            extra_schemes: vec![
+                "zotero:".to_string(),
+                "Obsidian".to_string(),
+                "file".to_string(),
+            ],
+            confirm: vec!["obsidian:".to_string()],
+            ..LinkPolicy::default()
+        };
+
+        assert_eq!(
+            decision(&policy, "zotero://select/items/ABC"),
+            LinkDecision::Allow
+        );
+        assert_eq!(
+            decision(&policy, "obsidian://open?vault=notes"),
+            LinkDecision::Confirm
+        );
+        // Local files and scripts stay blocked whatever the settings say
+        for url in ["file:///etc/passwd", "javascript:alert(1)"] {
+            assert!(matches!(
+                policy.evaluate(url),
+                Err(MonightError::BlockedScheme { .. })
+            ));
+        }
     }
 }
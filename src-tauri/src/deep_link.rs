@@ -0,0 +1,324 @@
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use crate::open_target::{OpenTarget, ViewMode};
+
+/// URL scheme of links that open documents, e.g. `monight://open?file=…&page=3`.
+/// Besides `file` and `page`, links take `dest`, `search`, `zoom` (percent) and
+/// `viewmode` (`single` or `continuous`).
+pub const DEEP_LINK_SCHEME: &str = "monight";
+/// The only action deep links support so far.
+const OPEN_ACTION: &str = "open";
+
+/// A document someone asked to open, and where to go in it.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct OpenRequest {
+    pub path: PathBuf,
+    /// One-based page number
+    pub page: Option<u32>,
+    /// Named destination
+    pub dest: Option<String>,
+    /// Text to search for
+    pub search: Option<String>,
+    /// Scale factor, 1.0 being actual size
+    pub zoom: Option<f64>,
+    pub view_mode: Option<ViewMode>,
+}
+
+impl OpenRequest {
+    pub fn from_path(path: PathBuf) -> Self {
+        OpenRequest {
+            path,
+            ..OpenRequest::default()
+        }
+    }
+
+    /// Where the request asks to go: a named destination is the most specific,
+    /// then a page, then a search.
+    pub fn target(&self) -> Option<OpenTarget> {
+        self.dest
+            .clone()
+            .map(OpenTarget::Destination)
+            .or(self.page.map(OpenTarget::Page))
+            .or_else(|| self.search.clone().map(OpenTarget::Search))
+    }
+
+    fn has_target(&self) -> bool {
+        self.page.is_some()
+            || self.dest.is_some()
+            || self.search.is_some()
+            || self.zoom.is_some()
+            || self.view_mode.is_some()
+    }
+
+    /// Apply the RFC 8118 parameters of a PDF fragment (`page=5&nameddest=intro`),
+    /// ignoring ones we do not act on such as `view`. `viewmode=continuous` is our own.
+    fn apply_fragment(&mut self, fragment: &str) {
+        for (key, value) in url::form_urlencoded::parse(fragment.as_bytes()) {
+            match key.as_ref() {
+                "page" => self.page = parse_page(&value).or(self.page),
+                "nameddest" => self.dest = non_empty(&value).or(self.dest.take()),
+                // The search string may be quoted to keep its words together
+                "search" => {
+                    self.search = non_empty(value.trim_matches('"')).or(self.search.take());
+                }
+                "zoom" => self.zoom = parse_zoom(&value).or(self.zoom),
+                "viewmode" => self.view_mode = ViewMode::parse(&value).or(self.view_mode),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse_page(value: &str) -> Option<u32> {
+    value.trim().parse().ok().filter(|page| *page > 0)
+}
+
+/// RFC 8118 zoom is a percentage, optionally followed by `,left,top`.
+fn parse_zoom(value: &str) -> Option<f64> {
+    let percent: f64 = value.split(',').next()?.trim().parse().ok()?;
+    (percent.is_finite() && percent > 0.0).then_some(percent / 100.0)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// A plain path that may end in a `#page=…` style fragment or a `:PAGE` suffix.
+/// A path that exists as written keeps any `#` or `:` it contains.
+fn request_from_path(text: &str) -> OpenRequest {
+    let whole = PathBuf::from(text);
+    if !whole.exists() {
+        if let Some((path, fragment)) = text.rsplit_once('#') {
+            let mut request = OpenRequest::from_path(PathBuf::from(path));
+            request.apply_fragment(fragment);
+            if !path.is_empty() && request.has_target() {
+                return request;
+            }
+        }
+        // Only digits count, so drive letters such as `C:` stay part of the path
+        if let Some((path, page)) = text.rsplit_once(':') {
+            let digits = !path.is_empty() && page.bytes().all(|b| b.is_ascii_digit());
+            if let Some(page) = parse_page(page).filter(|_| digits) {
+                return OpenRequest {
+                    page: Some(page),
+                    ..OpenRequest::from_path(PathBuf::from(path))
+                };
+            }
+        }
+    }
+    OpenRequest::from_path(whole)
+}
+
+/// Parse a `file://` URL or a `monight://open` link.
+pub(crate) fn request_from_url(url: &Url) -> Option<OpenRequest> {
+    match url.scheme() {
+        "file" => {
+            let mut request = OpenRequest::from_path(url.to_file_path().ok()?);
+            if let Some(fragment) = url.fragment() {
+                request.apply_fragment(fragment);
+            }
+            Some(request)
+        }
+        DEEP_LINK_SCHEME if url.host_str() == Some(OPEN_ACTION) => {
+            let query = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+
+            let file = query("file")?;
+            let mut request = match Url::parse(&file) {
+                Ok(file_url) if file_url.scheme() == "file" => request_from_url(&file_url)?,
+                _ => request_from_path(&file),
+            };
+            if let Some(page) = query("page").as_deref().and_then(parse_page) {
+                request.page = Some(page);
+            }
+            if let Some(dest) = query("dest").as_deref().and_then(non_empty) {
+                request.dest = Some(dest);
+            }
+            if let Some(search) = query("search").as_deref().and_then(non_empty) {
+                request.search = Some(search);
+            }
+            if let Some(zoom) = query("zoom").as_deref().and_then(parse_zoom) {
+                request.zoom = Some(zoom);
+            }
+            if let Some(view_mode) = query("viewmode").as_deref().and_then(ViewMode::parse) {
+                request.view_mode = Some(view_mode);
+            }
+            Some(request)
+        }
+        _ => None,
+    }
+}
+
+/// Link that opens `document` at a one-based `page`.
+pub(crate) fn open_link(document: &Path, page: u32) -> String {
+    let mut url = Url::parse(&format!("{}://{}", DEEP_LINK_SCHEME, OPEN_ACTION))
+        .expect("deep link base is a valid URL");
+    url.query_pairs_mut()
+        .append_pair("file", &document.to_string_lossy())
+        .append_pair("page", &page.to_string());
+    url.to_string()
+}
+
+/// Parse something handed to us to open: a path, a `file://` URL or a deep link.
+pub(crate) fn parse_open_candidate(candidate: &str) -> Option<OpenRequest> {
+    let trimmed = candidate.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    // Drive letters such as `C:` also parse as URL schemes, so only known ones count
+    if let Ok(url) = Url::parse(trimmed) {
+        if url.scheme() == "file" || url.scheme() == DEEP_LINK_SCHEME {
+            return request_from_url(&url);
+        }
+    }
+
+    Some(request_from_path(trimmed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        path: &str,
+        page: Option<u32>,
+        dest: Option<&str>,
+        search: Option<&str>,
+    ) -> OpenRequest {
+        OpenRequest {
+            path: PathBuf::from(path),
+            page,
+            dest: dest.map(str::to_string),
+            search: search.map(str::to_string),
+            ..OpenRequest::default()
+        }
+    }
+
+    #[test]
+    fn test_deep_links_carry_page_destination_and_search() {
+        assert_eq!(
+            parse_open_candidate("monight://open?file=%2Fdocs%2Fspec.pdf&page=42"),
+            Some(request("/docs/spec.pdf", Some(42), None, None))
+        );
+        assert_eq!(
+            parse_open_candidate(
+                "monight://open?file=/docs/my%20spec.pdf&dest=section.2&search=error+codes"
+            ),
+            Some(request(
+                "/docs/my spec.pdf",
+                None,
+                Some("section.2"),
+                Some("error codes")
+            ))
+        );
+        // The file may itself be a URL with a fragment; explicit parameters win
+        assert_eq!(
+            parse_open_candidate(
+                "monight://open?file=file%3A%2F%2F%2Fdocs%2Fspec.pdf%23page%3D3%26nameddest%3Dintro&page=5"
+            ),
+            Some(request("/docs/spec.pdf", Some(5), Some("intro"), None))
+        );
+
+        assert_eq!(parse_open_candidate("monight://open?page=3"), None);
+        assert_eq!(parse_open_candidate("monight://print?file=/a.pdf"), None);
+        assert_eq!(
+            parse_open_candidate("monight://open?file=/a.pdf&page=0&page=x"),
+            Some(request("/a.pdf", None, None, None))
+        );
+    }
+
+    #[test]
+    fn test_open_link_round_trips() {
+        let document = Path::new("/papers/a&b c.pdf");
+        let link = open_link(document, 3);
+
+        assert_eq!(link, "monight://open?file=%2Fpapers%2Fa%26b+c.pdf&page=3");
+        assert_eq!(
+            parse_open_candidate(&link),
+            Some(request("/papers/a&b c.pdf", Some(3), None, None))
+        );
+    }
+
+    #[test]
+    fn test_rfc_8118_fragments_on_urls_and_paths() {
+        assert_eq!(
+            parse_open_candidate("file:///docs/spec.pdf#page=5&zoom=200&view=Fit"),
+            Some(OpenRequest {
+                zoom: Some(2.0),
+                ..request("/docs/spec.pdf", Some(5), None, None)
+            })
+        );
+        assert_eq!(
+            parse_open_candidate("/docs/spec.pdf#search=%22exit%20code%22"),
+            Some(request("/docs/spec.pdf", None, None, Some("exit code")))
+        );
+        assert_eq!(
+            parse_open_candidate("spec.pdf#nameddest=Chapter6"),
+            Some(request("spec.pdf", None, Some("Chapter6"), None))
+        );
+        // Fragments we do not understand leave the path alone
+        assert_eq!(
+            parse_open_candidate("/docs/notes#1.pdf"),
+            Some(request("/docs/notes#1.pdf", None, None, None))
+        );
+        assert_eq!(parse_open_candidate("  "), None);
+    }
+
+    #[test]
+    fn test_page_suffixes_zoom_and_view_mode() {
+        assert_eq!(
+            parse_open_candidate("a.pdf:12"),
+            Some(request("a.pdf", Some(12), None, None))
+        );
+        assert_eq!(
+            parse_open_candidate(r"C:\docs\b.pdf:3"),
+            Some(request(r"C:\docs\b.pdf", Some(3), None, None))
+        );
+        for unchanged in ["a.pdf:0", "a.pdf:x", "a.pdf:", ":4", "C:"] {
+            assert_eq!(
+                parse_open_candidate(unchanged),
+                Some(request(unchanged, None, None, None))
+            );
+        }
+
+        assert_eq!(
+            parse_open_candidate("file:///docs/spec.pdf#page=2&zoom=150,0,0&viewmode=continuous"),
+            Some(OpenRequest {
+                zoom: Some(1.5),
+                view_mode: Some(ViewMode::Continuous),
+                ..request("/docs/spec.pdf", Some(2), None, None)
+            })
+        );
+        assert_eq!(
+            parse_open_candidate("monight://open?file=/a.pdf&zoom=50&viewmode=Single"),
+            Some(OpenRequest {
+                zoom: Some(0.5),
+                view_mode: Some(ViewMode::Single),
+                ..request("/a.pdf", None, None, None)
+            })
+        );
+        assert_eq!(
+            parse_open_candidate("/a.pdf#zoom=-5"),
+            Some(request("/a.pdf#zoom=-5", None, None, None))
+        );
+    }
+
+    #[test]
+    fn test_existing_paths_keep_their_hash() {
+        let dir = std::env::temp_dir().join(format!("monight-deep-link-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let odd = dir.join("report#page=2.pdf");
+        std::fs::write(&odd, b"%PDF-1.4").unwrap();
+
+        let parsed = parse_open_candidate(&odd.to_string_lossy()).unwrap();
+        assert_eq!(parsed, OpenRequest::from_path(odd));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of leading bytes inspected when sniffing a document.
+const SNIFF_LEN: usize = 4096;
+
+/// Document formats Monight understands, detected from file content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Pdf,
+    Fdf,
+    Xdp,
+    Xfdf,
+}
+
+impl DocumentKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DocumentKind::Pdf => "PDF",
+            DocumentKind::Fdf => "FDF",
+            DocumentKind::Xdp => "XDP",
+            DocumentKind::Xfdf => "XFDF",
+        }
+    }
+
+    pub(crate) fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "pdf" => Some(DocumentKind::Pdf),
+            "fdf" => Some(DocumentKind::Fdf),
+            "xdp" => Some(DocumentKind::Xdp),
+            "xfdf" => Some(DocumentKind::Xfdf),
+            _ => None,
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Name of the first element in an XML prolog, skipping declarations,
+/// processing instructions, comments and doctypes.
+fn xml_root_name(bytes: &[u8]) -> Option<&[u8]> {
+    let mut rest = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+    loop {
+        let start = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+        rest = &rest[start..];
+
+        if rest.starts_with(b"<?") {
+            rest = &rest[find(rest, b"?>")? + 2..];
+        } else if rest.starts_with(b"<!--") {
+            rest = &rest[find(rest, b"-->")? + 3..];
+        } else if rest.starts_with(b"<!") {
+            rest = &rest[find(rest, b">")? + 1..];
+        } else if let Some(tag) = rest.strip_prefix(b"<") {
+            let end = tag
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/')
+                .unwrap_or(tag.len());
+            return Some(&tag[..end]);
+        } else {
+            return None;
+        }
+    }
+}
+
+/// Detect the document kind from the leading bytes of a file.
+pub(crate) fn detect_document_kind_bytes(header: &[u8]) -> Option<DocumentKind> {
+    let header = &header[..header.len().min(SNIFF_LEN)];
+
+    // Readers tolerate junk before the header, so the marker may appear anywhere
+    // in the first kilobyte rather than strictly at offset 0.
+    let binary_window = &header[..header.len().min(1024)];
+    if find(binary_window, b"%PDF-").is_some() {
+        return Some(DocumentKind::Pdf);
+    }
+    if find(binary_window, b"%FDF-").is_some() {
+        return Some(DocumentKind::Fdf);
+    }
+
+    match xml_root_name(header)? {
+        b"xdp:xdp" | b"xdp" => Some(DocumentKind::Xdp),
+        b"xfdf" => Some(DocumentKind::Xfdf),
+        _ => None,
+    }
+}
+
+/// Detect the document kind of a file by reading its header.
+pub(crate) fn detect_document_kind(path: &Path) -> std::io::Result<Option<DocumentKind>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(detect_document_kind_bytes(&header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_pdf_and_fdf_headers() {
+        assert_eq!(
+            detect_document_kind_bytes(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n"),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            detect_document_kind_bytes(b"\r\n\r\n%PDF-1.4"),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            detect_document_kind_bytes(b"%FDF-1.2\n1 0 obj"),
+            Some(DocumentKind::Fdf)
+        );
+    }
+
+    #[test]
+    fn test_detects_xml_roots() {
+        let xdp = br#"<?xml version="1.0" encoding="UTF-8"?>
+<?xfa generator="AdobeDesigner" APIVersion="2.5"?>
+<xdp:xdp xmlns:xdp="http://ns.adobe.com/xdp/">"#;
+        assert_eq!(detect_document_kind_bytes(xdp), Some(DocumentKind::Xdp));
+
+        let xfdf = b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<!-- exported -->\n<xfdf xmlns=\"http://ns.adobe.com/xfdf/\">";
+        assert_eq!(detect_document_kind_bytes(xfdf), Some(DocumentKind::Xfdf));
+    }
+
+    #[test]
+    fn test_rejects_unknown_content() {
+        assert_eq!(detect_document_kind_bytes(b"not a pdf"), None);
+        assert_eq!(detect_document_kind_bytes(b""), None);
+        assert_eq!(
+            detect_document_kind_bytes(b"<?xml version=\"1.0\"?><html></html>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detect_document_kind_reads_file_header() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+        assert_eq!(
+            detect_document_kind(&fixtures.join("sample.pdf")).unwrap(),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            detect_document_kind(&fixtures.join("report.download")).unwrap(),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            detect_document_kind(&fixtures.join("not_a_pdf.pdf")).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use crate::commands::validate_open_path_inner;
+use crate::deep_link::OpenRequest;
+use crate::error::MonightError;
+
+fn blocked(path: &Path, reason: &str) -> MonightError {
+    let path = path.to_string_lossy().to_string();
+    // Surface attempts to launch something other than a document in the logs as well
+    eprintln!("Blocked link to {}: {}", path, reason);
+    MonightError::BlockedFileLink {
+        path,
+        reason: reason.to_string(),
+    }
+}
+
+/// Page or destination from the fragment of a link target. Viewers give a
+/// remote `/D` as a name, as an explicit destination in JSON whose first
+/// element is a zero-based page index, or as `page=`/`nameddest=` parameters.
+fn apply_destination(request: &mut OpenRequest, fragment: &str) {
+    let fragment = fragment.trim();
+    if fragment.is_empty() {
+        return;
+    }
+
+    if fragment.starts_with('[') {
+        request.page = serde_json::from_str::<Vec<serde_json::Value>>(fragment)
+            .ok()
+            .and_then(|dest| dest.first().and_then(serde_json::Value::as_u64))
+            .and_then(|index| u32::try_from(index + 1).ok());
+        return;
+    }
+
+    let mut parameters = false;
+    for (key, value) in url::form_urlencoded::parse(fragment.as_bytes()) {
+        match key.as_ref() {
+            "page" => {
+                parameters = true;
+                request.page = value.trim().parse().ok().filter(|page| *page > 0);
+            }
+            "nameddest" => {
+                parameters = true;
+                request.dest = Some(value.into_owned()).filter(|dest| !dest.is_empty());
+            }
+            _ => {}
+        }
+    }
+    if !parameters {
+        request.dest = Some(fragment.to_string());
+    }
+}
+
+/// Resolve the target of a cross-document link (`/GoToR`, or `/Launch` of a
+/// file) found in `document` to a document that may be opened.
+///
+/// Relative targets are taken from the linking document's folder. The result
+/// must lie in that folder or one of `roots`, and must be a document.
+pub(crate) fn resolve_document_link(
+    document: &Path,
+    target: &str,
+    roots: &[PathBuf],
+) -> Result<OpenRequest, MonightError> {
+    let (file, fragment) = target.split_once('#').unwrap_or((target, ""));
+    let file = match Url::parse(file) {
+        Ok(url) if url.scheme() == "file" => url
+            .to_file_path()
+            .map_err(|_| blocked(Path::new(file), "not a local file"))?,
+        // Single letters are Windows drive letters rather than schemes
+        Ok(url) if url.scheme().len() > 1 => {
+            return Err(blocked(Path::new(file), "not a local file"));
+        }
+        _ => PathBuf::from(file),
+    };
+
+    let base = document.parent().unwrap_or_else(|| Path::new(""));
+    let raw_path = base.join(&file);
+    let canonical =
+        std::fs::canonicalize(&raw_path).map_err(|e| MonightError::from_io(&raw_path, e))?;
+
+    // Canonical paths have no `..` or links left, so a prefix check is enough
+    let inside = std::iter::once(base.to_path_buf())
+        .chain(roots.iter().cloned())
+        .filter_map(|root| std::fs::canonicalize(root).ok())
+        .any(|root| canonical.starts_with(root));
+    if !inside {
+        return Err(blocked(
+            &canonical,
+            "it is outside the folders links may open",
+        ));
+    }
+
+    // Executables and anything else that is not a document stay blocked
+    let path = match validate_open_path_inner(canonical.to_string_lossy().to_string()) {
+        Ok(path) => PathBuf::from(path),
+        Err(MonightError::ContentMismatch { .. } | MonightError::UnsupportedType { .. }) => {
+            return Err(blocked(
+                &canonical,
+                "only documents can be opened from links",
+            ));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut request = OpenRequest::from_path(path);
+    apply_destination(&mut request, fragment);
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{copy_fixture, temp_dir};
+
+    /// `set/main.pdf` linking to `set/chapters/one.pdf`, with `shared/ref.pdf` next to the set.
+    fn document_set(name: &str) -> PathBuf {
+        let dir = temp_dir("document-links", name);
+        for path in ["set/main.pdf", "set/chapters/one.pdf", "shared/ref.pdf"] {
+            copy_fixture("outline.pdf", &dir.join(path));
+        }
+        dir
+    }
+
+    #[test]
+    fn test_relative_links_open_at_their_destination() {
+        let dir = document_set("relative");
+        let main = dir.join("set/main.pdf");
+        let one = dir.join("set/chapters/one.pdf");
+
+        let request = resolve_document_link(&main, "chapters/one.pdf#Intro", &[]).unwrap();
+        assert_eq!(request.path, one);
+        assert_eq!(request.dest.as_deref(), Some("Intro"));
+        assert_eq!(request.page, None);
+
+        let request = resolve_document_link(
+            &main,
+            r#"chapters/./one.pdf#[4,{"name":"XYZ"},null,null,null]"#,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(request.page, Some(5));
+
+        // Only documents below the linking one's folder, unless a root says otherwise
+        let err = resolve_document_link(&one, "../main.pdf#page=2", &[]).unwrap_err();
+        assert!(matches!(err, MonightError::BlockedFileLink { .. }));
+
+        let url = Url::from_file_path(&one).unwrap();
+        let request =
+            resolve_document_link(&main, &format!("{}#nameddest=Intro", url), &[]).unwrap();
+        assert_eq!(request.path, one);
+        assert_eq!(request.dest.as_deref(), Some("Intro"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_links_must_stay_within_an_allowed_root() {
+        let dir = document_set("roots");
+        let main = dir.join("set/main.pdf");
+
+        let err = resolve_document_link(&main, "../shared/ref.pdf", &[]).unwrap_err();
+        assert!(matches!(err, MonightError::BlockedFileLink { .. }));
+
+        // Library folders are allowed roots too
+        let request =
+            resolve_document_link(&main, "../shared/ref.pdf", &[dir.join("shared")]).unwrap();
+        assert_eq!(request.path, dir.join("shared/ref.pdf"));
+
+        let err = resolve_document_link(&main, "missing.pdf", &[]).unwrap_err();
+        assert!(matches!(err, MonightError::NotFound { .. }));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_launching_programs_is_blocked() {
+        let dir = document_set("launch");
+        let main = dir.join("set/main.pdf");
+        std::fs::write(dir.join("set/setup.exe"), b"MZ\x90\x00").unwrap();
+        std::fs::write(dir.join("set/run.sh"), b"#!/bin/sh\n").unwrap();
+
+        for target in ["setup.exe", "run.sh", "https://example.com/a.pdf", "cmd:/c"] {
+            let err = resolve_document_link(&main, target, &[]).unwrap_err();
+            assert!(
+                matches!(err, MonightError::BlockedFileLink { .. }),
+                "{} should be blocked",
+                target
+            );
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::path::Path;
+
+use crate::document_kind::DocumentKind;
+
+/// Error returned by every Tauri command.
+///
+/// Serialized as `{ code, message }` so the frontend can branch on a stable
+/// `code` instead of matching English text.
+#[derive(Debug, thiserror::Error)]
+pub enum MonightError {
+    #[error("File not found: {path}")]
+    NotFound { path: String },
+
+    #[error("Unsupported file type {}. Only PDF, XDP, FDF, and XFDF files are supported.", ext_label(.ext))]
+    UnsupportedType { ext: String },
+
+    #[error("File content does not match its extension: {file} is not a valid {} document.", .expected.label())]
+    ContentMismatch {
+        file: String,
+        expected: DocumentKind,
+    },
+
+    #[error("Permission denied: {path}")]
+    PermissionDenied { path: String },
+
+    #[error("Failed to read {path}: {message}")]
+    Io { path: String, message: String },
+
+    #[error("Failed to parse {path}: {message}")]
+    InvalidPdf { path: String, message: String },
+
+    #[error("Incorrect password for {path}")]
+    IncorrectPassword { path: String },
+
+    #[error("{path} is encrypted with the {handler} security handler, which is not supported")]
+    UnsupportedEncryption { path: String, handler: String },
+
+    #[error("{path} has no attachment named {name}")]
+    AttachmentNotFound { path: String, name: String },
+
+    #[error("Failed to read form data from {path}: {message}")]
+    InvalidFormData { path: String, message: String },
+
+    #[error("Form field {name}: {message}")]
+    FormField { name: String, message: String },
+
+    #[error("{reason}")]
+    InvalidUrl { reason: String },
+
+    #[error("Blocked unsupported PDF link scheme: {scheme}")]
+    BlockedScheme { scheme: String },
+
+    #[error("Link to {url} is blocked by the link policy")]
+    BlockedLink { url: String },
+
+    #[error("Blocked link to {path}: {reason}")]
+    BlockedFileLink { path: String, reason: String },
+
+    #[error("Failed to open external link: {message}")]
+    OpenFailed { message: String },
+
+    #[error("Failed to watch {path}: {message}")]
+    Watch { path: String, message: String },
+
+    #[error("Annotation: {message}")]
+    Annotation { message: String },
+
+    #[error("SyncTeX: {message}")]
+    Synctex { message: String },
+
+    #[error("Window not found: {label}")]
+    WindowMissing { label: String },
+
+    #[error("{message}")]
+    Tauri { message: String },
+}
+
+fn ext_label(ext: &str) -> String {
+    if ext.is_empty() {
+        "no extension".to_string()
+    } else {
+        format!(".{}", ext)
+    }
+}
+
+impl MonightError {
+    /// Stable machine-readable identifier for the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MonightError::NotFound { .. } => "NOT_FOUND",
+            MonightError::UnsupportedType { .. } => "UNSUPPORTED_TYPE",
+            MonightError::ContentMismatch { .. } => "CONTENT_MISMATCH",
+            MonightError::PermissionDenied { .. } => "PERMISSION_DENIED",
+            MonightError::Io { .. } => "IO",
+            MonightError::InvalidPdf { .. } => "INVALID_PDF",
+            MonightError::IncorrectPassword { .. } => "INCORRECT_PASSWORD",
+            MonightError::UnsupportedEncryption { .. } => "UNSUPPORTED_ENCRYPTION",
+            MonightError::AttachmentNotFound { .. } => "ATTACHMENT_NOT_FOUND",
+            MonightError::InvalidFormData { .. } => "INVALID_FORM_DATA",
+            MonightError::FormField { .. } => "FORM_FIELD",
+            MonightError::InvalidUrl { .. } => "INVALID_URL",
+            MonightError::BlockedScheme { .. } => "BLOCKED_SCHEME",
+            MonightError::BlockedLink { .. } => "BLOCKED_LINK",
+            MonightError::BlockedFileLink { .. } => "BLOCKED_FILE_LINK",
+            MonightError::OpenFailed { .. } => "OPEN_FAILED",
+            MonightError::Watch { .. } => "WATCH",
+            MonightError::Annotation { .. } => "ANNOTATION",
+            MonightError::Synctex { .. } => "SYNCTEX",
+            MonightError::WindowMissing { .. } => "WINDOW_MISSING",
+            MonightError::Tauri { .. } => "TAURI",
+        }
+    }
+
+    /// Map an I/O error on `path` to the most specific variant.
+    pub fn from_io(path: &Path, err: std::io::Error) -> Self {
+        let path = path.to_string_lossy().to_string();
+        match err.kind() {
+            std::io::ErrorKind::NotFound => MonightError::NotFound { path },
+            std::io::ErrorKind::PermissionDenied => MonightError::PermissionDenied { path },
+            _ => MonightError::Io {
+                path,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    pub fn window_missing(label: &str) -> Self {
+        MonightError::WindowMissing {
+            label: label.to_string(),
+        }
+    }
+}
+
+impl From<tauri::Error> for MonightError {
+    fn from(err: tauri::Error) -> Self {
+        MonightError::Tauri {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl Serialize for MonightError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MonightError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let err = MonightError::UnsupportedType {
+            ext: "txt".to_string(),
+        };
+
+        let value = serde_json::to_value(&err).unwrap();
+
+        assert_eq!(value["code"], "UNSUPPORTED_TYPE");
+        assert_eq!(
+            value["message"],
+            "Unsupported file type .txt. Only PDF, XDP, FDF, and XFDF files are supported."
+        );
+    }
+
+    #[test]
+    fn test_from_io_maps_error_kinds() {
+        let path = Path::new("/tmp/missing.pdf");
+
+        let err = MonightError::from_io(path, std::io::ErrorKind::NotFound.into());
+        assert!(matches!(err, MonightError::NotFound { .. }));
+
+        let err = MonightError::from_io(path, std::io::ErrorKind::PermissionDenied.into());
+        assert!(matches!(err, MonightError::PermissionDenied { .. }));
+
+        let err = MonightError::from_io(path, std::io::ErrorKind::InvalidData.into());
+        assert_eq!(err.code(), "IO");
+    }
+}
@@ -0,0 +1,711 @@
+use lopdf::{Dictionary, Document, Object, StringFormat};
+use std::path::Path;
+
+use crate::error::MonightError;
+use crate::form_data::{
+    field_tree, form_value, invalid_form_data, Annotation, FieldTree, FieldValue, FormValue,
+    ParsedFormData,
+};
+use crate::pdf::{dict_get, format_pdf_date, numbers, resolve, text_string};
+
+/// Deepest field hierarchy or object nesting we follow.
+const MAX_DEPTH: usize = 64;
+
+/// Minimal tokenizer for the PDF object syntax used by FDF files.
+///
+/// FDF files rarely carry a usable cross-reference table, so objects are read
+/// front to back instead of through lopdf's xref-driven loader.
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn is_regular(byte: u8) -> bool {
+    !byte.is_ascii_whitespace() && byte != 0 && !is_delimiter(byte)
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            if byte == b'%' {
+                while !matches!(self.peek(), None | Some(b'\r' | b'\n')) {
+                    self.pos += 1;
+                }
+            } else if byte.is_ascii_whitespace() || byte == 0 {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consume `keyword` if it is the next token.
+    fn keyword(&mut self, keyword: &[u8]) -> bool {
+        self.skip_whitespace();
+        let rest = &self.bytes[self.pos..];
+        let ends_token = rest
+            .get(keyword.len())
+            .map_or(true, |&byte| !is_regular(byte));
+        if rest.starts_with(keyword) && ends_token {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn regular_token(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        &self.bytes[start..self.pos]
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let token = self.regular_token();
+        match std::str::from_utf8(token).ok().and_then(|t| t.parse().ok()) {
+            Some(value) => Some(value),
+            None => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Object, String> {
+        if depth > MAX_DEPTH {
+            return Err("objects nested too deeply".to_string());
+        }
+
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            None => Err("unexpected end of file".to_string()),
+            Some(b'/') => {
+                self.pos += 1;
+                Ok(Object::Name(self.name()))
+            }
+            Some(b'(') => {
+                self.pos += 1;
+                self.literal_string()
+            }
+            Some(b'<') if self.bytes.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let dict = self.dictionary(depth)?;
+                if self.keyword(b"stream") {
+                    // Embedded streams carry nothing we read; skip their data
+                    let end = find(&self.bytes[self.pos..], b"endstream")
+                        .ok_or_else(|| "unterminated stream".to_string())?;
+                    self.pos += end + b"endstream".len();
+                }
+                Ok(Object::Dictionary(dict))
+            }
+            Some(b'<') => {
+                self.pos += 1;
+                self.hex_string()
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(b']') {
+                        self.pos += 1;
+                        return Ok(Object::Array(items));
+                    }
+                    items.push(self.object(depth + 1)?);
+                }
+            }
+            Some(_) => {
+                let token = self.regular_token();
+                match token {
+                    b"true" => return Ok(Object::Boolean(true)),
+                    b"false" => return Ok(Object::Boolean(false)),
+                    b"null" => return Ok(Object::Null),
+                    _ => {}
+                }
+
+                let text = std::str::from_utf8(token).unwrap_or_default();
+                if let Ok(number) = text.parse::<i64>() {
+                    // `N G R` is a reference; anything else leaves the integer alone
+                    let after_number = self.pos;
+                    if let Some(generation) = self.integer() {
+                        if self.keyword(b"R") {
+                            let id = u32::try_from(number).map_err(|e| e.to_string())?;
+                            let generation =
+                                u16::try_from(generation).map_err(|e| e.to_string())?;
+                            return Ok(Object::Reference((id, generation)));
+                        }
+                    }
+                    self.pos = after_number;
+                    return Ok(Object::Integer(number));
+                }
+                if let Ok(number) = text.parse::<f32>() {
+                    return Ok(Object::Real(number));
+                }
+                Err(format!(
+                    "unexpected token {:?} at byte {}",
+                    String::from_utf8_lossy(token),
+                    start
+                ))
+            }
+        }
+    }
+
+    fn name(&mut self) -> Vec<u8> {
+        let token = self.regular_token();
+        let mut name = Vec::with_capacity(token.len());
+        let mut index = 0;
+        while index < token.len() {
+            let escaped = token
+                .get(index + 1..index + 3)
+                .filter(|_| token[index] == b'#')
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match escaped {
+                Some(byte) => {
+                    name.push(byte);
+                    index += 3;
+                }
+                None => {
+                    name.push(token[index]);
+                    index += 1;
+                }
+            }
+        }
+        name
+    }
+
+    fn literal_string(&mut self) -> Result<Object, String> {
+        let mut text = Vec::new();
+        let mut nesting = 0;
+        loop {
+            let byte = self
+                .peek()
+                .ok_or_else(|| "unterminated string".to_string())?;
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    nesting += 1;
+                    text.push(byte);
+                }
+                b')' if nesting == 0 => break,
+                b')' => {
+                    nesting -= 1;
+                    text.push(byte);
+                }
+                b'\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| "unterminated string".to_string())?;
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => text.push(b'\n'),
+                        b'r' => text.push(b'\r'),
+                        b't' => text.push(b'\t'),
+                        b'b' => text.push(0x08),
+                        b'f' => text.push(0x0C),
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(digit - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            text.push(value as u8);
+                        }
+                        // Line continuation
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => text.push(other),
+                    }
+                }
+                _ => text.push(byte),
+            }
+        }
+        Ok(Object::String(text, StringFormat::Literal))
+    }
+
+    fn hex_string(&mut self) -> Result<Object, String> {
+        let end = self.bytes[self.pos..]
+            .iter()
+            .position(|&byte| byte == b'>')
+            .ok_or_else(|| "unterminated hex string".to_string())?;
+        let mut digits: Vec<u8> = self.bytes[self.pos..self.pos + end]
+            .iter()
+            .copied()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        self.pos += end + 1;
+
+        if digits.len() % 2 == 1 {
+            digits.push(b'0');
+        }
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| "invalid hex string".to_string())
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        Ok(Object::String(bytes, StringFormat::Hexadecimal))
+    }
+
+    fn dictionary(&mut self, depth: usize) -> Result<Dictionary, String> {
+        let mut dict = Dictionary::new();
+        loop {
+            self.skip_whitespace();
+            if self.bytes[self.pos..].starts_with(b">>") {
+                self.pos += 2;
+                return Ok(dict);
+            }
+            match self.object(depth + 1)? {
+                Object::Name(key) => {
+                    let value = self.object(depth + 1)?;
+                    dict.set(key, value);
+                }
+                _ => return Err("dictionary key is not a name".to_string()),
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Read every indirect object and the trailer of an FDF file into a document
+/// so the usual lookup helpers work on it.
+fn read_objects(bytes: &[u8]) -> Result<Document, String> {
+    let mut doc = Document::new();
+    let mut lexer = Lexer { bytes, pos: 0 };
+
+    loop {
+        lexer.skip_whitespace();
+        if lexer.peek().is_none() {
+            break;
+        }
+
+        if lexer.keyword(b"trailer") {
+            match lexer.object(0)? {
+                Object::Dictionary(trailer) => doc.trailer = trailer,
+                _ => return Err("trailer is not a dictionary".to_string()),
+            }
+        } else if lexer.keyword(b"xref") {
+            // Offsets are ignored; skip the table up to the trailer
+            let end = find(&bytes[lexer.pos..], b"trailer")
+                .ok_or_else(|| "cross-reference table without trailer".to_string())?;
+            lexer.pos += end;
+        } else if lexer.keyword(b"startxref") {
+            lexer.integer();
+        } else if let Some(number) = lexer.integer() {
+            let generation = lexer
+                .integer()
+                .ok_or_else(|| "expected generation number".to_string())?;
+            if !lexer.keyword(b"obj") {
+                return Err(format!("expected obj after {} {}", number, generation));
+            }
+            let object = lexer.object(0)?;
+            if !lexer.keyword(b"endobj") {
+                return Err(format!(
+                    "object {} {} is missing endobj",
+                    number, generation
+                ));
+            }
+            let id = (
+                u32::try_from(number).map_err(|e| e.to_string())?,
+                u16::try_from(generation).map_err(|e| e.to_string())?,
+            );
+            doc.max_id = doc.max_id.max(id.0);
+            doc.objects.insert(id, object);
+        } else {
+            return Err(format!("unexpected content at byte {}", lexer.pos));
+        }
+    }
+
+    Ok(doc)
+}
+
+/// Text of a file specification: a plain string, or the `/UF` or `/F` entry
+/// of a filespec dictionary.
+fn file_spec(doc: &Document, spec: &Object) -> Option<String> {
+    match spec {
+        Object::Dictionary(dict) => dict_get(doc, dict, b"UF")
+            .or_else(|| dict_get(doc, dict, b"F"))
+            .and_then(text_string),
+        other => text_string(other),
+    }
+}
+
+fn collect_fields(
+    doc: &Document,
+    fields: &[Object],
+    parent: Option<&str>,
+    depth: usize,
+    out: &mut Vec<FieldValue>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    for field in fields {
+        let Some(field) = resolve(doc, field).and_then(|f| f.as_dict().ok()) else {
+            continue;
+        };
+        let partial = dict_get(doc, field, b"T").and_then(text_string);
+        let name = match (parent, partial) {
+            (Some(parent), Some(partial)) => format!("{}.{}", parent, partial),
+            (Some(parent), None) => parent.to_string(),
+            (None, Some(partial)) => partial,
+            (None, None) => continue,
+        };
+
+        if let Some(value) = dict_get(doc, field, b"V").and_then(|v| form_value(doc, v)) {
+            out.push(FieldValue {
+                name: name.clone(),
+                value,
+            });
+        }
+        if let Some(kids) = dict_get(doc, field, b"Kids").and_then(|k| k.as_array().ok()) {
+            collect_fields(doc, kids, Some(&name), depth + 1, out);
+        }
+    }
+}
+
+/// Read an annotation dictionary. Only FDF carries `/Page`; callers reading a PDF set the page.
+pub(crate) fn annotation(doc: &Document, annot: &Dictionary) -> Option<Annotation> {
+    let subtype = dict_get(doc, annot, b"Subtype")?.as_name().ok()?;
+    let rect = numbers(doc, dict_get(doc, annot, b"Rect"));
+    let text = |key: &[u8]| dict_get(doc, annot, key).and_then(text_string);
+
+    Some(Annotation {
+        subtype: String::from_utf8_lossy(subtype).to_string(),
+        page: dict_get(doc, annot, b"Page")
+            .and_then(|page| page.as_i64().ok())
+            .and_then(|page| u32::try_from(page).ok())
+            .unwrap_or(0),
+        rect: rect.try_into().ok()?,
+        contents: text(b"Contents"),
+        author: text(b"T"),
+        color: numbers(doc, dict_get(doc, annot, b"C")).try_into().ok(),
+        name: text(b"NM"),
+        modified: text(b"M").map(|raw| format_pdf_date(&raw).unwrap_or(raw)),
+        quad_points: numbers(doc, dict_get(doc, annot, b"QuadPoints")),
+        ink_list: dict_get(doc, annot, b"InkList")
+            .and_then(|list| list.as_array().ok())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|path| numbers(doc, resolve(doc, path)))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+/// Parse the `/FDF` dictionary of an FDF file.
+pub(crate) fn parse_fdf(path: &Path, bytes: &[u8]) -> Result<ParsedFormData, MonightError> {
+    let doc = read_objects(bytes).map_err(|message| invalid_form_data(path, message))?;
+    let fdf = dict_get(&doc, &doc.trailer, b"Root")
+        .and_then(|root| root.as_dict().ok())
+        .and_then(|root| dict_get(&doc, root, b"FDF"))
+        .and_then(|fdf| fdf.as_dict().ok())
+        .ok_or_else(|| invalid_form_data(path, "missing /FDF dictionary"))?;
+
+    let mut fields = Vec::new();
+    if let Some(list) = dict_get(&doc, fdf, b"Fields").and_then(|f| f.as_array().ok()) {
+        collect_fields(&doc, list, None, 0, &mut fields);
+    }
+
+    let annotations = dict_get(&doc, fdf, b"Annots")
+        .and_then(|annots| annots.as_array().ok())
+        .map(|annots| {
+            annots
+                .iter()
+                .filter_map(|annot| resolve(&doc, annot)?.as_dict().ok())
+                .filter_map(|annot| annotation(&doc, annot))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(ParsedFormData {
+        target: dict_get(&doc, fdf, b"F").and_then(|spec| file_spec(&doc, spec)),
+        document_id: None,
+        fields,
+        annotations,
+    })
+}
+
+fn write_object(out: &mut Vec<u8>, object: &Object) {
+    match object {
+        Object::Null => out.extend_from_slice(b"null"),
+        Object::Boolean(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Integer(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Real(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Name(name) => {
+            out.push(b'/');
+            for &byte in name {
+                if is_regular(byte) && byte != b'#' && byte.is_ascii_graphic() {
+                    out.push(byte);
+                } else {
+                    out.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+                }
+            }
+        }
+        Object::String(bytes, StringFormat::Hexadecimal) => {
+            out.push(b'<');
+            for byte in bytes {
+                out.extend_from_slice(format!("{:02X}", byte).as_bytes());
+            }
+            out.push(b'>');
+        }
+        Object::String(bytes, StringFormat::Literal) => {
+            out.push(b'(');
+            for &byte in bytes {
+                match byte {
+                    b'(' | b')' | b'\\' => out.extend_from_slice(&[b'\\', byte]),
+                    b'\n' => out.extend_from_slice(b"\\n"),
+                    b'\r' => out.extend_from_slice(b"\\r"),
+                    0x20..=0x7E => out.push(byte),
+                    _ => out.extend_from_slice(format!("\\{:03o}", byte).as_bytes()),
+                }
+            }
+            out.push(b')');
+        }
+        Object::Array(items) => {
+            out.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(b' ');
+                }
+                write_object(out, item);
+            }
+            out.push(b']');
+        }
+        Object::Dictionary(dict) => {
+            out.extend_from_slice(b"<<");
+            for (key, value) in dict.iter() {
+                out.push(b' ');
+                write_object(out, &Object::Name(key.clone()));
+                out.push(b' ');
+                write_object(out, value);
+            }
+            out.extend_from_slice(b" >>");
+        }
+        Object::Stream(stream) => write_object(out, &Object::Dictionary(stream.dict.clone())),
+        Object::Reference((id, generation)) => {
+            out.extend_from_slice(format!("{} {} R", id, generation).as_bytes());
+        }
+    }
+}
+
+fn field_objects(
+    nodes: &[FieldTree],
+    parent: Option<&str>,
+    button_state: &dyn Fn(&str) -> bool,
+) -> Vec<Object> {
+    nodes
+        .iter()
+        .map(|node| {
+            let name = match parent {
+                Some(parent) => format!("{}.{}", parent, node.name),
+                None => node.name.to_string(),
+            };
+            let mut field = Dictionary::new();
+            field.set("T", lopdf::text_string(node.name));
+            match node.value {
+                Some(FormValue::Single(value)) if button_state(&name) => {
+                    field.set("V", Object::Name(value.as_bytes().to_vec()));
+                }
+                Some(FormValue::Single(value)) => field.set("V", lopdf::text_string(value)),
+                Some(FormValue::Multiple(values)) => field.set(
+                    "V",
+                    Object::Array(values.iter().map(|v| lopdf::text_string(v)).collect()),
+                ),
+                None => {}
+            }
+            if !node.kids.is_empty() {
+                field.set(
+                    "Kids",
+                    Object::Array(field_objects(&node.kids, Some(&name), button_state)),
+                );
+            }
+            Object::Dictionary(field)
+        })
+        .collect()
+}
+
+/// Write field values as an FDF file for `target`. `button_state` tells which
+/// fields hold checkbox or radio states, which FDF stores as names.
+pub(crate) fn write_fdf(
+    target: &str,
+    fields: &[FieldValue],
+    button_state: impl Fn(&str) -> bool,
+) -> Vec<u8> {
+    let mut fdf = Dictionary::new();
+    fdf.set("F", lopdf::text_string(target));
+    fdf.set(
+        "Fields",
+        Object::Array(field_objects(&field_tree(fields), None, &button_state)),
+    );
+    let mut catalog = Dictionary::new();
+    catalog.set("FDF", Object::Dictionary(fdf));
+
+    let mut out = b"%FDF-1.2\n%\xE2\xE3\xCF\xD3\n1 0 obj\n".to_vec();
+    write_object(&mut out, &Object::Dictionary(catalog));
+    out.extend_from_slice(b"\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> ParsedFormData {
+        parse_fdf(Path::new("test.fdf"), bytes).unwrap()
+    }
+
+    #[test]
+    fn test_fields_annotations_and_target() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms/form.fdf");
+        let data = parse(&std::fs::read(path).unwrap());
+
+        assert_eq!(data.target.as_deref(), Some("form.pdf"));
+        let single = |value: &str| FormValue::Single(value.to_string());
+        assert_eq!(
+            data.fields,
+            vec![
+                FieldValue {
+                    name: "name".to_string(),
+                    value: single("Ada Lovelace"),
+                },
+                FieldValue {
+                    name: "address.street".to_string(),
+                    value: single("12 St James\u{2019}s Sq"),
+                },
+                FieldValue {
+                    name: "address.city".to_string(),
+                    value: single("London"),
+                },
+                FieldValue {
+                    name: "subscribe".to_string(),
+                    value: single("Yes"),
+                },
+                FieldValue {
+                    name: "colors".to_string(),
+                    value: FormValue::Multiple(vec!["red".to_string(), "blue".to_string()]),
+                },
+            ]
+        );
+
+        assert_eq!(
+            data.annotations[0],
+            Annotation {
+                subtype: "Text".to_string(),
+                page: 0,
+                rect: [100.0, 100.0, 120.0, 120.0],
+                contents: Some("Check the totals".to_string()),
+                author: Some("Reviewer".to_string()),
+                color: Some([1.0, 0.5, 0.0]),
+                name: Some("note-1".to_string()),
+                modified: Some("2024-01-02T03:04:05Z".to_string()),
+                ..Annotation::default()
+            }
+        );
+        assert_eq!(data.annotations[1].subtype, "Highlight");
+        assert_eq!(data.annotations[1].page, 1);
+        assert_eq!(data.annotations[1].quad_points.len(), 8);
+    }
+
+    #[test]
+    fn test_lexer_handles_escapes_comments_and_streams() {
+        let data = parse(
+            b"%FDF-1.2\n1 0 obj % comment\n<< /FDF << /F (dir/a\\(1\\).pdf) /Fields [\n\
+              << /T (A#20B) /V /Of#66 >> << /T <4E> /V (line\\\none) >> ] >> >>\nendobj\n\
+              2 0 obj << /Length 4 >> stream\n>>>>\nendstream endobj\n\
+              trailer << /Root 1 0 R >>\n%%EOF\n",
+        );
+
+        assert_eq!(data.target.as_deref(), Some("dir/a(1).pdf"));
+        assert_eq!(
+            data.fields,
+            vec![
+                FieldValue {
+                    name: "A#20B".to_string(),
+                    value: FormValue::Single("Off".to_string()),
+                },
+                FieldValue {
+                    name: "N".to_string(),
+                    value: FormValue::Single("lineone".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_written_fdf_parses_back() {
+        let fields = vec![
+            FieldValue {
+                name: "address.street".to_string(),
+                value: FormValue::Single("12 (Main) St \\ Apt 4".to_string()),
+            },
+            FieldValue {
+                name: "address.city".to_string(),
+                value: FormValue::Single("Zoë № 4".to_string()),
+            },
+            FieldValue {
+                name: "subscribe".to_string(),
+                value: FormValue::Single("Yes".to_string()),
+            },
+            FieldValue {
+                name: "colors".to_string(),
+                value: FormValue::Multiple(vec!["red".to_string(), "blue".to_string()]),
+            },
+        ];
+
+        let bytes = write_fdf("forms/tax form.pdf", &fields, |name| name == "subscribe");
+
+        assert!(bytes.starts_with(b"%FDF-1.2"));
+        assert!(bytes
+            .windows(b"/V /Yes".len())
+            .any(|window| window == b"/V /Yes"));
+        let data = parse(&bytes);
+        assert_eq!(data.target.as_deref(), Some("forms/tax form.pdf"));
+        assert_eq!(data.fields, fields);
+    }
+
+    #[test]
+    fn test_missing_fdf_dictionary_is_an_error() {
+        let err = parse_fdf(Path::new("x.fdf"), b"%FDF-1.2\ntrailer << >>\n").unwrap_err();
+        assert_eq!(err.code(), "INVALID_FORM_DATA");
+
+        let err = parse_fdf(Path::new("x.fdf"), b"%FDF-1.2\n1 0 obj << /FDF").unwrap_err();
+        assert_eq!(err.code(), "INVALID_FORM_DATA");
+    }
+}
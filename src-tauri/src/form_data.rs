@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lopdf::{Document, Object};
+
+use crate::document_kind::{detect_document_kind, DocumentKind};
+use crate::error::MonightError;
+use crate::pdf::{resolve, text_string};
+use crate::{fdf, xfdf};
+
+/// Value of a form field: a single text or button state, or the selected
+/// options of a multi-select list.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum FormValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct FieldValue {
+    /// Fully qualified field name, e.g. `address.city`
+    pub name: String,
+    pub value: FormValue,
+}
+
+/// Markup annotation shared by FDF, XFDF and the PDF writers.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Annotation {
+    /// PDF annotation subtype, e.g. `Text`, `Highlight`, `FreeText`
+    pub subtype: String,
+    /// Zero-based page index
+    pub page: u32,
+    /// `[x1, y1, x2, y2]` in PDF user space
+    pub rect: [f64; 4],
+    pub contents: Option<String>,
+    pub author: Option<String>,
+    /// RGB components in `0.0..=1.0`
+    pub color: Option<[f64; 3]>,
+    /// Unique annotation name (`/NM`)
+    pub name: Option<String>,
+    /// Last modification as ISO 8601
+    pub modified: Option<String>,
+    #[serde(default)]
+    pub quad_points: Vec<f64>,
+    #[serde(default)]
+    pub ink_list: Vec<Vec<f64>>,
+}
+
+/// Field values and annotations read from a form data file.
+#[derive(Clone, Serialize, Debug, Default, PartialEq)]
+pub struct FormData {
+    /// Canonical path of the PDF the data belongs to
+    pub target: Option<String>,
+    pub fields: Vec<FieldValue>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Read a field's `/V`: names for button states, text strings, or arrays of
+/// text strings for multi-select lists.
+pub(crate) fn form_value(doc: &Document, value: &Object) -> Option<FormValue> {
+    match value {
+        Object::Name(name) => Some(FormValue::Single(String::from_utf8_lossy(name).to_string())),
+        Object::Array(items) => Some(FormValue::Multiple(
+            items
+                .iter()
+                .filter_map(|item| resolve(doc, item).and_then(text_string))
+                .collect(),
+        )),
+        Object::String(..) => text_string(value).map(FormValue::Single),
+        _ => None,
+    }
+}
+
+/// Field values regrouped by partial name, as FDF and XFDF nest them.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FieldTree<'a> {
+    pub name: &'a str,
+    pub value: Option<&'a FormValue>,
+    pub kids: Vec<FieldTree<'a>>,
+}
+
+pub(crate) fn field_tree(fields: &[FieldValue]) -> Vec<FieldTree<'_>> {
+    let mut roots: Vec<FieldTree> = Vec::new();
+    for field in fields {
+        let mut level = &mut roots;
+        let mut parts = field.name.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let index = match level.iter().position(|node| node.name == part) {
+                Some(index) => index,
+                None => {
+                    level.push(FieldTree {
+                        name: part,
+                        value: None,
+                        kids: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            if parts.peek().is_none() {
+                level[index].value = Some(&field.value);
+            }
+            level = &mut level[index].kids;
+        }
+    }
+    roots
+}
+
+/// How a data file saved at `data_file` should name `pdf`: just the file name
+/// when both share a folder, the full path otherwise.
+pub(crate) fn target_href(pdf: &Path, data_file: &Path) -> String {
+    match (pdf.parent(), data_file.parent(), pdf.file_name()) {
+        (Some(pdf_dir), Some(data_dir), Some(name)) if pdf_dir == data_dir => {
+            name.to_string_lossy().to_string()
+        }
+        _ => pdf.to_string_lossy().to_string(),
+    }
+}
+
+/// Replace `path` with `bytes` via a temporary file, so readers never see a
+/// half-written file.
+pub(crate) fn replace_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = Path::new(&temp);
+    std::fs::write(temp, bytes)?;
+    std::fs::rename(temp, path)
+}
+
+/// [`replace_file`] reporting failures against `path`.
+pub(crate) fn write_file(path: &Path, bytes: &[u8]) -> Result<(), MonightError> {
+    replace_file(path, bytes).map_err(|e| MonightError::from_io(path, e))
+}
+
+/// A form data file parsed before its target is resolved.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ParsedFormData {
+    /// Target file exactly as written in the data file
+    pub target: Option<String>,
+    /// Identifier of the target's contents (XFDF `<ids original>`)
+    pub document_id: Option<String>,
+    pub fields: Vec<FieldValue>,
+    pub annotations: Vec<Annotation>,
+}
+
+pub(crate) fn invalid_form_data(path: &Path, message: impl Into<String>) -> MonightError {
+    MonightError::InvalidFormData {
+        path: path.to_string_lossy().to_string(),
+        message: message.into(),
+    }
+}
+
+/// A file specification as a path; specifications use `/` even on Windows.
+fn spec_path(target: &str) -> PathBuf {
+    target
+        .split('/')
+        .collect::<Vec<_>>()
+        .join(std::path::MAIN_SEPARATOR_STR)
+        .into()
+}
+
+/// Resolve the target named by a data file relative to the data file's folder.
+pub(crate) fn resolve_target(data_file: &Path, target: &str) -> Result<PathBuf, MonightError> {
+    let target_path = spec_path(target);
+    let candidate = if target_path.is_absolute() {
+        target_path
+    } else {
+        data_file
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(target_path)
+    };
+
+    let canonical =
+        std::fs::canonicalize(&candidate).map_err(|e| MonightError::from_io(&candidate, e))?;
+    match detect_document_kind(&canonical) {
+        Ok(Some(DocumentKind::Pdf)) => Ok(canonical),
+        Ok(_) => Err(MonightError::ContentMismatch {
+            file: target.to_string(),
+            expected: DocumentKind::Pdf,
+        }),
+        Err(e) => Err(MonightError::from_io(&canonical, e)),
+    }
+}
+
+/// Parse an FDF or XFDF file and resolve the PDF it fills in.
+pub fn load_form_data(path: &Path, kind: DocumentKind) -> Result<FormData, MonightError> {
+    let bytes = std::fs::read(path).map_err(|e| MonightError::from_io(path, e))?;
+    let parsed = match kind {
+        DocumentKind::Fdf => fdf::parse_fdf(path, &bytes)?,
+        DocumentKind::Xfdf => xfdf::parse_xfdf(path, &bytes)?,
+        DocumentKind::Pdf | DocumentKind::Xdp => {
+            return Err(invalid_form_data(path, "not an FDF or XFDF file"))
+        }
+    };
+
+    let target = parsed
+        .target
+        .as_deref()
+        .map(|target| resolve_target(path, target))
+        .transpose()?;
+
+    Ok(FormData {
+        target: target.map(|target| target.to_string_lossy().to_string()),
+        fields: parsed.fields,
+        annotations: parsed.annotations,
+    })
+}
+
+/// Form data waiting for the viewer, keyed by the canonical path of its target PDF.
+pub struct PendingFormData(pub Mutex<HashMap<String, FormData>>);
+
+/// Load a form data file and hold its values until the viewer asks for them,
+/// returning the canonical path of the PDF to open in its place.
+pub(crate) fn stash_form_data(
+    pending: &PendingFormData,
+    path: &Path,
+    kind: DocumentKind,
+) -> Result<String, MonightError> {
+    let data = load_form_data(path, kind)?;
+    let target = data
+        .target
+        .clone()
+        .ok_or_else(|| invalid_form_data(path, "no target PDF is named"))?;
+    pending.0.lock().unwrap().insert(target.clone(), data);
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms")
+    }
+
+    #[test]
+    fn test_fdf_and_xfdf_resolve_the_same_target_and_fields() {
+        let target = std::fs::canonicalize(forms().join("form.pdf"))
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let fdf = load_form_data(&forms().join("form.fdf"), DocumentKind::Fdf).unwrap();
+        let xfdf = load_form_data(&forms().join("form.xfdf"), DocumentKind::Xfdf).unwrap();
+
+        assert_eq!(fdf.target.as_deref(), Some(target.as_str()));
+        assert_eq!(xfdf.target, fdf.target);
+        assert_eq!(xfdf.fields, fdf.fields);
+
+        // XFDF colours are 8-bit hex, so compare them separately
+        let without_color = |annotations: &[Annotation]| -> Vec<Annotation> {
+            annotations
+                .iter()
+                .map(|annotation| Annotation {
+                    color: None,
+                    ..annotation.clone()
+                })
+                .collect()
+        };
+        assert_eq!(
+            without_color(&xfdf.annotations[..2]),
+            without_color(&fdf.annotations)
+        );
+    }
+
+    #[test]
+    fn test_target_is_resolved_relative_to_the_data_file() {
+        let data = load_form_data(&forms().join("nested/relative.fdf"), DocumentKind::Fdf).unwrap();
+
+        assert_eq!(
+            data.target.map(PathBuf::from),
+            Some(std::fs::canonicalize(forms().join("form.pdf")).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_spec_paths_use_the_platform_separator() {
+        assert_eq!(
+            spec_path("../forms/form.pdf"),
+            Path::new("..").join("forms").join("form.pdf")
+        );
+        assert_eq!(spec_path("form.pdf"), PathBuf::from("form.pdf"));
+    }
+
+    #[test]
+    fn test_missing_target_is_reported() {
+        let err =
+            load_form_data(&forms().join("missing_target.fdf"), DocumentKind::Fdf).unwrap_err();
+        assert!(matches!(err, MonightError::NotFound { .. }));
+    }
+
+    #[test]
+    fn test_stash_keys_data_by_target() {
+        let pending = PendingFormData(Mutex::new(HashMap::new()));
+
+        let target =
+            stash_form_data(&pending, &forms().join("form.xfdf"), DocumentKind::Xfdf).unwrap();
+
+        let stashed = pending.0.lock().unwrap().remove(&target).unwrap();
+        assert_eq!(stashed.fields.len(), 5);
+        assert_eq!(stashed.annotations.len(), 3);
+    }
+
+    #[test]
+    fn test_field_tree_nests_dotted_names() {
+        let field = |name: &str, value: &str| FieldValue {
+            name: name.to_string(),
+            value: FormValue::Single(value.to_string()),
+        };
+        let fields = vec![
+            field("address.street", "Main St"),
+            field("name", "Ada"),
+            field("address.city", "London"),
+        ];
+
+        let tree = field_tree(&fields);
+
+        let names: Vec<&str> = tree.iter().map(|node| node.name).collect();
+        assert_eq!(names, vec!["address", "name"]);
+        assert_eq!(tree[0].value, None);
+        let kids: Vec<(&str, Option<&FormValue>)> = tree[0]
+            .kids
+            .iter()
+            .map(|node| (node.name, node.value))
+            .collect();
+        assert_eq!(
+            kids,
+            vec![
+                ("street", Some(&fields[0].value)),
+                ("city", Some(&fields[2].value))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_href_is_relative_only_within_a_folder() {
+        assert_eq!(
+            target_href(Path::new("/forms/tax.pdf"), Path::new("/forms/tax.fdf")),
+            "tax.pdf"
+        );
+        assert_eq!(
+            target_href(Path::new("/forms/tax.pdf"), Path::new("/exports/tax.fdf")),
+            "/forms/tax.pdf"
+        );
+    }
+
+    #[test]
+    fn test_form_value_serializes_untagged() {
+        let single = serde_json::to_value(FormValue::Single("Yes".to_string())).unwrap();
+        let multiple = serde_json::to_value(FormValue::Multiple(vec!["a".to_string()])).unwrap();
+
+        assert_eq!(single, serde_json::json!("Yes"));
+        assert_eq!(multiple, serde_json::json!(["a"]));
+    }
+}
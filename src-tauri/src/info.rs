@@ -0,0 +1,195 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::error::MonightError;
+use crate::pdf::{dict_get, format_pdf_date, info_dictionary, parse_document, text_string};
+
+/// How far into the file the linearization dictionary may appear.
+const LINEARIZATION_WINDOW: usize = 1024;
+
+/// Metadata reported by `monight info`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DocumentInfo {
+    pub path: String,
+    pub file_size: u64,
+    pub pdf_version: String,
+    pub page_count: usize,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+    pub modification_date: Option<String>,
+    pub has_xmp: bool,
+    pub encrypted: bool,
+    pub linearized: bool,
+}
+
+/// A linearized file starts with a dictionary carrying `/Linearized`.
+fn is_linearized(bytes: &[u8]) -> bool {
+    let window = &bytes[..bytes.len().min(LINEARIZATION_WINDOW)];
+    window
+        .windows(b"/Linearized".len())
+        .any(|candidate| candidate == b"/Linearized")
+}
+
+/// Gather metadata about the PDF at `path`.
+pub fn document_info(path: &Path) -> Result<DocumentInfo, MonightError> {
+    let bytes = std::fs::read(path).map_err(|e| MonightError::from_io(path, e))?;
+    let mut doc = parse_document(path, &bytes)?;
+
+    // Info strings of encrypted files are only readable once decrypted; most
+    // such files only restrict permissions and open with an empty password.
+    let encrypted = doc.is_encrypted();
+    let readable = !encrypted || doc.decrypt("").is_ok();
+
+    let catalog = doc.catalog().ok();
+    // The catalog may declare a newer version than the header after an incremental update
+    let catalog_version = catalog
+        .and_then(|catalog| dict_get(&doc, catalog, b"Version"))
+        .and_then(|version| version.as_name().ok())
+        .map(|version| String::from_utf8_lossy(version).to_string());
+    let pdf_version = match catalog_version {
+        Some(version) if version > doc.version => version,
+        _ => doc.version.clone(),
+    };
+    let has_xmp = catalog
+        .and_then(|catalog| dict_get(&doc, catalog, b"Metadata"))
+        .is_some_and(|metadata| metadata.as_stream().is_ok());
+
+    let info = info_dictionary(&doc).filter(|_| readable);
+    let field = |key: &[u8]| {
+        info.and_then(|info| dict_get(&doc, info, key))
+            .and_then(text_string)
+            .filter(|value| !value.is_empty())
+    };
+    let date = |key: &[u8]| field(key).map(|raw| format_pdf_date(&raw).unwrap_or(raw));
+
+    Ok(DocumentInfo {
+        path: path.to_string_lossy().to_string(),
+        file_size: bytes.len() as u64,
+        pdf_version,
+        page_count: doc.get_pages().len(),
+        title: field(b"Title"),
+        author: field(b"Author"),
+        subject: field(b"Subject"),
+        keywords: field(b"Keywords"),
+        creator: field(b"Creator"),
+        producer: field(b"Producer"),
+        creation_date: date(b"CreationDate"),
+        modification_date: date(b"ModDate"),
+        has_xmp,
+        encrypted,
+        linearized: is_linearized(&bytes),
+    })
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+impl fmt::Display for DocumentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "File:          {}", self.path)?;
+        writeln!(f, "File size:     {} bytes", self.file_size)?;
+        writeln!(f, "PDF version:   {}", self.pdf_version)?;
+        writeln!(f, "Pages:         {}", self.page_count)?;
+
+        let fields = [
+            ("Title:", &self.title),
+            ("Author:", &self.author),
+            ("Subject:", &self.subject),
+            ("Keywords:", &self.keywords),
+            ("Creator:", &self.creator),
+            ("Producer:", &self.producer),
+            ("Created:", &self.creation_date),
+            ("Modified:", &self.modification_date),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                writeln!(f, "{:<15}{}", label, value)?;
+            }
+        }
+
+        writeln!(f, "XMP metadata:  {}", yes_no(self.has_xmp))?;
+        writeln!(f, "Encrypted:     {}", yes_no(self.encrypted))?;
+        write!(f, "Linearized:    {}", yes_no(self.linearized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+    }
+
+    #[test]
+    fn test_document_info_reads_metadata() {
+        let info = document_info(&fixtures().join("metadata.pdf")).unwrap();
+
+        assert_eq!(info.pdf_version, "1.6");
+        assert_eq!(info.page_count, 2);
+        assert_eq!(info.title.as_deref(), Some("Quarterly Report — Q3"));
+        assert_eq!(info.author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(info.producer.as_deref(), Some("Monight Fixtures"));
+        assert_eq!(info.subject, None);
+        assert_eq!(
+            info.creation_date.as_deref(),
+            Some("2024-03-15T09:30:00+01:00")
+        );
+        assert_eq!(
+            info.modification_date.as_deref(),
+            Some("2024-04-01T00:00:00")
+        );
+        assert!(info.has_xmp);
+        assert!(!info.encrypted);
+        assert!(info.linearized);
+        assert_eq!(
+            info.file_size,
+            std::fs::metadata(fixtures().join("metadata.pdf"))
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_document_info_json_uses_snake_case_fields() {
+        let info = document_info(&fixtures().join("metadata.pdf")).unwrap();
+
+        let value = serde_json::to_value(&info).unwrap();
+
+        assert_eq!(value["page_count"], 2);
+        assert_eq!(value["has_xmp"], true);
+        assert_eq!(value["keywords"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_document_info_text_skips_missing_fields() {
+        let text = document_info(&fixtures().join("metadata.pdf"))
+            .unwrap()
+            .to_string();
+
+        assert!(text.contains("Pages:         2\n"));
+        assert!(text.contains("Author:        Ada Lovelace\n"));
+        assert!(!text.contains("Subject:"));
+        assert!(text.ends_with("Linearized:    yes"));
+    }
+
+    #[test]
+    fn test_document_info_reports_unparseable_files() {
+        let err = document_info(&fixtures().join("not_a_pdf.pdf")).unwrap_err();
+        assert!(matches!(err, MonightError::InvalidPdf { .. }));
+
+        let err = document_info(&fixtures().join("missing.pdf")).unwrap_err();
+        assert!(matches!(err, MonightError::NotFound { .. }));
+    }
+}
@@ -2,55 +2,150 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use clap::Parser;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::{Emitter, Listener, Manager};
 
+mod acroform;
+mod annotations;
+mod attachments;
+mod cli;
 mod commands;
+mod deep_link;
+mod document_kind;
+mod document_links;
+mod error;
+mod fdf;
+mod form_data;
+mod info;
+mod library;
+mod link_policy;
 mod menu;
+mod notes;
+mod open_target;
+mod outline;
+mod page_labels;
+mod page_text;
+mod pdf;
+mod persist;
+mod protocol;
+mod reading_position;
+mod recent;
+mod scan;
+mod security;
+mod settings;
+mod single_instance;
+mod synctex;
+#[cfg(test)]
+mod test_support;
+mod watcher;
+mod xdp;
+mod xfdf;
 
 /// Command line arguments for Monight PDF viewer
 #[derive(Parser, Debug, Clone)]
 #[command(name = "Monight")]
 #[command(about = "Monight (墨页) - A modern PDF reader", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(group(clap::ArgGroup::new("target").args(["page", "page_label", "dest", "search"])))]
 struct Cli {
-    /// PDF file(s) to open
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    /// PDF file(s) to open; `file.pdf:12`, `file.pdf#page=5&zoom=150` and
+    /// `monight://open?file=…` links open each one at its own page
     #[arg(value_name = "FILE")]
     files: Vec<String>,
 
     /// Page number to open (applies to first file only)
     #[arg(short, long, value_name = "PAGE")]
     page: Option<u32>,
+
+    /// Open at the page with this printed label, e.g. "iv" or "A-12"
+    #[arg(long, value_name = "LABEL")]
+    page_label: Option<String>,
+
+    /// Open at a named destination
+    #[arg(long, value_name = "NAME")]
+    dest: Option<String>,
+
+    /// Open at the first page containing this text
+    #[arg(long, value_name = "TEXT")]
+    search: Option<String>,
+
+    /// Jump to the PDF location typeset from a TeX source line (LINE:COLUMN:FILE)
+    #[arg(long, value_name = "LINE:COLUMN:FILE")]
+    synctex_forward: Option<synctex::ForwardSearch>,
+
+    /// Editor command run on inverse search; %{input} and %{line} are substituted
+    #[arg(long, value_name = "COMMAND")]
+    synctex_editor: Option<String>,
+
+    /// Start a separate process instead of handing files to a running instance
+    #[arg(long)]
+    new_instance: bool,
 }
 
 /// Payload sent to frontend with CLI arguments
-#[derive(Clone, Serialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct CliPayload {
     files: Vec<String>,
-    page: Option<u32>,
+    /// Where to open the files that were given a page, zoom or view mode
+    #[serde(default)]
+    targets: Vec<open_target::FileTarget>,
 }
 
-pub struct PendingCliPayload(pub Mutex<Option<CliPayload>>);
+impl CliPayload {
+    /// The target of `file`, added empty if it has none yet.
+    fn target_for_mut(&mut self, file: &str) -> &mut open_target::FileTarget {
+        let index = match self.targets.iter().position(|target| target.file == file) {
+            Some(index) => index,
+            None => {
+                self.targets.push(open_target::FileTarget {
+                    file: file.to_string(),
+                    ..open_target::FileTarget::default()
+                });
+                self.targets.len() - 1
+            }
+        };
+        &mut self.targets[index]
+    }
 
-pub(crate) fn is_supported_extension(path: &std::path::Path) -> bool {
-    path.extension()
-        .and_then(|e| e.to_str())
-        .map(|ext| {
-            matches!(
-                ext.to_ascii_lowercase().as_str(),
-                "pdf" | "xdp" | "fdf" | "xfdf"
-            )
-        })
-        .unwrap_or(false)
+    /// Keep only the files that pass the same checks as files opened from the
+    /// frontend, by canonical path, and the targets of those files.
+    pub(crate) fn into_openable(self) -> Option<CliPayload> {
+        let mut payload = CliPayload {
+            files: Vec::new(),
+            targets: Vec::new(),
+        };
+        for file in self.files {
+            let Ok(canonical) = commands::validate_open_path_inner(file.clone()) else {
+                continue;
+            };
+            if let Some(target) = self.targets.iter().find(|target| target.file == file) {
+                payload.targets.push(open_target::FileTarget {
+                    file: canonical.clone(),
+                    ..target.clone()
+                });
+            }
+            payload.files.push(canonical);
+        }
+        (!payload.files.is_empty()).then_some(payload)
+    }
 }
 
+pub struct PendingCliPayload(pub Mutex<Option<CliPayload>>);
+
 pub(crate) fn store_pending_payload_inner(state: &PendingCliPayload, payload: CliPayload) {
     let mut guard = state.0.lock().unwrap();
     if let Some(existing) = guard.as_mut() {
         existing.files.extend(payload.files);
-        if existing.page.is_none() {
-            existing.page = payload.page;
+        // A file asked for again goes where the latest request says
+        for target in payload.targets {
+            let file = target.file.clone();
+            *existing.target_for_mut(&file) = target;
         }
     } else {
         *guard = Some(payload);
@@ -78,22 +173,9 @@ fn dispatch_open_payload(app: &tauri::AppHandle, payload: CliPayload) {
     }
 }
 
-fn path_from_open_candidate(candidate: String) -> Option<PathBuf> {
-    let trimmed = candidate.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-
-    if let Ok(url) = url::Url::parse(trimmed) {
-        if url.scheme() == "file" {
-            return url.to_file_path().ok();
-        }
-    }
-
-    Some(PathBuf::from(trimmed))
-}
-
-pub(crate) fn paths_from_legacy_file_open_payload(payload: &str) -> Vec<PathBuf> {
+/// Requests from a `tauri://file-open` payload: a JSON list, a JSON string or a
+/// raw path, each of which may carry a `#page=…` style fragment.
+pub(crate) fn requests_from_legacy_file_open_payload(payload: &str) -> Vec<deep_link::OpenRequest> {
     if payload.is_empty() {
         return Vec::new();
     }
@@ -110,7 +192,7 @@ pub(crate) fn paths_from_legacy_file_open_payload(payload: &str) -> Vec<PathBuf>
 
     files
         .into_iter()
-        .filter_map(path_from_open_candidate)
+        .filter_map(|file| deep_link::parse_open_candidate(&file))
         .collect()
 }
 
@@ -118,46 +200,166 @@ pub(crate) fn payload_from_file_paths<I>(files: I, page: Option<u32>) -> Option<
 where
     I: IntoIterator<Item = PathBuf>,
 {
-    let valid_files = files
-        .into_iter()
-        .filter_map(|file| std::fs::canonicalize(file).ok())
-        .filter(|canonical| canonical.exists() && is_supported_extension(canonical))
-        .map(|canonical| canonical.to_string_lossy().to_string())
-        .collect::<Vec<_>>();
+    payload_from_open_requests(
+        files.into_iter().map(deep_link::OpenRequest::from_path),
+        page.map(open_target::OpenTarget::Page),
+    )
+}
 
-    if valid_files.is_empty() {
-        None
-    } else {
-        Some(CliPayload {
-            files: valid_files,
-            page,
-        })
+/// Build a payload from the documents that can be opened. Each one goes where
+/// its request asks, except that `target` overrides the first; targets are
+/// resolved to pages up front.
+pub(crate) fn payload_from_open_requests<I>(
+    requests: I,
+    mut target: Option<open_target::OpenTarget>,
+) -> Option<CliPayload>
+where
+    I: IntoIterator<Item = deep_link::OpenRequest>,
+{
+    let valid_requests = requests.into_iter().filter_map(|request| {
+        let canonical = std::fs::canonicalize(&request.path).ok()?;
+        matches!(document_kind::detect_document_kind(&canonical), Ok(Some(_))).then_some(
+            deep_link::OpenRequest {
+                path: canonical,
+                ..request
+            },
+        )
+    });
+
+    let mut payload = CliPayload {
+        files: Vec::new(),
+        targets: Vec::new(),
+    };
+    for request in valid_requests {
+        let file = request.path.to_string_lossy().to_string();
+        let target = if payload.files.is_empty() {
+            target.take().or_else(|| request.target())
+        } else {
+            request.target()
+        };
+        if target.is_some() || request.zoom.is_some() || request.view_mode.is_some() {
+            let page = target.as_ref().and_then(|target| {
+                open_target::resolve_target_page(&request.path, target).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    None
+                })
+            });
+            payload.targets.push(open_target::FileTarget {
+                file: file.clone(),
+                page,
+                zoom: request.zoom,
+                view_mode: request.view_mode,
+                target,
+                synctex: None,
+            });
+        }
+        payload.files.push(file);
     }
+
+    (!payload.files.is_empty()).then_some(payload)
+}
+
+/// Resolve a SyncTeX forward search against the first file, overriding the requested page.
+pub(crate) fn apply_forward_search(
+    payload: &mut CliPayload,
+    search: &synctex::ForwardSearch,
+) -> Result<(), error::MonightError> {
+    let Some(pdf) = payload.files.first().cloned() else {
+        return Ok(());
+    };
+
+    let data = synctex::SynctexData::load_for_pdf(std::path::Path::new(&pdf))?;
+    let position = data.forward(&search.file, search.line).ok_or_else(|| {
+        synctex::synctex_error(format!(
+            "{}:{} does not appear in {}",
+            search.file, search.line, pdf
+        ))
+    })?;
+
+    let target = payload.target_for_mut(&pdf);
+    target.page = Some(position.page);
+    target.synctex = Some(position);
+    Ok(())
 }
 
 pub(crate) fn payload_from_opened_urls(urls: &[url::Url]) -> Option<CliPayload> {
-    payload_from_file_paths(
-        urls.iter().filter_map(|url| {
-            if url.scheme() == "file" {
-                url.to_file_path().ok()
-            } else {
-                None
-            }
-        }),
-        None,
-    )
+    payload_from_open_requests(urls.iter().filter_map(deep_link::request_from_url), None)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Parse command line arguments (ignore macOS Finder -psn_* argument)
+    let cli = Cli::parse_from(std::env::args().filter(|arg| !arg.starts_with("-psn_")));
+
+    // Subcommands are headless and never start the GUI
+    if let Some(command) = cli.command {
+        std::process::exit(cli::run_command(command));
+    }
+
+    let target = cli
+        .page
+        .map(open_target::OpenTarget::Page)
+        .or(cli.page_label.map(open_target::OpenTarget::PageLabel))
+        .or(cli.dest.map(open_target::OpenTarget::Destination))
+        .or(cli.search.map(open_target::OpenTarget::Search));
+    let mut cli_payload = payload_from_open_requests(
+        cli.files
+            .iter()
+            .filter_map(|file| deep_link::parse_open_candidate(file)),
+        target,
+    );
+
+    // Resolve forward search up front so a running instance receives a plain page
+    if let (Some(payload), Some(search)) = (cli_payload.as_mut(), cli.synctex_forward.as_ref()) {
+        if let Err(e) = apply_forward_search(payload, search) {
+            eprintln!("{}", e);
+        }
+    }
+
+    // Hand the launch over to an already running instance unless asked not to
+    let listener = if cli.new_instance {
+        None
+    } else {
+        let forwarded = single_instance::Launch {
+            payload: cli_payload.clone().unwrap_or(CliPayload {
+                files: Vec::new(),
+                targets: Vec::new(),
+            }),
+            synctex_editor: cli.synctex_editor.clone(),
+        };
+        match single_instance::acquire(&forwarded) {
+            Ok(single_instance::Instance::Forwarded) => return,
+            Ok(single_instance::Instance::Primary(listener)) => Some(listener),
+            Err(e) => {
+                eprintln!("Single-instance socket unavailable: {}", e);
+                None
+            }
+        }
+    };
+
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .manage(PendingCliPayload(Mutex::new(None)))
+        .manage(synctex::SynctexEditor(Mutex::new(cli.synctex_editor)))
+        .manage(form_data::PendingFormData(Mutex::new(HashMap::new())))
+        .manage(protocol::DocumentRegistry(Mutex::new(HashMap::new())))
+        .manage(security::DocumentPermissions::default())
+        .manage(scan::ScanCache::default())
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, |ctx, request, responder| {
+            // Serve from a blocking task so large range reads never stall the event loop
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let registry = app.state::<protocol::DocumentRegistry>();
+                responder.respond(protocol::handle_request(registry.inner(), &request));
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             commands::read_pdf_file,
+            commands::scan_document,
+            commands::read_xdp_package,
             commands::get_file_name,
             commands::get_file_directory,
             commands::open_settings,
@@ -165,14 +367,71 @@ pub fn run() {
             commands::fit_main_window_for_pdf,
             commands::take_cli_payload,
             commands::validate_open_path,
+            commands::unlock_document,
+            commands::document_permissions,
+            commands::forget_document_permissions,
+            commands::take_form_data,
             commands::open_external_url,
+            commands::open_document_link,
+            commands::open_document_stream,
+            commands::close_document_stream,
+            commands::watch_document,
+            commands::unwatch_document,
+            commands::get_document_outline,
+            commands::get_form_fields,
+            commands::save_form_as,
+            commands::export_form_data,
+            commands::list_attachments,
+            commands::save_attachment,
+            commands::get_annotations,
+            commands::add_annotation,
+            commands::update_annotation,
+            commands::delete_annotation,
+            commands::reanchor_annotations,
+            commands::clear_annotations,
+            commands::embed_annotations,
+            commands::export_notes,
+            commands::get_recent_documents,
+            commands::set_recent_pinned,
+            commands::clear_recent_documents,
+            commands::get_reading_position,
+            commands::set_reading_position,
+            commands::list_library_folders,
+            commands::add_library_folder,
+            commands::remove_library_folder,
+            commands::update_library_index,
+            commands::search_library,
+            commands::open_search_hit,
+            commands::synctex_forward,
+            commands::synctex_inverse,
+            commands::synctex_edit,
         ])
-        .setup(|app| {
-            // Parse command line arguments (ignore macOS Finder -psn_* argument)
-            let cli = Cli::parse_from(std::env::args().filter(|arg| !arg.starts_with("-psn_")));
+        .setup(move |app| {
             let window = app.get_webview_window("main").unwrap();
             let app_handle = app.handle();
 
+            // Watch open documents so edits on disk reach the viewer
+            app.manage(watcher::DocumentWatcher::start(app_handle));
+
+            // Library search index, recent documents, reading positions and fallback
+            // annotation sidecars live next to the other app data
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(library::Library::new(data_dir.join(library::INDEX_FILE)));
+            app.manage(annotations::AnnotationStore::new(
+                data_dir.join(annotations::ANNOTATIONS_DIR),
+            ));
+            app.manage(recent::RecentStore::load(
+                data_dir.join(recent::RECENT_FILE),
+            ));
+            app.manage(reading_position::ReadingPositionStore::load(
+                data_dir.join(reading_position::READING_POSITIONS_FILE),
+            ));
+
+            // Receive files from later launches
+            if let Some(listener) = listener {
+                single_instance::serve(app_handle.clone(), listener);
+            }
+
             // Create and set application menu
             let menu = menu::create_menu(app.handle())?;
             app.set_menu(menu)?;
@@ -184,24 +443,22 @@ pub fn run() {
                 let app_handle_for_open = app_handle.clone();
                 app_handle.listen("tauri://file-open", move |event| {
                     let payload = event.payload();
-                    if let Some(payload) =
-                        payload_from_file_paths(paths_from_legacy_file_open_payload(payload), None)
-                    {
+                    if let Some(payload) = payload_from_open_requests(
+                        requests_from_legacy_file_open_payload(payload),
+                        None,
+                    ) {
                         dispatch_open_payload(&app_handle_for_open, payload);
                     }
                 });
             }
 
             // If files were provided via CLI, emit event to frontend
-            if !cli.files.is_empty() {
-                let paths = cli.files.into_iter().map(PathBuf::from);
-                if let Some(payload) = payload_from_file_paths(paths, cli.page) {
-                    #[cfg(debug_assertions)]
-                    println!("Opening files from CLI: {:?}", payload.files);
-
-                    // Store and emit event (frontend will also pull pending on ready)
-                    dispatch_open_payload(&app_handle, payload);
-                }
+            if let Some(payload) = cli_payload {
+                #[cfg(debug_assertions)]
+                println!("Opening files from CLI: {:?}", payload.files);
+
+                // Store and emit event (frontend will also pull pending on ready)
+                dispatch_open_payload(app_handle, payload);
             }
 
             // Show window after setup complete
@@ -233,13 +490,26 @@ pub fn run() {
 mod tests {
     use super::*;
 
+    fn target_for<'a>(payload: &'a CliPayload, file: &str) -> Option<&'a open_target::FileTarget> {
+        payload.targets.iter().find(|target| target.file == file)
+    }
+
+    fn page_target(file: &str, page: u32) -> open_target::FileTarget {
+        open_target::FileTarget {
+            file: file.to_string(),
+            page: Some(page),
+            target: Some(open_target::OpenTarget::Page(page)),
+            ..open_target::FileTarget::default()
+        }
+    }
+
     #[test]
     fn test_pending_cli_payload_flow() {
         let state = PendingCliPayload(Mutex::new(None));
 
         let payload = CliPayload {
             files: vec!["/tmp/a.pdf".to_string()],
-            page: Some(2),
+            targets: vec![page_target("/tmp/a.pdf", 2)],
         };
         store_pending_payload_inner(&state, payload.clone());
         let taken = take_cli_payload_inner(&state).expect("payload should be present");
@@ -248,17 +518,141 @@ mod tests {
 
         let payload_a = CliPayload {
             files: vec!["/tmp/one.pdf".to_string()],
-            page: None,
+            targets: Vec::new(),
         };
         let payload_b = CliPayload {
             files: vec!["/tmp/two.pdf".to_string()],
-            page: Some(7),
+            targets: vec![page_target("/tmp/two.pdf", 7)],
         };
         store_pending_payload_inner(&state, payload_a);
         store_pending_payload_inner(&state, payload_b);
         let merged = take_cli_payload_inner(&state).expect("merged payload should be present");
         assert_eq!(merged.files, vec!["/tmp/one.pdf", "/tmp/two.pdf"]);
-        assert_eq!(merged.page, Some(7));
+        assert_eq!(target_for(&merged, "/tmp/one.pdf"), None);
+        assert_eq!(
+            target_for(&merged, "/tmp/two.pdf").and_then(|target| target.page),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_merged_payloads_keep_each_files_target() {
+        let state = PendingCliPayload(Mutex::new(None));
+
+        store_pending_payload_inner(
+            &state,
+            CliPayload {
+                files: vec!["/tmp/a.pdf".to_string(), "/tmp/b.pdf".to_string()],
+                targets: vec![page_target("/tmp/a.pdf", 12), page_target("/tmp/b.pdf", 3)],
+            },
+        );
+        store_pending_payload_inner(
+            &state,
+            CliPayload {
+                files: vec!["/tmp/c.pdf".to_string(), "/tmp/b.pdf".to_string()],
+                targets: vec![page_target("/tmp/c.pdf", 5), page_target("/tmp/b.pdf", 9)],
+            },
+        );
+
+        let merged = take_cli_payload_inner(&state).unwrap();
+        let page = |file: &str| target_for(&merged, file).and_then(|target| target.page);
+        assert_eq!(page("/tmp/a.pdf"), Some(12));
+        assert_eq!(page("/tmp/c.pdf"), Some(5));
+        // The later request for the same file wins
+        assert_eq!(page("/tmp/b.pdf"), Some(9));
+        assert_eq!(merged.targets.len(), 3);
+    }
+
+    #[test]
+    fn test_each_file_opens_at_its_own_target() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let outline = fixtures.join("outline.pdf").to_string_lossy().to_string();
+        let paper = fixtures
+            .join("notes/paper.pdf")
+            .to_string_lossy()
+            .to_string();
+        let untitled = fixtures.join("untitled").to_string_lossy().to_string();
+
+        let cli = Cli::try_parse_from([
+            "monight".to_string(),
+            format!("{}:3", outline),
+            format!("{}#search=results&zoom=150&viewmode=continuous", paper),
+            untitled.clone(),
+        ])
+        .unwrap();
+        let payload = payload_from_open_requests(
+            cli.files
+                .iter()
+                .filter_map(|file| deep_link::parse_open_candidate(file)),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            payload.files,
+            vec![outline.clone(), paper.clone(), untitled.clone()]
+        );
+        assert_eq!(
+            target_for(&payload, &outline),
+            Some(&page_target(&outline, 3))
+        );
+        assert_eq!(
+            target_for(&payload, &paper),
+            Some(&open_target::FileTarget {
+                file: paper.clone(),
+                page: Some(2),
+                zoom: Some(1.5),
+                view_mode: Some(open_target::ViewMode::Continuous),
+                target: Some(open_target::OpenTarget::Search("results".to_string())),
+                synctex: None,
+            })
+        );
+        assert_eq!(target_for(&payload, &untitled), None);
+
+        // An option from the command line overrides the first file's own target only
+        let payload = payload_from_open_requests(
+            cli.files
+                .iter()
+                .filter_map(|file| deep_link::parse_open_candidate(file)),
+            Some(open_target::OpenTarget::PageLabel("1".to_string())),
+        )
+        .unwrap();
+        assert_eq!(target_for(&payload, &outline).unwrap().page, Some(1));
+        assert_eq!(target_for(&payload, &paper).unwrap().page, Some(2));
+    }
+
+    #[test]
+    fn test_forwarded_payloads_keep_only_openable_files() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let outline = fixtures.join("outline.pdf").to_string_lossy().to_string();
+        let indirect = fixtures
+            .join("notes/../outline.pdf")
+            .to_string_lossy()
+            .to_string();
+        let not_a_pdf = fixtures.join("not_a_pdf.pdf").to_string_lossy().to_string();
+
+        let payload = CliPayload {
+            files: vec![
+                indirect.clone(),
+                not_a_pdf.clone(),
+                "/no/such.pdf".to_string(),
+            ],
+            targets: vec![
+                page_target(&indirect, 2),
+                page_target(&not_a_pdf, 1),
+                page_target("/elsewhere.pdf", 1),
+            ],
+        };
+
+        let payload = payload.into_openable().unwrap();
+        assert_eq!(payload.files, vec![outline.clone()]);
+        assert_eq!(payload.targets, vec![page_target(&outline, 2)]);
+
+        let nothing = CliPayload {
+            files: vec![not_a_pdf],
+            targets: Vec::new(),
+        };
+        assert_eq!(nothing.into_openable(), None);
     }
 
     #[test]
@@ -266,14 +660,14 @@ mod tests {
         let fixture =
             std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pdf");
 
-        let payload = payload_from_file_paths(
-            paths_from_legacy_file_open_payload(fixture.to_string_lossy().as_ref()),
+        let payload = payload_from_open_requests(
+            requests_from_legacy_file_open_payload(fixture.to_string_lossy().as_ref()),
             None,
         )
         .expect("raw path payload should be accepted");
 
         assert_eq!(payload.files, vec![fixture.to_string_lossy().to_string()]);
-        assert_eq!(payload.page, None);
+        assert!(payload.targets.is_empty());
     }
 
     #[test]
@@ -282,12 +676,72 @@ mod tests {
             std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pdf");
         let file_url = url::Url::from_file_path(&fixture).expect("fixture should become file URL");
 
-        let payload =
-            payload_from_file_paths(paths_from_legacy_file_open_payload(file_url.as_str()), None)
-                .expect("file URL payload should be accepted");
+        let payload = payload_from_open_requests(
+            requests_from_legacy_file_open_payload(file_url.as_str()),
+            None,
+        )
+        .expect("file URL payload should be accepted");
 
         assert_eq!(payload.files, vec![fixture.to_string_lossy().to_string()]);
-        assert_eq!(payload.page, None);
+        assert!(payload.targets.is_empty());
+    }
+
+    #[test]
+    fn test_legacy_file_open_payload_keeps_fragments() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline.pdf");
+        let file = fixture.to_string_lossy().to_string();
+        let mut file_url = url::Url::from_file_path(&fixture).unwrap();
+        file_url.set_fragment(Some("page=2"));
+        let list = serde_json::to_string(&[format!("{}#page=3", file)]).unwrap();
+
+        for payload in [file_url.as_str(), list.as_str()] {
+            let payload =
+                payload_from_open_requests(requests_from_legacy_file_open_payload(payload), None)
+                    .expect("fragment payload should be accepted");
+            assert_eq!(payload.files, vec![file.clone()]);
+            assert!(target_for(&payload, &file).unwrap().page.is_some());
+        }
+    }
+
+    #[test]
+    fn test_payload_from_file_paths_filters_by_content() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+        let payload = payload_from_file_paths(
+            [fixtures.join("untitled"), fixtures.join("not_a_pdf.pdf")],
+            None,
+        )
+        .expect("extension-less PDF should be accepted");
+
+        assert_eq!(
+            payload.files,
+            vec![fixtures.join("untitled").to_string_lossy().to_string()]
+        );
+    }
+
+    #[test]
+    fn test_forward_search_sets_page_and_region() {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/synctex");
+        let pdf = fixtures.join("paper.pdf").to_string_lossy().to_string();
+        let mut payload = CliPayload {
+            files: vec![pdf.clone()],
+            targets: vec![page_target(&pdf, 1)],
+        };
+
+        let search = "12:0:main.tex".parse().unwrap();
+        apply_forward_search(&mut payload, &search).expect("line 12 is typeset");
+
+        let target = target_for(&payload, &pdf).unwrap();
+        assert_eq!(target.page, Some(2));
+        assert_eq!(
+            target.synctex.as_ref().map(|position| position.page),
+            Some(2)
+        );
+
+        let search = "3:0:unknown.tex".parse().unwrap();
+        assert!(apply_forward_search(&mut payload, &search).is_err());
     }
 
     #[test]
@@ -300,6 +754,63 @@ mod tests {
             payload_from_opened_urls(&[file_url]).expect("opened file URL should be accepted");
 
         assert_eq!(payload.files, vec![fixture.to_string_lossy().to_string()]);
-        assert_eq!(payload.page, None);
+        assert!(payload.targets.is_empty());
+    }
+
+    #[test]
+    fn test_deep_links_open_at_their_target() {
+        use open_target::OpenTarget;
+
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline.pdf");
+        let mut link = url::Url::parse("monight://open").unwrap();
+        link.query_pairs_mut()
+            .append_pair("file", &fixture.to_string_lossy())
+            .append_pair("page", "1")
+            .append_pair("dest", "Intro");
+
+        // The named destination wins over the page and resolves before dispatch
+        let file = fixture.to_string_lossy().to_string();
+        let payload = payload_from_opened_urls(&[link.clone()]).expect("deep link should open");
+        assert_eq!(payload.files, vec![file.clone()]);
+        let target = target_for(&payload, &file).unwrap();
+        assert_eq!(target.page, Some(3));
+        assert_eq!(
+            target.target,
+            Some(OpenTarget::Destination("Intro".to_string()))
+        );
+
+        // A target from the command line wins over the link's
+        let payload = payload_from_open_requests(
+            [link.as_str(), "/no/such/file.pdf"]
+                .into_iter()
+                .filter_map(deep_link::parse_open_candidate),
+            Some(OpenTarget::PageLabel("2".to_string())),
+        )
+        .unwrap();
+        assert_eq!(payload.files.len(), 1);
+        assert_eq!(target_for(&payload, &file).unwrap().page, Some(2));
+
+        // Targets that are not in the document leave the page unset
+        let payload = payload_from_open_requests(
+            [deep_link::OpenRequest::from_path(fixture)],
+            Some(OpenTarget::Destination("nowhere".to_string())),
+        )
+        .unwrap();
+        let target = target_for(&payload, &file).unwrap();
+        assert_eq!(target.page, None);
+        assert!(target.target.is_some());
+    }
+
+    #[test]
+    fn test_target_options_are_mutually_exclusive() {
+        let cli = Cli::try_parse_from(["monight", "spec.pdf", "--page-label", "A-12"]).unwrap();
+        assert_eq!(cli.page_label.as_deref(), Some("A-12"));
+
+        assert!(Cli::try_parse_from(["monight", "a.pdf", "--page", "2", "--dest", "x"]).is_err());
+        assert!(
+            Cli::try_parse_from(["monight", "a.pdf", "--search", "x", "--page-label", "i"])
+                .is_err()
+        );
     }
 }
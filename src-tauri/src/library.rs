@@ -0,0 +1,543 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::document_kind::{detect_document_kind, DocumentKind};
+use crate::error::MonightError;
+use crate::pdf::load_document;
+use crate::watcher::FileSnapshot;
+
+/// File in the app data directory that holds the index.
+pub const INDEX_FILE: &str = "library-index.json";
+/// Bumped whenever the on-disk layout or tokenization changes; older indexes are rebuilt.
+const INDEX_VERSION: u32 = 1;
+/// Characters of context shown on each side of a match.
+const SNIPPET_CONTEXT: usize = 60;
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+struct IndexedDocument {
+    path: String,
+    snapshot: FileSnapshot,
+    /// Extracted text of each page, kept for snippets and to rebuild postings without re-parsing
+    pages: Vec<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Posting {
+    document: u32,
+    /// Zero-based page index
+    page: u32,
+}
+
+/// On-disk inverted index over the text of every PDF in the library folders.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct LibraryIndex {
+    version: u32,
+    folders: Vec<String>,
+    documents: Vec<IndexedDocument>,
+    terms: BTreeMap<String, Vec<Posting>>,
+}
+
+/// A page matching a library search.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct SearchHit {
+    pub file: String,
+    /// 1-based page number, as used by `CliPayload::page`
+    pub page: u32,
+    pub snippet: String,
+}
+
+/// Outcome of an index update.
+#[derive(Clone, Serialize, Debug, Default, PartialEq)]
+pub struct IndexStats {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+    pub documents: usize,
+}
+
+/// Lowercased alphanumeric runs; everything else separates terms.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// A window of `text` around the first occurrence of `term`, with whitespace collapsed.
+pub(crate) fn snippet(text: &str, term: &str) -> String {
+    let chars = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let chars = chars.chars().collect::<Vec<_>>();
+    let lowered = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let needle = term.chars().collect::<Vec<_>>();
+
+    let position = lowered
+        .windows(needle.len().max(1))
+        .position(|window| window == needle.as_slice())
+        .unwrap_or(0);
+    let start = position.saturating_sub(SNIPPET_CONTEXT);
+    let end = (position + needle.len() + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut snippet = chars[start..end].iter().collect::<String>();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Every PDF below `folder`, by content rather than extension. Hidden entries
+/// and symlinked directories (which could loop) are skipped.
+fn collect_pdfs(folder: &Path, found: &mut BTreeSet<PathBuf>) {
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push(path),
+                Ok(kind) if kind.is_symlink() && path.is_dir() => {}
+                Ok(_) => {
+                    if matches!(detect_document_kind(&path), Ok(Some(DocumentKind::Pdf))) {
+                        if let Ok(canonical) = std::fs::canonicalize(&path) {
+                            found.insert(canonical);
+                        }
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// Extract the text of every page of a PDF. Pages that fail to decode are indexed as empty.
+pub(crate) fn extract_page_texts(path: &Path) -> Result<Vec<String>, MonightError> {
+    let mut doc = load_document(path)?;
+    if doc.is_encrypted() && doc.decrypt("").is_err() {
+        return Err(MonightError::InvalidPdf {
+            path: path.to_string_lossy().to_string(),
+            message: "document is encrypted".to_string(),
+        });
+    }
+
+    let page_count = doc.get_pages().len() as u32;
+    Ok((1..=page_count)
+        .map(|page| doc.extract_text(&[page]).unwrap_or_default())
+        .collect())
+}
+
+impl LibraryIndex {
+    /// Read the index from disk, starting fresh if it is missing, corrupt or outdated.
+    pub fn load(path: &Path) -> Self {
+        let index = std::fs::read(path).ok().and_then(|bytes| {
+            match serde_json::from_slice::<LibraryIndex>(&bytes) {
+                Ok(index) => Some(index),
+                Err(e) => {
+                    eprintln!("Discarding unreadable library index: {}", e);
+                    None
+                }
+            }
+        });
+
+        match index {
+            Some(index) if index.version == INDEX_VERSION => index,
+            // Keep the user's folders so the next update rebuilds the same library
+            Some(index) => LibraryIndex {
+                version: INDEX_VERSION,
+                folders: index.folders,
+                ..Default::default()
+            },
+            None => LibraryIndex {
+                version: INDEX_VERSION,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Write the index through a temporary file so a crash never leaves it half-written.
+    pub fn save(&self, path: &Path) -> Result<(), MonightError> {
+        crate::persist::save_json(path, self)
+    }
+
+    pub fn folders(&self) -> &[String] {
+        &self.folders
+    }
+
+    pub fn add_folder(&mut self, folder: &Path) {
+        let folder = folder.to_string_lossy().to_string();
+        if !self.folders.contains(&folder) {
+            self.folders.push(folder);
+            self.folders.sort();
+        }
+    }
+
+    /// Forget a folder and every document that no remaining folder covers.
+    pub fn remove_folder(&mut self, folder: &Path) {
+        self.folders
+            .retain(|existing| Path::new(existing) != folder);
+        self.drop_uncovered();
+    }
+
+    /// Remove documents that are in none of the library folders.
+    fn drop_uncovered(&mut self) {
+        let folders = self.folders.iter().map(PathBuf::from).collect::<Vec<_>>();
+        self.documents.retain(|document| {
+            folders
+                .iter()
+                .any(|folder| Path::new(&document.path).starts_with(folder))
+        });
+        self.rebuild_terms();
+    }
+
+    /// Bring the index in line with the library folders, re-extracting only
+    /// files whose size or modification time changed.
+    pub fn update<F>(&mut self, extract: F) -> IndexStats
+    where
+        F: Fn(&Path) -> Result<Vec<String>, MonightError>,
+    {
+        let mut found = BTreeSet::new();
+        for folder in &self.folders {
+            collect_pdfs(Path::new(folder), &mut found);
+        }
+
+        let mut previous = self
+            .documents
+            .drain(..)
+            .map(|document| (PathBuf::from(&document.path), document))
+            .collect::<HashMap<_, _>>();
+        let mut stats = IndexStats::default();
+
+        for path in found {
+            let Some(snapshot) = FileSnapshot::read(&path) else {
+                continue;
+            };
+            if let Some(document) = previous
+                .remove(&path)
+                .filter(|document| document.snapshot == snapshot)
+            {
+                stats.unchanged += 1;
+                self.documents.push(document);
+                continue;
+            }
+
+            match extract(&path) {
+                Ok(pages) => {
+                    stats.indexed += 1;
+                    self.documents.push(IndexedDocument {
+                        path: path.to_string_lossy().to_string(),
+                        snapshot,
+                        pages,
+                    });
+                }
+                Err(e) => {
+                    eprintln!("Skipping {} in library index: {}", path.display(), e);
+                    stats.failed += 1;
+                }
+            }
+        }
+
+        stats.removed = previous.len();
+        stats.documents = self.documents.len();
+        self.documents.sort_by(|a, b| a.path.cmp(&b.path));
+        self.rebuild_terms();
+        stats
+    }
+
+    fn rebuild_terms(&mut self) {
+        let mut terms = BTreeMap::<String, Vec<Posting>>::new();
+        for (document, indexed) in self.documents.iter().enumerate() {
+            for (page, text) in indexed.pages.iter().enumerate() {
+                let posting = Posting {
+                    document: document as u32,
+                    page: page as u32,
+                };
+                for term in tokenize(text).into_iter().collect::<BTreeSet<_>>() {
+                    terms.entry(term).or_default().push(posting);
+                }
+            }
+        }
+        self.terms = terms;
+    }
+
+    /// Pages containing a term starting with each query word, so `lm317`
+    /// also finds `LM317T`.
+    fn postings_for_prefix(&self, prefix: &str) -> BTreeSet<Posting> {
+        self.terms
+            .range(prefix.to_string()..)
+            .take_while(|(term, _)| term.starts_with(prefix))
+            .flat_map(|(_, postings)| postings.iter().copied())
+            .collect()
+    }
+
+    /// Pages that contain every word of `query`, in library order.
+    pub fn search(&self, query: &str, limit: Option<usize>) -> Vec<SearchHit> {
+        let words = tokenize(query);
+        let Some(first) = words.first() else {
+            return Vec::new();
+        };
+
+        let mut matches = self.postings_for_prefix(first);
+        for word in &words[1..] {
+            let postings = self.postings_for_prefix(word);
+            matches.retain(|posting| postings.contains(posting));
+        }
+
+        matches
+            .into_iter()
+            .take(limit.unwrap_or(DEFAULT_LIMIT))
+            .filter_map(|posting| {
+                let document = self.documents.get(posting.document as usize)?;
+                let text = document.pages.get(posting.page as usize)?;
+                Some(SearchHit {
+                    file: document.path.clone(),
+                    page: posting.page + 1,
+                    snippet: snippet(text, first),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Lazily loaded library index shared by the search commands.
+pub struct Library {
+    path: PathBuf,
+    index: Mutex<Option<LibraryIndex>>,
+    /// Serializes updates so two rescans never race to write the index
+    updating: Mutex<()>,
+}
+
+impl Library {
+    pub fn new(path: PathBuf) -> Self {
+        Library {
+            path,
+            index: Mutex::new(None),
+            updating: Mutex::new(()),
+        }
+    }
+
+    pub fn with_index<T>(&self, f: impl FnOnce(&mut LibraryIndex) -> T) -> T {
+        let mut guard = self.index.lock().unwrap();
+        f(guard.get_or_insert_with(|| LibraryIndex::load(&self.path)))
+    }
+
+    /// Apply a change to the index and persist it.
+    pub fn modify<T>(&self, f: impl FnOnce(&mut LibraryIndex) -> T) -> Result<T, MonightError> {
+        self.with_index(|index| {
+            let result = f(index);
+            index.save(&self.path)?;
+            Ok(result)
+        })
+    }
+
+    /// Rescan the library folders. Extraction runs on a copy so searches
+    /// keep answering from the previous index in the meantime.
+    pub fn update(&self) -> Result<IndexStats, MonightError> {
+        self.update_with(extract_page_texts)
+    }
+
+    fn update_with<F>(&self, extract: F) -> Result<IndexStats, MonightError>
+    where
+        F: Fn(&Path) -> Result<Vec<String>, MonightError>,
+    {
+        let _updating = self.updating.lock().unwrap();
+        let mut next = self.with_index(|index| index.clone());
+        let mut stats = next.update(extract);
+
+        self.with_index(|index| {
+            // Folder edits made during the rescan win: documents of folders
+            // removed meanwhile are dropped, and added folders wait for the next update
+            next.folders = std::mem::take(&mut index.folders);
+            next.drop_uncovered();
+            stats.documents = next.documents.len();
+            *index = next;
+            index.save(&self.path)
+        })?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/library")
+    }
+
+    /// Fresh copy of the library fixtures that tests may modify.
+    fn library_copy(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("monight-library-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sensors")).unwrap();
+        for file in ["regulators.pdf", "README.txt", "sensors/temperature.pdf"] {
+            std::fs::copy(fixtures().join(file), dir.join(file)).unwrap();
+        }
+        std::fs::canonicalize(dir).unwrap()
+    }
+
+    fn fresh_index(folder: &Path) -> LibraryIndex {
+        let mut index = LibraryIndex {
+            version: INDEX_VERSION,
+            ..Default::default()
+        };
+        index.add_folder(folder);
+        index
+    }
+
+    #[test]
+    fn test_tokenize_splits_on_punctuation_and_lowercases() {
+        assert_eq!(
+            tokenize("LM317T, XYZ-100 (Ünïcode)"),
+            vec!["lm317t", "xyz", "100", "ünïcode"]
+        );
+        assert!(tokenize(" -- ").is_empty());
+    }
+
+    #[test]
+    fn test_snippet_centers_on_match() {
+        let text = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+
+        let snippet = snippet(&text, "needle");
+
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+        assert!(snippet.chars().count() <= 2 * SNIPPET_CONTEXT + "needle".len() + 2);
+        assert_eq!(super::snippet("Short  text", "short"), "Short text");
+    }
+
+    #[test]
+    fn test_extract_page_texts_reads_each_page() {
+        let pages = extract_page_texts(&fixtures().join("regulators.pdf")).unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].contains("Linear Regulators"));
+        assert!(pages[1].contains("LM317T"));
+    }
+
+    #[test]
+    fn test_update_indexes_folder_and_search_finds_pages() {
+        let dir = library_copy("search");
+        let mut index = fresh_index(&dir);
+
+        let stats = index.update(extract_page_texts);
+        assert_eq!(stats.indexed, 2, "README.txt is not a PDF");
+        assert_eq!(stats.documents, 2);
+
+        let hits = index.search("xyz-100", None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].file,
+            dir.join("regulators.pdf").to_string_lossy().to_string()
+        );
+        assert_eq!(hits[0].page, 2);
+        assert!(hits[0].snippet.contains("XYZ-100"));
+
+        // Prefix matching and AND semantics
+        assert_eq!(index.search("lm317", None).len(), 1);
+        assert_eq!(index.search("regulator", None).len(), 2);
+        assert!(index.search("regulator tmp36", None).is_empty());
+        assert_eq!(index.search("tmp36", None)[0].page, 1);
+        assert!(index.search("   ", None).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_only_reextracts_changed_files() {
+        let dir = library_copy("incremental");
+        let mut index = fresh_index(&dir);
+        let calls = AtomicUsize::new(0);
+        let extract = |path: &Path| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            extract_page_texts(path)
+        };
+
+        index.update(extract);
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+
+        let stats = index.update(extract);
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 0);
+        assert_eq!(stats.unchanged, 2);
+
+        // Appending changes the size, so the file is re-extracted
+        let sensors = dir.join("sensors/temperature.pdf");
+        let mut bytes = std::fs::read(&sensors).unwrap();
+        bytes.extend_from_slice(b"\n% touched\n");
+        std::fs::write(&sensors, bytes).unwrap();
+        std::fs::remove_file(dir.join("regulators.pdf")).unwrap();
+
+        let stats = index.update(extract);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            stats,
+            IndexStats {
+                indexed: 1,
+                unchanged: 0,
+                removed: 1,
+                failed: 0,
+                documents: 1,
+            }
+        );
+        assert!(index.search("lm317t", None).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_folders_removed_during_a_rescan_stay_removed() {
+        let dir = library_copy("removed-mid-rescan");
+        let file = dir.join("index").join(INDEX_FILE);
+        let library = Library::new(file.clone());
+        library.modify(|index| index.add_folder(&dir)).unwrap();
+
+        let stats = library
+            .update_with(|path| {
+                library.modify(|index| index.remove_folder(&dir)).unwrap();
+                extract_page_texts(path)
+            })
+            .unwrap();
+
+        assert_eq!(stats.documents, 0);
+        assert!(library.with_index(|index| index.search("tmp36", None).is_empty()));
+        assert!(LibraryIndex::load(&file).search("tmp36", None).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_round_trips_through_disk_and_folders_can_be_removed() {
+        let dir = library_copy("persist");
+        let file = dir.join("index").join(INDEX_FILE);
+        let mut index = fresh_index(&dir);
+        index.update(extract_page_texts);
+        index.save(&file).unwrap();
+
+        let mut loaded = LibraryIndex::load(&file);
+        assert_eq!(loaded.folders(), index.folders());
+        assert_eq!(loaded.search("tmp36", None), index.search("tmp36", None));
+
+        loaded.remove_folder(&dir);
+        assert!(loaded.folders().is_empty());
+        assert!(loaded.search("tmp36", None).is_empty());
+
+        std::fs::write(&file, b"{ not json").unwrap();
+        assert!(LibraryIndex::load(&file).folders().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
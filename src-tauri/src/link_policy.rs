@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::error::MonightError;
+use crate::settings::setting;
+
+/// Schemes that run code or reach local files, which no policy can enable.
+const FORBIDDEN_SCHEMES: &[&str] = &["javascript", "vbscript", "data", "file", "blob"];
+
+/// What happens when a link is activated.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkDecision {
+    #[default]
+    Allow,
+    Confirm,
+    Block,
+}
+
+/// Which external links open straight away, which ask first and which never open.
+///
+/// Patterns are a host (`example.com`), a host and its subdomains
+/// (`*.example.com`), a scheme (`zotero:`), both (`https://*.example.com`) or
+/// `*` for everything. Email links match on the domains of their addresses.
+/// When patterns from several lists match, the strictest list wins.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LinkPolicy {
+    pub allow: Vec<String>,
+    pub confirm: Vec<String>,
+    pub block: Vec<String>,
+    /// Schemes that may open besides http, https and mailto, e.g. `zotero` or `obsidian`
+    pub extra_schemes: Vec<String>,
+    /// Decision for links no pattern matches
+    pub fallback: LinkDecision,
+}
+
+/// What `open_external_url` did with a link.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(tag = "outcome", rename_all = "camelCase")]
+pub enum LinkOutcome {
+    Opened,
+    /// The policy wants the user to see `url`, the real target, before it opens
+    Confirm {
+        url: String,
+    },
+}
+
+fn invalid_url(reason: &str) -> MonightError {
+    MonightError::InvalidUrl {
+        reason: reason.to_string(),
+    }
+}
+
+/// Domains a link points at: the host, or the domain of each email address.
+fn link_domains(url: &Url) -> Vec<String> {
+    if url.scheme() == "mailto" {
+        return url
+            .path()
+            .split(',')
+            .filter_map(|address| address.rsplit_once('@'))
+            .map(|(_, domain)| domain.trim().to_ascii_lowercase())
+            .collect();
+    }
+    url.host_str()
+        .map(|host| host.trim_end_matches('.').to_string())
+        .into_iter()
+        .collect()
+}
+
+fn host_matches(pattern: &str, domain: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(parent) => domain == parent || domain.ends_with(&format!(".{}", parent)),
+        None => domain == pattern,
+    }
+}
+
+fn pattern_matches(pattern: &str, url: &Url) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let is_scheme = |scheme: &str| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    };
+    let (scheme, host) = match pattern.split_once(':') {
+        Some((scheme, rest)) if is_scheme(scheme) => (Some(scheme), rest.trim_start_matches("//")),
+        _ => (None, pattern.as_str()),
+    };
+
+    if scheme.is_some_and(|scheme| scheme != url.scheme()) {
+        return false;
+    }
+    match host {
+        "" | "*" => true,
+        host => link_domains(url)
+            .iter()
+            .any(|domain| host_matches(host, domain)),
+    }
+}
+
+impl LinkPolicy {
+    fn allows_scheme(&self, scheme: &str) -> bool {
+        !FORBIDDEN_SCHEMES.contains(&scheme)
+            && self.extra_schemes.iter().any(|extra| {
+                extra
+                    .trim()
+                    .trim_end_matches(':')
+                    .eq_ignore_ascii_case(scheme)
+            })
+    }
+
+    /// Parse a link, rejecting schemes the policy does not open and links
+    /// that cannot lead anywhere.
+    pub fn validate(&self, raw_url: &str) -> Result<Url, MonightError> {
+        let url = Url::parse(raw_url).map_err(|_| invalid_url("Invalid external link URL"))?;
+
+        match url.scheme() {
+            "http" | "https" => {
+                if url.host_str().is_none() {
+                    return Err(invalid_url("External web links must include a host"));
+                }
+            }
+            "mailto" => {
+                if url.path().trim().is_empty() {
+                    return Err(invalid_url("Email links must include an address"));
+                }
+            }
+            scheme if self.allows_scheme(scheme) => {}
+            scheme => {
+                return Err(MonightError::BlockedScheme {
+                    scheme: scheme.to_string(),
+                });
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// The normalized link and what to do with it.
+    pub fn evaluate(&self, raw_url: &str) -> Result<(Url, LinkDecision), MonightError> {
+        let url = self.validate(raw_url)?;
+        let decision = [
+            (LinkDecision::Block, &self.block),
+            (LinkDecision::Confirm, &self.confirm),
+            (LinkDecision::Allow, &self.allow),
+        ]
+        .into_iter()
+        .find(|(_, patterns)| {
+            patterns
+                .iter()
+                .any(|pattern| pattern_matches(pattern, &url))
+        })
+        .map_or(self.fallback, |(decision, _)| decision);
+        Ok((url, decision))
+    }
+}
+
+/// The policy from the settings. One that cannot be read asks before opening anything.
+pub fn load_policy(app: &AppHandle) -> LinkPolicy {
+    let Some(value) = setting(app, "linkPolicy") else {
+        return LinkPolicy::default();
+    };
+
+    serde_json::from_value(value).unwrap_or_else(|e| {
+        eprintln!("Invalid link policy in settings: {}", e);
+        LinkPolicy {
+            fallback: LinkDecision::Confirm,
+            ..LinkPolicy::default()
+        }
+    })
+}
@@ -6,6 +6,86 @@ use tauri::{
 // Import for opening URLs in browser
 use tauri_plugin_opener::OpenerExt;
 
+use crate::recent::{menu_labels, RecentStore};
+
+const RECENT_MENU_ID: &str = "open_recent";
+const RECENT_ITEM_PREFIX: &str = "recent:";
+
+/// Replace the contents of the "Open Recent" submenu with the current list.
+fn populate_recent_menu(app: &AppHandle, submenu: &Submenu<Wry>) -> Result<(), tauri::Error> {
+    while submenu.remove_at(0)?.is_some() {}
+
+    let documents = app.state::<RecentStore>().snapshot();
+    let entries = documents.entries();
+    if entries.is_empty() {
+        submenu.append(&MenuItem::with_id(
+            app,
+            "recent_empty",
+            "No Recent Documents",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    for (index, label) in menu_labels(entries).into_iter().enumerate() {
+        let id = format!("{}{}", RECENT_ITEM_PREFIX, index);
+        submenu.append(&MenuItem::with_id(
+            app,
+            id.as_str(),
+            label,
+            true,
+            None::<&str>,
+        )?)?;
+    }
+
+    submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    submenu.append(&MenuItem::with_id(
+        app,
+        "clear_recent",
+        "Clear Menu",
+        !entries.is_empty(),
+        None::<&str>,
+    )?)
+}
+
+fn build_recent_menu(app: &AppHandle) -> Result<Submenu<Wry>, tauri::Error> {
+    let submenu = Submenu::with_id_and_items(app, RECENT_MENU_ID, "Open Recent", true, &[])?;
+    populate_recent_menu(app, &submenu)?;
+    Ok(submenu)
+}
+
+/// Rebuild the "Open Recent" submenu after the recent list changed
+pub fn refresh_recent_menu(app: &AppHandle) -> Result<(), tauri::Error> {
+    let Some(menu) = app.menu() else {
+        return Ok(());
+    };
+
+    for item in menu.items()? {
+        let Some(recent) = item.as_submenu().and_then(|menu| menu.get(RECENT_MENU_ID)) else {
+            continue;
+        };
+        if let Some(recent) = recent.as_submenu() {
+            return populate_recent_menu(app, recent);
+        }
+    }
+    Ok(())
+}
+
+fn open_recent(app: &AppHandle, index: usize) {
+    let documents = app.state::<RecentStore>().snapshot();
+    let Some(entry) = documents.entries().get(index) else {
+        return;
+    };
+
+    match crate::payload_from_file_paths([std::path::PathBuf::from(&entry.path)], None) {
+        Some(payload) => crate::dispatch_open_payload(app, payload),
+        None => {
+            // The file went away since the menu was built
+            let _ =
+                crate::recent::change(app, |documents| documents.prune(std::path::Path::exists));
+        }
+    }
+}
+
 
 fn build_file_menu(app: &AppHandle) -> Result<Submenu<Wry>, tauri::Error> {
     Submenu::with_items(
@@ -14,6 +94,7 @@ fn build_file_menu(app: &AppHandle) -> Result<Submenu<Wry>, tauri::Error> {
         true,
         &[
             &MenuItem::with_id(app, "open", "Open...", true, Some("CmdOrCtrl+O"))?,
+            &build_recent_menu(app)?,
             &MenuItem::with_id(app, "print", "Print", true, Some("CmdOrCtrl+P"))?,
             &PredefinedMenuItem::separator(app)?,
             &PredefinedMenuItem::close_window(app, Some("Close"))?,
@@ -33,6 +114,7 @@ fn build_file_menu_with_settings(
         true,
         &[
             &MenuItem::with_id(app, "open", "Open...", true, Some("CmdOrCtrl+O"))?,
+            &build_recent_menu(app)?,
             &MenuItem::with_id(app, "print", "Print", true, Some("CmdOrCtrl+P"))?,
             &PredefinedMenuItem::separator(app)?,
             &MenuItem::with_id(app, "settings", settings_label, true, Some(settings_shortcut))?,
@@ -219,6 +301,11 @@ pub fn handle_menu_event(app: &AppHandle, event_id: &str) {
         "close_tab" => {
             emit_to_main(app, "menu-close-tab");
         }
+        "clear_recent" => {
+            if let Err(e) = crate::recent::change(app, |documents| documents.clear()) {
+                eprintln!("Error clearing recent documents: {}", e);
+            }
+        }
         "learn_more" => {
             // Open GitHub repo in browser (placeholder URL)
             let _ = app.opener().open_url("https://github.com/yourusername/yourrepo", None::<&str>);
@@ -241,6 +328,13 @@ pub fn handle_menu_event(app: &AppHandle, event_id: &str) {
             // Open email client (placeholder email)
             let _ = app.opener().open_url("mailto:your-email@example.com", None::<&str>);
         }
-        _ => {}
+        id => {
+            if let Some(index) = id
+                .strip_prefix(RECENT_ITEM_PREFIX)
+                .and_then(|index| index.parse().ok())
+            {
+                open_recent(app, index);
+            }
+        }
     }
 }
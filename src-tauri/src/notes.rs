@@ -0,0 +1,273 @@
+use lopdf::{Document, ObjectId};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::annotations::AnnotationStore;
+use crate::deep_link::open_link;
+use crate::error::MonightError;
+use crate::form_data::Annotation;
+use crate::outline::{document_outline, OutlineItem};
+use crate::page_text::text_in_quads;
+use crate::pdf::{dict_get, info_dictionary, load_document, text_string};
+use crate::xfdf::{hex_color, ANNOTATION_ELEMENTS};
+
+/// Markup subtypes whose marked text is quoted in the notes.
+const TEXT_MARKUP: &[&str] = &["Highlight", "Underline", "Squiggly", "StrikeOut"];
+
+/// An outline entry that notes can be grouped under.
+struct Section {
+    title: String,
+    depth: usize,
+    page: u32,
+}
+
+fn collect_sections(items: &[OutlineItem], depth: usize, out: &mut Vec<Section>) {
+    for item in items {
+        if let Some(page) = item.page_index {
+            out.push(Section {
+                title: item.title.clone(),
+                depth,
+                page,
+            });
+        }
+        collect_sections(&item.children, depth + 1, out);
+    }
+}
+
+/// Markup annotations already in the PDF, such as ones added by another viewer.
+fn pdf_annotations(doc: &Document) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for (number, page_id) in doc.get_pages() {
+        let Ok(annots) = doc.get_page_annotations(page_id) else {
+            continue;
+        };
+        for annot in annots {
+            let markup = dict_get(doc, annot, b"Subtype")
+                .and_then(|subtype| subtype.as_name().ok())
+                .is_some_and(|subtype| {
+                    ANNOTATION_ELEMENTS
+                        .iter()
+                        .any(|(_, known)| known.as_bytes() == subtype)
+                });
+            if !markup {
+                continue;
+            }
+            if let Some(annotation) = crate::fdf::annotation(doc, annot) {
+                annotations.push(Annotation {
+                    page: number - 1,
+                    ..annotation
+                });
+            }
+        }
+    }
+    annotations
+}
+
+/// The text a markup annotation covers, or its rectangle when it has no quads.
+fn marked_text(doc: &Document, page_id: ObjectId, annotation: &Annotation) -> String {
+    if annotation.quad_points.is_empty() {
+        let [x1, y1, x2, y2] = annotation.rect;
+        text_in_quads(doc, page_id, &[x1, y2, x2, y2, x1, y1, x2, y1])
+    } else {
+        text_in_quads(doc, page_id, &annotation.quad_points)
+    }
+}
+
+fn write_note(
+    out: &mut String,
+    doc: &Document,
+    pages: &BTreeMap<u32, ObjectId>,
+    document: &Path,
+    annotation: &Annotation,
+) {
+    let page = annotation.page + 1;
+    let mut details = vec![
+        format!("[Page {}]({})", page, open_link(document, page)),
+        annotation.subtype.clone(),
+    ];
+    details.extend(annotation.color.map(hex_color));
+    details.extend(annotation.author.clone());
+    let _ = writeln!(out, "- {}", details.join(" · "));
+
+    if TEXT_MARKUP.contains(&annotation.subtype.as_str()) {
+        if let Some(&page_id) = pages.get(&page) {
+            let text = marked_text(doc, page_id, annotation);
+            if !text.is_empty() {
+                let _ = write!(out, "\n  > {}\n", text);
+            }
+        }
+    }
+    if let Some(contents) = annotation.contents.as_deref().map(str::trim) {
+        if !contents.is_empty() {
+            out.push('\n');
+            for line in contents.lines() {
+                let _ = writeln!(out, "  {}", line.trim_end());
+            }
+        }
+    }
+    out.push('\n');
+}
+
+/// Render annotations as Markdown, grouped under the outline section each one
+/// falls in, or by page when the document has no outline.
+pub(crate) fn notes_markdown(
+    doc: &Document,
+    document: &Path,
+    mut annotations: Vec<Annotation>,
+) -> String {
+    let title = info_dictionary(doc)
+        .and_then(|info| dict_get(doc, info, b"Title"))
+        .and_then(text_string)
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| {
+            document
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+    let mut out = format!("# {}\n\n", title);
+
+    // Reading order: by page, then top to bottom, then left to right
+    annotations.sort_by(|a, b| {
+        a.page
+            .cmp(&b.page)
+            .then(b.rect[3].total_cmp(&a.rect[3]))
+            .then(a.rect[0].total_cmp(&b.rect[0]))
+    });
+
+    let mut sections = Vec::new();
+    collect_sections(&document_outline(doc), 0, &mut sections);
+    sections.sort_by_key(|section| section.page);
+
+    let pages = doc.get_pages();
+    let mut current: Option<usize> = None;
+    for annotation in &annotations {
+        let heading = if sections.is_empty() {
+            Some(annotation.page as usize)
+        } else {
+            sections
+                .iter()
+                .rposition(|section| section.page <= annotation.page)
+        };
+        if heading != current {
+            current = heading;
+            match heading {
+                Some(page) if sections.is_empty() => {
+                    let _ = writeln!(out, "## Page {}\n", page + 1);
+                }
+                Some(index) => {
+                    let section = &sections[index];
+                    let level = "#".repeat((section.depth + 2).min(6));
+                    let _ = writeln!(out, "{} {}\n", level, section.title);
+                }
+                None => {}
+            }
+        }
+        write_note(&mut out, doc, &pages, document, annotation);
+    }
+
+    if annotations.is_empty() {
+        out.push_str("No annotations.\n");
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Markdown notes for `document` from its stored annotations and the markup
+/// annotations inside the PDF. Stored ones win when both have the same name.
+pub fn export_notes(store: &AnnotationStore, document: &Path) -> Result<String, MonightError> {
+    let doc = load_document(document)?;
+    let mut annotations = store.load(document)?.annotations;
+    for embedded in pdf_annotations(&doc) {
+        let stored = embedded.name.is_some()
+            && annotations
+                .iter()
+                .any(|annotation| annotation.name == embedded.name);
+        if !stored {
+            annotations.push(embedded);
+        }
+    }
+    Ok(notes_markdown(&doc, document, annotations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{copy_fixture, temp_dir};
+
+    #[test]
+    fn test_notes_quote_marked_text_under_outline_sections() {
+        let dir = temp_dir("notes", "export");
+        let document = copy_fixture("notes/paper.pdf", &dir.join("my paper.pdf"));
+        let store = AnnotationStore::new(dir.join("app-data"));
+        crate::annotations::add_annotation(
+            &store,
+            &document,
+            Annotation {
+                subtype: "Highlight".to_string(),
+                rect: [100.0, 683.0, 181.0, 711.0],
+                contents: Some("Classic pangram.\nWorth citing.".to_string()),
+                color: Some([1.0, 1.0, 0.0]),
+                quad_points: vec![
+                    100.0, 711.0, 181.0, 711.0, 100.0, 697.0, 181.0, 697.0, //
+                    70.0, 697.0, 145.0, 697.0, 70.0, 683.0, 145.0, 683.0,
+                ],
+                ..Annotation::default()
+            },
+        )
+        .unwrap();
+        crate::annotations::add_annotation(
+            &store,
+            &document,
+            Annotation {
+                subtype: "Text".to_string(),
+                rect: [300.0, 750.0, 320.0, 770.0],
+                contents: Some("Check the title".to_string()),
+                author: Some("Ada".to_string()),
+                ..Annotation::default()
+            },
+        )
+        .unwrap();
+
+        let notes = export_notes(&store, &document).unwrap();
+        let link = |page: u32| open_link(&document, page);
+        let expected = format!(
+            "# A Short Paper\n\n\
+             ## Introduction\n\n\
+             - [Page 1]({}) · Text · Ada\n\n  Check the title\n\n\
+             - [Page 1]({}) · Highlight · #FFFF00\n\n  > quick brown jumps over\n\n  \
+             Classic pangram.\n  Worth citing.\n\n\
+             ## Results\n\n\
+             - [Page 2]({}) · Highlight · #00FF00 · Reviewer\n\n  > Results\n\n  From another app\n",
+            link(1),
+            link(1),
+            link(2)
+        );
+        assert_eq!(notes, expected);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_notes_without_an_outline_are_grouped_by_page() {
+        let document = Path::new("/tmp/untitled.pdf");
+        let doc = Document::load(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms/fields.pdf"),
+        )
+        .unwrap();
+        let note = Annotation {
+            subtype: "Text".to_string(),
+            contents: Some("First".to_string()),
+            ..Annotation::default()
+        };
+
+        let notes = notes_markdown(&doc, document, vec![note]);
+        assert!(notes.contains("\n## Page 1\n\n- [Page 1]("));
+        assert!(notes.ends_with("  First\n"));
+
+        let empty = notes_markdown(&doc, document, Vec::new());
+        assert!(empty.ends_with("No annotations.\n"));
+    }
+}
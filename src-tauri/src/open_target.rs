@@ -0,0 +1,144 @@
+use lopdf::Document;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::error::MonightError;
+use crate::outline::named_destination_page;
+use crate::page_labels::page_index_for_label;
+use crate::page_text::page_text;
+use crate::pdf::{parse_document, read_pdf_bytes};
+use crate::synctex::SynctexPosition;
+
+/// Where to go in a document once it is open.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum OpenTarget {
+    /// One-based page number
+    Page(u32),
+    /// Printed page label, such as `iv` or `A-12`
+    PageLabel(String),
+    /// Named destination from `/Dests` or the `/Names` tree
+    Destination(String),
+    /// The first page containing this text
+    Search(String),
+}
+
+/// How pages are laid out in the viewer.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ViewMode {
+    Single,
+    Continuous,
+}
+
+impl ViewMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "single" => Some(ViewMode::Single),
+            "continuous" => Some(ViewMode::Continuous),
+            _ => None,
+        }
+    }
+}
+
+/// Where and how to show one of the files being opened.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTarget {
+    pub file: String,
+    /// One-based page, resolved from `target` when needed
+    pub page: Option<u32>,
+    /// Scale factor, 1.0 being actual size
+    pub zoom: Option<f64>,
+    pub view_mode: Option<ViewMode>,
+    /// What the page was resolved from, kept so a miss can be reported
+    pub target: Option<OpenTarget>,
+    /// Region to highlight after a SyncTeX forward search
+    pub synctex: Option<SynctexPosition>,
+}
+
+/// Lowercase with runs of whitespace collapsed, so line breaks do not defeat a search.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl OpenTarget {
+    /// One-based page of the target in `doc`, if it can be found.
+    pub(crate) fn page_in(&self, doc: &Document) -> Option<u32> {
+        let index = match self {
+            OpenTarget::Page(page) => return Some(*page),
+            OpenTarget::PageLabel(label) => page_index_for_label(doc, label)?,
+            OpenTarget::Destination(name) => named_destination_page(doc, name)?,
+            OpenTarget::Search(text) => {
+                let needle = normalize(text);
+                if needle.is_empty() {
+                    return None;
+                }
+                let number = doc
+                    .get_pages()
+                    .into_iter()
+                    .find(|(_, page_id)| normalize(&page_text(doc, *page_id)).contains(&needle))
+                    .map(|(number, _)| number)?;
+                number - 1
+            }
+        };
+        Some(index + 1)
+    }
+}
+
+/// One-based page of `target` in the document at `path`.
+pub(crate) fn resolve_target_page(
+    path: &Path,
+    target: &OpenTarget,
+) -> Result<Option<u32>, MonightError> {
+    if let OpenTarget::Page(page) = target {
+        return Ok(Some(*page));
+    }
+    let bytes = read_pdf_bytes(path.to_string_lossy().to_string())?;
+    let doc = parse_document(path, &bytes)?;
+    Ok(target.page_in(&doc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_targets_resolve_to_one_based_pages() {
+        let outline = fixture("outline.pdf");
+        let resolve = |target: OpenTarget| resolve_target_page(&outline, &target).unwrap();
+
+        assert_eq!(resolve(OpenTarget::Page(7)), Some(7));
+        assert_eq!(resolve(OpenTarget::Destination("Intro".into())), Some(3));
+        assert_eq!(resolve(OpenTarget::PageLabel("2".into())), Some(2));
+        assert_eq!(resolve(OpenTarget::Destination("nowhere".into())), None);
+
+        let paper = fixture("notes/paper.pdf");
+        let search =
+            |text: &str| resolve_target_page(&paper, &OpenTarget::Search(text.into())).unwrap();
+        assert_eq!(search("RESULTS  are"), Some(2));
+        assert_eq!(search("fox jumps"), Some(1));
+        assert_eq!(search("missing"), None);
+        assert_eq!(search(" "), None);
+    }
+
+    #[test]
+    fn test_targets_serialize_with_a_kind_tag() {
+        assert_eq!(
+            serde_json::to_value(OpenTarget::PageLabel("iv".into())).unwrap(),
+            serde_json::json!({ "kind": "pageLabel", "value": "iv" })
+        );
+        assert!(
+            resolve_target_page(&fixture("missing.pdf"), &OpenTarget::Search("x".into())).is_err()
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::pdf::{dict_get, resolve, text_string};
+
+/// Deepest outline nesting we follow; real documents stay in single digits.
+const MAX_DEPTH: usize = 64;
+/// Deepest name tree we descend into when resolving named destinations.
+const MAX_NAME_TREE_DEPTH: usize = 32;
+
+/// One bookmark in the document outline.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+    /// Zero-based index of the target page, if the destination resolves to one
+    pub page_index: Option<u32>,
+    /// Whether the item starts expanded (a positive `/Count`)
+    pub open: bool,
+    pub children: Vec<OutlineItem>,
+}
+
+struct OutlineReader<'a> {
+    doc: &'a Document,
+    page_indices: HashMap<ObjectId, u32>,
+    /// Items already emitted, so `/Next` or `/First` cycles terminate
+    visited: HashSet<ObjectId>,
+}
+
+impl<'a> OutlineReader<'a> {
+    fn new(doc: &'a Document) -> Self {
+        OutlineReader {
+            doc,
+            page_indices: doc
+                .get_pages()
+                .into_iter()
+                .map(|(number, id)| (id, number - 1))
+                .collect(),
+            visited: HashSet::new(),
+        }
+    }
+
+    fn items(&mut self, first: Option<&Object>, depth: usize) -> Vec<OutlineItem> {
+        let mut items = Vec::new();
+        if depth > MAX_DEPTH {
+            return items;
+        }
+
+        let mut next = first.and_then(|object| object.as_reference().ok());
+        while let Some(id) = next {
+            if !self.visited.insert(id) {
+                break;
+            }
+            let Ok(item) = self.doc.get_dictionary(id) else {
+                break;
+            };
+
+            let title = dict_get(self.doc, item, b"Title")
+                .and_then(text_string)
+                .unwrap_or_default();
+            let open = dict_get(self.doc, item, b"Count")
+                .and_then(|count| count.as_i64().ok())
+                .is_some_and(|count| count > 0);
+            let page_index = self.item_page(item);
+            let children = self.items(item.get(b"First").ok(), depth + 1);
+
+            items.push(OutlineItem {
+                title,
+                page_index,
+                open,
+                children,
+            });
+            next = item
+                .get(b"Next")
+                .ok()
+                .and_then(|next| next.as_reference().ok());
+        }
+
+        items
+    }
+
+    /// Target page of an item's `/Dest`, or of a `/GoTo` action in `/A`.
+    fn item_page(&self, item: &Dictionary) -> Option<u32> {
+        if let Some(dest) = dict_get(self.doc, item, b"Dest") {
+            return self.destination_page(dest, true);
+        }
+
+        let action = dict_get(self.doc, item, b"A")?.as_dict().ok()?;
+        if action.get(b"S").and_then(Object::as_name).ok() != Some(b"GoTo".as_slice()) {
+            return None;
+        }
+        self.destination_page(dict_get(self.doc, action, b"D")?, true)
+    }
+
+    fn destination_page(&self, dest: &Object, allow_names: bool) -> Option<u32> {
+        match dest {
+            Object::Array(parts) => match resolve(self.doc, parts.first()?)? {
+                Object::Dictionary(_) => {
+                    let id = parts.first()?.as_reference().ok()?;
+                    self.page_indices.get(&id).copied()
+                }
+                // Some producers write a page number where a page reference belongs
+                Object::Integer(index) => u32::try_from(*index).ok(),
+                _ => None,
+            },
+            Object::Dictionary(dict) => {
+                self.destination_page(dict_get(self.doc, dict, b"D")?, false)
+            }
+            Object::Name(name) | Object::String(name, _) if allow_names => {
+                let target = self.named_destination(name)?;
+                self.destination_page(target, false)
+            }
+            _ => None,
+        }
+    }
+
+    /// Look a name up in the catalog's `/Dests` dictionary or the `/Names` → `/Dests` tree.
+    fn named_destination(&self, name: &[u8]) -> Option<&'a Object> {
+        let catalog = self.doc.catalog().ok()?;
+
+        if let Some(dests) = dict_get(self.doc, catalog, b"Dests").and_then(|d| d.as_dict().ok()) {
+            if let Some(target) = dict_get(self.doc, dests, name) {
+                return Some(target);
+            }
+        }
+
+        let names = dict_get(self.doc, catalog, b"Names")?.as_dict().ok()?;
+        let tree = dict_get(self.doc, names, b"Dests")?.as_dict().ok()?;
+        self.name_tree_lookup(tree, name, 0)
+    }
+
+    fn name_tree_lookup(
+        &self,
+        node: &'a Dictionary,
+        key: &[u8],
+        depth: usize,
+    ) -> Option<&'a Object> {
+        if depth > MAX_NAME_TREE_DEPTH {
+            return None;
+        }
+
+        if let Some(limits) = dict_get(self.doc, node, b"Limits").and_then(|l| l.as_array().ok()) {
+            let bound = |index: usize| limits.get(index).and_then(|b| b.as_str().ok());
+            if let (Some(low), Some(high)) = (bound(0), bound(1)) {
+                if key < low || key > high {
+                    return None;
+                }
+            }
+        }
+
+        if let Some(names) = dict_get(self.doc, node, b"Names").and_then(|n| n.as_array().ok()) {
+            for pair in names.chunks(2) {
+                if let [name, value] = pair {
+                    if resolve(self.doc, name).and_then(|n| n.as_str().ok()) == Some(key) {
+                        return resolve(self.doc, value);
+                    }
+                }
+            }
+        }
+
+        let kids = dict_get(self.doc, node, b"Kids")?.as_array().ok()?;
+        kids.iter()
+            .filter_map(|kid| resolve(self.doc, kid)?.as_dict().ok())
+            .find_map(|kid| self.name_tree_lookup(kid, key, depth + 1))
+    }
+}
+
+/// Read the `/Outlines` tree of a document. Broken entries are kept with
+/// whatever could be recovered rather than failing the whole outline.
+pub fn document_outline(doc: &Document) -> Vec<OutlineItem> {
+    let Some(root) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dict_get(doc, catalog, b"Outlines"))
+        .and_then(|outlines| outlines.as_dict().ok())
+    else {
+        return Vec::new();
+    };
+
+    OutlineReader::new(doc).items(root.get(b"First").ok(), 0)
+}
+
+/// Zero-based page index of a named destination from `/Dests` or the `/Names` tree.
+pub(crate) fn named_destination_page(doc: &Document, name: &str) -> Option<u32> {
+    let reader = OutlineReader::new(doc);
+    let target = reader.named_destination(name.as_bytes())?;
+    reader.destination_page(target, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn load(name: &str) -> Document {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        Document::load(path).expect("fixture should parse")
+    }
+
+    fn summary(items: &[OutlineItem]) -> Vec<(String, Option<u32>, bool, usize)> {
+        items
+            .iter()
+            .map(|item| {
+                (
+                    item.title.clone(),
+                    item.page_index,
+                    item.open,
+                    item.children.len(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_outline_titles_pages_and_state() {
+        let outline = document_outline(&load("outline.pdf"));
+
+        assert_eq!(
+            summary(&outline),
+            vec![
+                ("Chapter 1".to_string(), Some(0), true, 2),
+                ("Chapter 2 — Ünïcode".to_string(), Some(2), false, 1),
+                ("Reader\u{2019}s notes".to_string(), None, false, 0),
+            ]
+        );
+        assert_eq!(
+            summary(&outline[0].children),
+            vec![
+                ("Section 1.1".to_string(), Some(1), false, 0),
+                ("Section 1.2".to_string(), Some(2), false, 0),
+            ]
+        );
+        assert_eq!(
+            summary(&outline[1].children),
+            vec![("Hidden detail".to_string(), Some(2), false, 0)]
+        );
+    }
+
+    #[test]
+    fn test_malformed_outline_terminates_and_keeps_recoverable_items() {
+        let outline = document_outline(&load("outline_malformed.pdf"));
+
+        assert_eq!(
+            summary(&outline),
+            vec![
+                ("Loops back".to_string(), Some(0), false, 0),
+                (String::new(), None, false, 0),
+                ("Unknown name".to_string(), None, false, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document_without_outline_is_empty() {
+        assert!(document_outline(&load("metadata.pdf")).is_empty());
+    }
+
+    #[test]
+    fn test_named_destinations_resolve_through_dests_and_the_name_tree() {
+        let doc = load("outline.pdf");
+
+        assert_eq!(named_destination_page(&doc, "Intro"), Some(2));
+        assert_eq!(named_destination_page(&doc, "sec-1.1"), Some(1));
+        assert_eq!(named_destination_page(&doc, "appendix"), Some(0));
+        assert_eq!(named_destination_page(&doc, "nowhere"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use lopdf::{Dictionary, Document};
+
+use crate::pdf::{dict_get, resolve, text_string};
+
+/// Deepest number tree we descend into; real trees are one or two levels.
+const MAX_TREE_DEPTH: usize = 32;
+/// Largest number written as letters or roman numerals; beyond it they grow
+/// absurdly long, so labels switch to decimal.
+const MAX_NUMERAL: u32 = 3999;
+
+/// Numbering style of a page label range (`/S`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Style {
+    Decimal,
+    UpperRoman,
+    LowerRoman,
+    UpperLetters,
+    LowerLetters,
+}
+
+/// A `/PageLabels` entry: pages from `start` on are labelled `prefix` plus a
+/// number counting up from `first`.
+#[derive(Debug, PartialEq)]
+struct LabelRange {
+    /// Zero-based index of the first page in the range
+    start: u32,
+    /// No style means the label is the prefix alone
+    style: Option<Style>,
+    prefix: String,
+    first: u32,
+}
+
+fn label_range(doc: &Document, start: u32, dict: &Dictionary) -> LabelRange {
+    let style = match dict_get(doc, dict, b"S").and_then(|s| s.as_name().ok()) {
+        Some(b"D") => Some(Style::Decimal),
+        Some(b"R") => Some(Style::UpperRoman),
+        Some(b"r") => Some(Style::LowerRoman),
+        Some(b"A") => Some(Style::UpperLetters),
+        Some(b"a") => Some(Style::LowerLetters),
+        _ => None,
+    };
+    LabelRange {
+        start,
+        style,
+        prefix: dict_get(doc, dict, b"P")
+            .and_then(text_string)
+            .unwrap_or_default(),
+        first: dict_get(doc, dict, b"St")
+            .and_then(|st| st.as_i64().ok())
+            .and_then(|st| u32::try_from(st).ok())
+            .filter(|st| *st > 0)
+            .unwrap_or(1),
+    }
+}
+
+/// Collect the `/Nums` pairs of a number tree node and its `/Kids`.
+fn collect_ranges(doc: &Document, node: &Dictionary, depth: usize, out: &mut Vec<LabelRange>) {
+    if depth > MAX_TREE_DEPTH {
+        return;
+    }
+
+    if let Some(nums) = dict_get(doc, node, b"Nums").and_then(|n| n.as_array().ok()) {
+        for pair in nums.chunks(2) {
+            if let [key, value] = pair {
+                let start = resolve(doc, key)
+                    .and_then(|key| key.as_i64().ok())
+                    .and_then(|key| u32::try_from(key).ok());
+                let dict = resolve(doc, value).and_then(|value| value.as_dict().ok());
+                if let (Some(start), Some(dict)) = (start, dict) {
+                    out.push(label_range(doc, start, dict));
+                }
+            }
+        }
+    }
+
+    if let Some(kids) = dict_get(doc, node, b"Kids").and_then(|k| k.as_array().ok()) {
+        for kid in kids {
+            if let Some(kid) = resolve(doc, kid).and_then(|kid| kid.as_dict().ok()) {
+                collect_ranges(doc, kid, depth + 1, out);
+            }
+        }
+    }
+}
+
+fn roman(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            out.push_str(numeral);
+            number -= value;
+        }
+    }
+    out
+}
+
+/// `a`–`z`, then `aa`–`zz`, `aaa`… as the PDF specification numbers letters.
+fn letters(number: u32) -> String {
+    let index = number.saturating_sub(1);
+    let letter = char::from(b'a' + (index % 26) as u8);
+    std::iter::repeat(letter)
+        .take(index as usize / 26 + 1)
+        .collect()
+}
+
+impl LabelRange {
+    fn label(&self, page: u32) -> String {
+        let Some(number) = self.first.checked_add(page - self.start) else {
+            // Past the largest label number, so number the page plainly
+            return (page + 1).to_string();
+        };
+        let numeral = match self.style {
+            Some(Style::Decimal) => number.to_string(),
+            Some(_) if number > MAX_NUMERAL => number.to_string(),
+            Some(Style::UpperRoman) => roman(number).to_uppercase(),
+            Some(Style::LowerRoman) => roman(number),
+            Some(Style::UpperLetters) => letters(number).to_uppercase(),
+            Some(Style::LowerLetters) => letters(number),
+            None => String::new(),
+        };
+        format!("{}{}", self.prefix, numeral)
+    }
+}
+
+/// The printed label of every page. Without `/PageLabels`, pages are numbered from 1.
+pub(crate) fn page_labels(doc: &Document) -> Vec<String> {
+    let page_count = doc.get_pages().len() as u32;
+    let mut ranges = Vec::new();
+    if let Some(tree) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dict_get(doc, catalog, b"PageLabels"))
+        .and_then(|tree| tree.as_dict().ok())
+    {
+        collect_ranges(doc, tree, 0, &mut ranges);
+    }
+    ranges.sort_by_key(|range| range.start);
+
+    (0..page_count)
+        .map(
+            |page| match ranges.iter().rev().find(|range| range.start <= page) {
+                Some(range) => range.label(page),
+                None => (page + 1).to_string(),
+            },
+        )
+        .collect()
+}
+
+/// Zero-based index of the page with `label`, preferring an exact match over
+/// one that differs only in case.
+pub(crate) fn page_index_for_label(doc: &Document, label: &str) -> Option<u32> {
+    let labels = page_labels(doc);
+    let label = label.trim();
+    labels
+        .iter()
+        .position(|candidate| candidate == label)
+        .or_else(|| {
+            labels
+                .iter()
+                .position(|candidate| candidate.eq_ignore_ascii_case(label))
+        })
+        .map(|index| index as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object};
+
+    /// A document with `pages` blank pages and `labels` as its `/PageLabels` tree.
+    fn document(pages: u32, labels: Option<Dictionary>) -> Document {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..pages)
+            .map(|_| {
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                })
+                .into()
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => i64::from(pages),
+            }),
+        );
+
+        let mut catalog = dictionary! { "Type" => "Catalog", "Pages" => pages_id };
+        if let Some(labels) = labels {
+            let labels_id = doc.add_object(labels);
+            catalog.set("PageLabels", labels_id);
+        }
+        let catalog_id = doc.add_object(catalog);
+        doc.trailer.set("Root", catalog_id);
+        doc
+    }
+
+    #[test]
+    fn test_number_tree_ranges_with_styles_prefixes_and_starts() {
+        let labels = dictionary! {
+            "Nums" => vec![
+                0.into(), Object::Dictionary(dictionary! { "S" => "r" }),
+                3.into(), Object::Dictionary(dictionary! { "S" => "D" }),
+                6.into(), Object::Dictionary(dictionary! {
+                    "S" => "D",
+                    "P" => Object::string_literal("A-"),
+                    "St" => 12,
+                }),
+                8.into(), Object::Dictionary(dictionary! { "P" => Object::string_literal("Cover") }),
+            ],
+        };
+        let doc = document(9, Some(labels));
+
+        assert_eq!(
+            page_labels(&doc),
+            vec!["i", "ii", "iii", "1", "2", "3", "A-12", "A-13", "Cover"]
+        );
+        assert_eq!(page_index_for_label(&doc, "iii"), Some(2));
+        assert_eq!(page_index_for_label(&doc, "A-13"), Some(7));
+        assert_eq!(page_index_for_label(&doc, " a-12 "), Some(6));
+        assert_eq!(page_index_for_label(&doc, "4"), None);
+    }
+
+    #[test]
+    fn test_number_tree_kids_are_followed() {
+        let doc = {
+            let mut doc = document(30, None);
+            let first = doc.add_object(dictionary! {
+                "Limits" => vec![0.into(), 0.into()],
+                "Nums" => vec![0.into(), Object::Dictionary(dictionary! { "S" => "R", "St" => 4 })],
+            });
+            let second = doc.add_object(dictionary! {
+                "Limits" => vec![2.into(), 2.into()],
+                "Nums" => vec![2.into(), Object::Dictionary(dictionary! { "S" => "a" })],
+            });
+            let root = doc.add_object(dictionary! {
+                "Kids" => vec![first.into(), second.into()],
+            });
+            let catalog_id = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+            doc.get_dictionary_mut(catalog_id)
+                .unwrap()
+                .set("PageLabels", root);
+            doc
+        };
+
+        let labels = page_labels(&doc);
+        assert_eq!(&labels[..4], &["IV", "V", "a", "b"]);
+        assert_eq!(labels[27], "z");
+        assert_eq!(labels[28], "aa");
+        assert_eq!(labels[29], "bb");
+    }
+
+    #[test]
+    fn test_huge_start_numbers_neither_overflow_nor_explode() {
+        let labels = dictionary! {
+            "Nums" => vec![
+                0.into(), Object::Dictionary(dictionary! { "S" => "R", "St" => 3999 }),
+                2.into(), Object::Dictionary(dictionary! { "S" => "a", "St" => i64::from(u32::MAX) }),
+            ],
+        };
+        let doc = document(4, Some(labels));
+
+        let labels = page_labels(&doc);
+        assert_eq!(labels[0], "MMMCMXCIX");
+        assert_eq!(labels[1], "4000");
+        assert_eq!(labels[2], u32::MAX.to_string());
+        assert_eq!(labels[3], "4");
+    }
+
+    #[test]
+    fn test_documents_without_labels_count_from_one() {
+        let doc = document(3, None);
+
+        assert_eq!(page_labels(&doc), vec!["1", "2", "3"]);
+        assert_eq!(page_index_for_label(&doc, "2"), Some(1));
+        assert_eq!(roman(1994), "mcmxciv");
+        assert_eq!(letters(53), "aaa");
+    }
+}
@@ -0,0 +1,462 @@
+use lopdf::{Dictionary, Document, Encoding, Object, ObjectId};
+use std::collections::HashMap;
+
+use crate::pdf::{dict_get, number, numbers, resolve};
+
+/// Advance used for glyphs whose font gives no width, in thousandths of an em.
+const FALLBACK_WIDTH: f64 = 500.0;
+/// How far above the baseline a glyph's centre sits, as a fraction of the font size.
+const CENTRE_HEIGHT: f64 = 0.3;
+
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `a × b` in PDF's row-vector convention.
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+fn apply(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+    (x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5])
+}
+
+fn translation(x: f64, y: f64) -> Matrix {
+    [1.0, 0.0, 0.0, 1.0, x, y]
+}
+
+/// What we need of a font to place and decode its glyphs.
+struct Font<'a> {
+    encoding: Option<Encoding<'a>>,
+    /// Composite (Type0) fonts use two-byte codes
+    two_byte: bool,
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+}
+
+impl<'a> Font<'a> {
+    fn new(doc: &'a Document, dict: &'a Dictionary) -> Self {
+        let two_byte = dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0");
+        let mut widths = HashMap::new();
+
+        let default_width = if two_byte {
+            let descendant = dict_get(doc, dict, b"DescendantFonts")
+                .and_then(|fonts| fonts.as_array().ok())
+                .and_then(|fonts| resolve(doc, fonts.first()?)?.as_dict().ok());
+            if let Some(descendant) = descendant {
+                cid_widths(doc, dict_get(doc, descendant, b"W"), &mut widths);
+            }
+            descendant
+                .and_then(|descendant| dict_get(doc, descendant, b"DW"))
+                .and_then(number)
+                .unwrap_or(1000.0)
+        } else {
+            let first = dict_get(doc, dict, b"FirstChar")
+                .and_then(|first| first.as_i64().ok())
+                .unwrap_or(0);
+            for (offset, width) in numbers(doc, dict_get(doc, dict, b"Widths"))
+                .into_iter()
+                .enumerate()
+            {
+                if let Ok(code) = u32::try_from(first + offset as i64) {
+                    widths.insert(code, width);
+                }
+            }
+            dict_get(doc, dict, b"FontDescriptor")
+                .and_then(|descriptor| descriptor.as_dict().ok())
+                .and_then(|descriptor| dict_get(doc, descriptor, b"MissingWidth"))
+                .and_then(number)
+                .unwrap_or(FALLBACK_WIDTH)
+        };
+
+        Font {
+            encoding: dict.get_font_encoding(doc).ok(),
+            two_byte,
+            widths,
+            default_width,
+        }
+    }
+
+    fn codes<'s>(&self, bytes: &'s [u8]) -> std::slice::Chunks<'s, u8> {
+        bytes.chunks(if self.two_byte { 2 } else { 1 })
+    }
+
+    fn width(&self, code: &[u8]) -> f64 {
+        let key = code
+            .iter()
+            .fold(0u32, |key, byte| key << 8 | u32::from(*byte));
+        self.widths.get(&key).copied().unwrap_or(self.default_width)
+    }
+
+    fn decode(&self, code: &[u8]) -> String {
+        match &self.encoding {
+            Some(encoding) => encoding.bytes_to_string(code).unwrap_or_default(),
+            None if !self.two_byte => code.iter().map(|byte| char::from(*byte)).collect(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Parse a CIDFont `/W` array: `c [w1 w2 …]` or `c_first c_last w` runs.
+fn cid_widths(doc: &Document, w: Option<&Object>, widths: &mut HashMap<u32, f64>) {
+    let Some(items) = w.and_then(|w| w.as_array().ok()) else {
+        return;
+    };
+    let mut items = items.iter().filter_map(|item| resolve(doc, item));
+    while let Some(first) = items.next().and_then(number) {
+        let first = first as u32;
+        match items.next() {
+            Some(run @ Object::Array(_)) => {
+                for (offset, width) in numbers(doc, Some(run)).into_iter().enumerate() {
+                    let Some(code) = u32::try_from(offset)
+                        .ok()
+                        .and_then(|offset| first.checked_add(offset))
+                    else {
+                        break;
+                    };
+                    widths.insert(code, width);
+                }
+            }
+            Some(last) => {
+                let (Some(last), Some(width)) = (number(last), items.next().and_then(number))
+                else {
+                    return;
+                };
+                for code in first..=(last as u32).min(first.saturating_add(0xFFFF)) {
+                    widths.insert(code, width);
+                }
+            }
+            None => return,
+        }
+    }
+}
+
+/// A decoded glyph in user space.
+struct Glyph {
+    text: String,
+    origin: (f64, f64),
+    end: (f64, f64),
+    centre: (f64, f64),
+    /// Font size after all transformations
+    size: f64,
+}
+
+#[derive(Clone, Copy)]
+struct TextState {
+    ctm: Matrix,
+    font: Option<usize>,
+    size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scale: f64,
+    leading: f64,
+    rise: f64,
+}
+
+/// Walks a page's content stream and records where each glyph lands.
+struct GlyphReader<'a> {
+    doc: &'a Document,
+    font_dicts: std::collections::BTreeMap<Vec<u8>, &'a Dictionary>,
+    fonts: Vec<Font<'a>>,
+    font_names: HashMap<Vec<u8>, usize>,
+    state: TextState,
+    stack: Vec<TextState>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    glyphs: Vec<Glyph>,
+}
+
+impl<'a> GlyphReader<'a> {
+    fn select_font(&mut self, name: &[u8]) -> Option<usize> {
+        if let Some(index) = self.font_names.get(name) {
+            return Some(*index);
+        }
+        let dict = *self.font_dicts.get(name)?;
+        self.fonts.push(Font::new(self.doc, dict));
+        self.font_names.insert(name.to_vec(), self.fonts.len() - 1);
+        Some(self.fonts.len() - 1)
+    }
+
+    fn move_line(&mut self, x: f64, y: f64) {
+        self.line_matrix = multiply(&translation(x, y), &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn show(&mut self, bytes: &[u8]) {
+        let Some(font) = self.state.font.map(|index| &self.fonts[index]) else {
+            return;
+        };
+        let state = self.state;
+        for code in font.codes(bytes) {
+            let advance = font.width(code) / 1000.0;
+            let rendering = multiply(
+                &multiply(
+                    &[
+                        state.size * state.horizontal_scale,
+                        0.0,
+                        0.0,
+                        state.size,
+                        0.0,
+                        state.rise,
+                    ],
+                    &self.text_matrix,
+                ),
+                &state.ctm,
+            );
+            let origin = apply(&rendering, 0.0, 0.0);
+            let top = apply(&rendering, 0.0, 1.0);
+            self.glyphs.push(Glyph {
+                text: font.decode(code),
+                origin,
+                end: apply(&rendering, advance, 0.0),
+                centre: apply(&rendering, advance / 2.0, CENTRE_HEIGHT),
+                size: (top.0 - origin.0).hypot(top.1 - origin.1),
+            });
+
+            // Word spacing only applies to the single-byte space
+            let word_spacing = if code == b" " {
+                state.word_spacing
+            } else {
+                0.0
+            };
+            let shift =
+                (advance * state.size + state.char_spacing + word_spacing) * state.horizontal_scale;
+            self.text_matrix = multiply(&translation(shift, 0.0), &self.text_matrix);
+        }
+    }
+
+    fn operation(&mut self, operator: &str, operands: &[Object]) {
+        let n = |index: usize| operands.get(index).and_then(number).unwrap_or(0.0);
+        let matrix = || [n(0), n(1), n(2), n(3), n(4), n(5)];
+
+        match operator {
+            "q" => self.stack.push(self.state),
+            "Q" => {
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+            }
+            "cm" => self.state.ctm = multiply(&matrix(), &self.state.ctm),
+            "BT" => {
+                self.text_matrix = IDENTITY;
+                self.line_matrix = IDENTITY;
+            }
+            "Tf" => {
+                self.state.font = operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| self.select_font(name));
+                self.state.size = n(1);
+            }
+            "Tc" => self.state.char_spacing = n(0),
+            "Tw" => self.state.word_spacing = n(0),
+            "Tz" => self.state.horizontal_scale = n(0) / 100.0,
+            "TL" => self.state.leading = n(0),
+            "Ts" => self.state.rise = n(0),
+            "Td" => self.move_line(n(0), n(1)),
+            "TD" => {
+                self.state.leading = -n(1);
+                self.move_line(n(0), n(1));
+            }
+            "Tm" => {
+                self.line_matrix = matrix();
+                self.text_matrix = self.line_matrix;
+            }
+            "T*" => self.move_line(0.0, -self.state.leading),
+            "Tj" | "'" | "\"" => {
+                if operator == "\"" {
+                    self.state.word_spacing = n(0);
+                    self.state.char_spacing = n(1);
+                }
+                if operator != "Tj" {
+                    self.move_line(0.0, -self.state.leading);
+                }
+                if let Some(Object::String(bytes, _)) = operands.last() {
+                    self.show(bytes);
+                }
+            }
+            "TJ" => {
+                let items = operands
+                    .first()
+                    .and_then(|items| items.as_array().ok())
+                    .cloned()
+                    .unwrap_or_default();
+                for item in items {
+                    match item {
+                        Object::String(bytes, _) => self.show(&bytes),
+                        adjustment => {
+                            let shift = -number(&adjustment).unwrap_or(0.0) / 1000.0
+                                * self.state.size
+                                * self.state.horizontal_scale;
+                            self.text_matrix =
+                                multiply(&translation(shift, 0.0), &self.text_matrix);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Glyphs drawn directly by a page's content stream. Text inside form XObjects is
+/// not followed.
+fn page_glyphs(doc: &Document, page_id: ObjectId) -> Vec<Glyph> {
+    let Ok(content) = doc.get_and_decode_page_content(page_id) else {
+        return Vec::new();
+    };
+    let mut reader = GlyphReader {
+        doc,
+        font_dicts: doc.get_page_fonts(page_id).unwrap_or_default(),
+        fonts: Vec::new(),
+        font_names: HashMap::new(),
+        state: TextState {
+            ctm: IDENTITY,
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        },
+        stack: Vec::new(),
+        text_matrix: IDENTITY,
+        line_matrix: IDENTITY,
+        glyphs: Vec::new(),
+    };
+    for operation in &content.operations {
+        reader.operation(&operation.operator, &operation.operands);
+    }
+    reader.glyphs
+}
+
+/// The text on a page under the quadrilaterals of a text markup annotation,
+/// taking a glyph when its centre falls inside one of them.
+pub(crate) fn text_in_quads(doc: &Document, page_id: ObjectId, quad_points: &[f64]) -> String {
+    let boxes: Vec<[f64; 4]> = quad_points
+        .chunks_exact(8)
+        .map(|quad| {
+            let xs = [quad[0], quad[2], quad[4], quad[6]];
+            let ys = [quad[1], quad[3], quad[5], quad[7]];
+            [
+                xs.iter().copied().fold(f64::INFINITY, f64::min),
+                ys.iter().copied().fold(f64::INFINITY, f64::min),
+                xs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                ys.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            ]
+        })
+        .collect();
+    let inside = |(x, y): (f64, f64)| {
+        boxes
+            .iter()
+            .any(|b| x >= b[0] && x <= b[2] && y >= b[1] && y <= b[3])
+    };
+
+    join_glyphs(
+        page_glyphs(doc, page_id)
+            .into_iter()
+            .filter(|glyph| inside(glyph.centre)),
+    )
+}
+
+/// All the text drawn on a page, in content stream order.
+pub(crate) fn page_text(doc: &Document, page_id: ObjectId) -> String {
+    join_glyphs(page_glyphs(doc, page_id))
+}
+
+/// Join glyphs into words, adding spaces at gaps and line breaks.
+fn join_glyphs(glyphs: impl IntoIterator<Item = Glyph>) -> String {
+    let mut text = String::new();
+    let mut previous: Option<Glyph> = None;
+    for glyph in glyphs {
+        if let Some(previous) = &previous {
+            let new_line = (glyph.origin.1 - previous.origin.1).abs() > previous.size / 2.0;
+            let gap = (glyph.origin.0 - previous.end.0).hypot(glyph.origin.1 - previous.end.1);
+            let spaced = text.ends_with(char::is_whitespace) || glyph.text.starts_with(' ');
+            if !spaced && (new_line || gap > previous.size * 0.2) {
+                text.push(' ');
+            }
+        }
+        text.push_str(&glyph.text);
+        previous = Some(glyph);
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper() -> Document {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/notes/paper.pdf");
+        Document::load(path).unwrap()
+    }
+
+    #[test]
+    fn test_text_under_quads_follows_positioning_operators() {
+        let doc = paper();
+        let pages = doc.get_pages();
+
+        // "quick brown" on the first line, then all of the TJ-kerned second line
+        let quads = [
+            100.0, 711.0, 181.0, 711.0, 100.0, 697.0, 181.0, 697.0, //
+            70.0, 697.0, 145.0, 697.0, 70.0, 683.0, 145.0, 683.0,
+        ];
+        assert_eq!(
+            text_in_quads(&doc, pages[&1], &quads),
+            "quick brown jumps over"
+        );
+
+        // Text placed through `cm` on the second page
+        let quads = [71.0, 610.0, 115.0, 610.0, 71.0, 597.0, 115.0, 597.0];
+        assert_eq!(text_in_quads(&doc, pages[&2], &quads), "Results");
+        assert_eq!(text_in_quads(&doc, pages[&2], &[]), "");
+        assert_eq!(page_text(&doc, pages[&1]), "The quick brown fox jumps over");
+    }
+
+    #[test]
+    fn test_cid_widths_reads_both_run_forms() {
+        let doc = Document::new();
+        let w = Object::Array(vec![
+            1.into(),
+            Object::Array(vec![250.into(), 300.into()]),
+            10.into(),
+            12.into(),
+            700.into(),
+        ]);
+        let mut widths = HashMap::new();
+        cid_widths(&doc, Some(&w), &mut widths);
+
+        assert_eq!(widths.get(&1), Some(&250.0));
+        assert_eq!(widths.get(&2), Some(&300.0));
+        assert_eq!(widths.get(&11), Some(&700.0));
+        assert_eq!(widths.len(), 5);
+    }
+
+    #[test]
+    fn test_cid_widths_stop_at_the_largest_code() {
+        let doc = Document::new();
+        let max = i64::from(u32::MAX);
+        let w = Object::Array(vec![
+            max.into(),
+            Object::Array(vec![250.into(), 300.into()]),
+            (max - 2).into(),
+            max.into(),
+            700.into(),
+        ]);
+        let mut widths = HashMap::new();
+        cid_widths(&doc, Some(&w), &mut widths);
+
+        assert_eq!(widths.len(), 3);
+        assert_eq!(widths.get(&u32::MAX), Some(&700.0));
+    }
+}
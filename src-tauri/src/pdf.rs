@@ -0,0 +1,379 @@
+use lopdf::{Dictionary, Document, Object};
+use std::path::Path;
+
+use crate::document_kind::{detect_document_kind, DocumentKind};
+use crate::error::MonightError;
+use crate::xdp::read_xdp;
+
+pub(crate) fn invalid_pdf(path: &Path, message: impl ToString) -> MonightError {
+    MonightError::InvalidPdf {
+        path: path.to_string_lossy().to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Sniff a document's content and reconcile it with its extension.
+/// Content wins: a PDF without a `.pdf` extension is accepted, while a file
+/// that claims a supported extension but holds something else is rejected.
+pub(crate) fn check_document_kind(path: &Path) -> Result<DocumentKind, MonightError> {
+    let detected = detect_document_kind(path).map_err(|e| MonightError::from_io(path, e))?;
+
+    match (detected, DocumentKind::from_extension(path)) {
+        (Some(kind), _) => Ok(kind),
+        (None, Some(expected)) => Err(MonightError::ContentMismatch {
+            file: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            expected,
+        }),
+        (None, None) => Err(MonightError::UnsupportedType {
+            ext: path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_string(),
+        }),
+    }
+}
+
+/// Read and validate a PDF file, returning raw bytes.
+/// This is the pure, testable core — no Tauri dependencies.
+pub(crate) fn read_pdf_bytes(path: String) -> Result<Vec<u8>, MonightError> {
+    // Validate file exists
+    let file_path = Path::new(&path);
+    if !file_path.exists() {
+        return Err(MonightError::NotFound { path });
+    }
+
+    // Validate file content
+    let kind = check_document_kind(file_path)?;
+
+    // An XDP package carries or points to the PDF the viewer renders
+    if kind == DocumentKind::Xdp {
+        return read_xdp(file_path)?.into_pdf_bytes();
+    }
+
+    // Read file contents
+    std::fs::read(file_path).map_err(|e| MonightError::from_io(file_path, e))
+}
+
+/// Parse PDF bytes that were read from `path`.
+pub(crate) fn parse_document(path: &Path, bytes: &[u8]) -> Result<Document, MonightError> {
+    Document::load_mem(bytes).map_err(|e| invalid_pdf(path, e))
+}
+
+/// Read and parse a PDF from disk.
+pub(crate) fn load_document(path: &Path) -> Result<Document, MonightError> {
+    let bytes = std::fs::read(path).map_err(|e| MonightError::from_io(path, e))?;
+    parse_document(path, &bytes)
+}
+
+/// Follow a reference to the object it points at; direct objects are returned as-is.
+pub(crate) fn resolve<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Object> {
+    doc.dereference(object).ok().map(|(_, object)| object)
+}
+
+/// Look up `key` in `dict`, following an indirect reference if needed.
+pub(crate) fn dict_get<'a>(
+    doc: &'a Document,
+    dict: &'a Dictionary,
+    key: &[u8],
+) -> Option<&'a Object> {
+    resolve(doc, dict.get(key).ok()?)
+}
+
+/// Numeric value of an integer or real object.
+pub(crate) fn number(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(value) => Some(*value as f64),
+        Object::Real(value) => Some(f64::from(*value)),
+        _ => None,
+    }
+}
+
+/// The numbers in an array such as `/Rect` or `/QuadPoints`, skipping anything else.
+pub(crate) fn numbers(doc: &Document, object: Option<&Object>) -> Vec<f64> {
+    object
+        .and_then(|object| object.as_array().ok())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| number(resolve(doc, item)?))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Decode a PDF text string (PDFDocEncoding or UTF-16BE), dropping trailing NULs
+/// that some producers leave behind.
+pub(crate) fn text_string(object: &Object) -> Option<String> {
+    let text = lopdf::decode_text_string(object).ok()?;
+    Some(text.trim_end_matches('\0').to_string())
+}
+
+/// The document information dictionary, if the trailer has one.
+pub(crate) fn info_dictionary(doc: &Document) -> Option<&Dictionary> {
+    dict_get(doc, &doc.trailer, b"Info")?.as_dict().ok()
+}
+
+/// Convert a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`) to ISO 8601.
+///
+/// Every component after the year is optional; missing ones take their
+/// lowest value, and a missing offset leaves the time unqualified.
+pub(crate) fn format_pdf_date(raw: &str) -> Option<String> {
+    let value = raw.trim();
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits = value
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map(|(index, _)| index)
+        .unwrap_or(value.len());
+    if digits < 4 || digits % 2 != 0 || digits > 14 {
+        return None;
+    }
+
+    let field = |start: usize, default: &'static str| -> &str {
+        value
+            .get(start..start + 2)
+            .filter(|_| start + 2 <= digits)
+            .unwrap_or(default)
+    };
+    let mut iso = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &value[..4],
+        field(4, "01"),
+        field(6, "01"),
+        field(8, "00"),
+        field(10, "00"),
+        field(12, "00"),
+    );
+
+    let zone = &value[digits..];
+    match zone.chars().next() {
+        Some('Z') => iso.push('Z'),
+        Some(sign @ ('+' | '-')) => {
+            let offset = zone[1..].replace('\'', "");
+            let hours = offset.get(..2)?;
+            let minutes = offset.get(2..4).unwrap_or("00");
+            if !(hours.chars().chain(minutes.chars())).all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            iso.push_str(&format!("{}{}:{}", sign, hours, minutes));
+        }
+        Some(_) => return None,
+        None => {}
+    }
+
+    Some(iso)
+}
+
+/// Convert an ISO 8601 timestamp as produced by `format_pdf_date` back to a PDF date.
+pub(crate) fn to_pdf_date(iso: &str) -> Option<String> {
+    let (date, time) = iso.trim().split_once('T').unwrap_or((iso.trim(), ""));
+    let zone_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+    let (time, zone) = time.split_at(zone_start);
+
+    let digits: String = date
+        .chars()
+        .chain(time.chars())
+        .filter(|c| *c != '-' && *c != ':')
+        .collect();
+    if digits.len() < 4 || digits.len() > 14 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let zone = match zone {
+        "" => String::new(),
+        "Z" => "Z".to_string(),
+        offset => {
+            let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "00"));
+            format!("{}{}'{}'", &offset[..1], hours, minutes)
+        }
+    };
+    Some(format!("D:{}{}", digits, zone))
+}
+
+/// The current UTC time as ISO 8601.
+pub(crate) fn now_iso() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_pdf_date_handles_partial_dates_and_offsets() {
+        assert_eq!(
+            format_pdf_date("D:20240315093000+01'00'").as_deref(),
+            Some("2024-03-15T09:30:00+01:00")
+        );
+        assert_eq!(
+            format_pdf_date("D:20240401").as_deref(),
+            Some("2024-04-01T00:00:00")
+        );
+        assert_eq!(
+            format_pdf_date("D:199912312359Z").as_deref(),
+            Some("1999-12-31T23:59:00Z")
+        );
+        assert_eq!(
+            format_pdf_date("20200101120000-05'30").as_deref(),
+            Some("2020-01-01T12:00:00-05:30")
+        );
+        assert_eq!(format_pdf_date("yesterday"), None);
+        assert_eq!(format_pdf_date("D:202"), None);
+    }
+
+    #[test]
+    fn test_to_pdf_date_inverts_format_pdf_date() {
+        for raw in [
+            "D:20240315093000+01'00'",
+            "D:19991231235900Z",
+            "D:20200101120000-05'30'",
+            "D:20240401000000",
+        ] {
+            let iso = format_pdf_date(raw).unwrap();
+            assert_eq!(to_pdf_date(&iso).as_deref(), Some(raw));
+        }
+        assert_eq!(to_pdf_date("next week"), None);
+    }
+
+    #[test]
+    fn test_now_iso_is_a_parseable_utc_timestamp() {
+        let now = now_iso();
+
+        assert_eq!(now.len(), "2024-01-02T03:04:05Z".len());
+        assert!(now.as_str() > "2024");
+        let pdf_date = to_pdf_date(&now).unwrap();
+        assert_eq!(format_pdf_date(&pdf_date), Some(now));
+    }
+
+    #[test]
+    fn test_text_string_decodes_utf16_and_trims_nuls() {
+        let utf16 = Object::String(
+            b"\xFE\xFF\x00H\x00i\x00\x00".to_vec(),
+            lopdf::StringFormat::Hexadecimal,
+        );
+        assert_eq!(text_string(&utf16).as_deref(), Some("Hi"));
+
+        let literal = Object::string_literal("Plain");
+        assert_eq!(text_string(&literal).as_deref(), Some("Plain"));
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_returns_correct_content() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pdf");
+        let expected = std::fs::read(&fixture).expect("fixture should be readable");
+
+        let result = read_pdf_bytes(fixture.to_string_lossy().to_string());
+
+        assert!(
+            result.is_ok(),
+            "read_pdf_bytes should succeed for a valid PDF"
+        );
+        let bytes = result.unwrap();
+        assert_eq!(bytes.len(), expected.len());
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_rejects_unsupported_extension() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/readme.txt");
+
+        let result = read_pdf_bytes(fixture.to_string_lossy().to_string());
+
+        assert!(
+            matches!(result, Err(MonightError::UnsupportedType { ref ext }) if ext == "txt"),
+            "read_pdf_bytes should reject .txt files, got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_errors_for_missing_file() {
+        let missing = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/does_not_exist.pdf");
+
+        let result = read_pdf_bytes(missing.to_string_lossy().to_string());
+
+        assert!(
+            matches!(result, Err(MonightError::NotFound { .. })),
+            "read_pdf_bytes should fail for missing files, got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_accepts_pdf_content_without_pdf_extension() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+        for name in ["report.download", "untitled"] {
+            let result = read_pdf_bytes(fixtures.join(name).to_string_lossy().to_string());
+            assert!(result.is_ok(), "{} should be sniffed as a PDF", name);
+        }
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_unpacks_xdp_packages() {
+        let forms = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms");
+        let expected = std::fs::read(forms.join("form.pdf")).unwrap();
+
+        for name in ["embedded.xdp", "referenced.xdp"] {
+            let bytes = read_pdf_bytes(forms.join(name).to_string_lossy().to_string()).unwrap();
+            assert_eq!(bytes, expected, "{} should yield its PDF", name);
+        }
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_rejects_mismatched_content() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/not_a_pdf.pdf");
+
+        let result = read_pdf_bytes(fixture.to_string_lossy().to_string());
+
+        assert!(
+            matches!(
+                result,
+                Err(MonightError::ContentMismatch {
+                    expected: DocumentKind::Pdf,
+                    ..
+                })
+            ),
+            "read_pdf_bytes should reject a renamed text file, got: {:?}",
+            result
+        );
+    }
+}
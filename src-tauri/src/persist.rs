@@ -0,0 +1,38 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::error::MonightError;
+use crate::form_data::write_file;
+
+/// Save `value` as JSON at `path`, creating its folder first. The file is
+/// replaced through a temporary one so a crash never leaves it half-written.
+pub(crate) fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), MonightError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| MonightError::from_io(dir, e))?;
+    }
+    let bytes = serde_json::to_vec(value).map_err(|e| MonightError::Io {
+        path: path.to_string_lossy().to_string(),
+        message: e.to_string(),
+    })?;
+    write_file(path, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_save_json_creates_the_folder_and_replaces_the_file() {
+        let dir = temp_dir("persist", "save");
+        let path = dir.join("data").join("values.json");
+
+        save_json(&path, &[1, 2]).unwrap();
+        save_json(&path, &[3]).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[3]");
+        assert!(!dir.join("data").join("values.json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
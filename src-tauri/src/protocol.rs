@@ -0,0 +1,482 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+
+/// URI scheme used to stream opened documents to the webview.
+pub const SCHEME: &str = "monight";
+/// Most bytes sent in one response. Responses are built in memory, so longer
+/// requests get this much as partial content and ask again for the rest.
+pub(crate) const MAX_RESPONSE_LEN: u64 = 4 * 1024 * 1024;
+
+/// A document exposed through the `monight://` scheme.
+pub struct RegisteredDocument {
+    path: PathBuf,
+    /// Tabs showing the document; it is served until the last one closes
+    opens: usize,
+}
+
+/// Documents currently exposed through the `monight://` scheme, keyed by token.
+pub struct DocumentRegistry(pub Mutex<HashMap<String, RegisteredDocument>>);
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+impl ByteRange {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// The first `max` bytes of the range.
+    fn capped(self, max: u64) -> ByteRange {
+        ByteRange {
+            start: self.start,
+            end: self.end.min(self.start + max - 1),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum RangeRequest {
+    /// No usable range; serve the whole file.
+    Full,
+    Partial(ByteRange),
+    Unsatisfiable,
+}
+
+fn new_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    // RandomState is seeded per instance, which is enough to keep tokens unguessable
+    // by other pages without pulling in a dedicated RNG.
+    let mut high = RandomState::new().build_hasher();
+    high.write_u64(count);
+    let mut low = RandomState::new().build_hasher();
+    low.write_u64(count);
+
+    format!("{:016x}{:016x}", high.finish(), low.finish())
+}
+
+/// Register a canonical path and return its token, reusing the existing token if
+/// the document is already registered. Each registration needs its own unregister.
+pub(crate) fn register_document_inner(registry: &DocumentRegistry, path: PathBuf) -> String {
+    let mut guard = registry.0.lock().unwrap();
+    if let Some((token, existing)) = guard.iter_mut().find(|(_, existing)| existing.path == path) {
+        existing.opens += 1;
+        return token.clone();
+    }
+
+    let token = new_token();
+    guard.insert(token.clone(), RegisteredDocument { path, opens: 1 });
+    token
+}
+
+/// Undo one registration; the token stops working once every one is undone.
+pub(crate) fn unregister_document_inner(registry: &DocumentRegistry, token: &str) -> bool {
+    let mut guard = registry.0.lock().unwrap();
+    let Some(existing) = guard.get_mut(token) else {
+        return false;
+    };
+    existing.opens -= 1;
+    if existing.opens == 0 {
+        guard.remove(token);
+    }
+    true
+}
+
+/// URL under which the webview can fetch a registered document.
+pub(crate) fn document_url(token: &str) -> String {
+    // WebView2 cannot load custom schemes directly, so Tauri maps them to http://<scheme>.localhost
+    if cfg!(windows) {
+        format!("http://{}.localhost/{}", SCHEME, token)
+    } else {
+        format!("{}://localhost/{}", SCHEME, token)
+    }
+}
+
+/// Parse a `Range` header against a resource of `len` bytes.
+///
+/// Only single `bytes=` ranges are honoured; multi-range and foreign units fall
+/// back to a full response as RFC 9110 allows.
+pub(crate) fn parse_range(header_value: &str, len: u64) -> RangeRequest {
+    let Some(spec) = header_value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // Suffix range: the last N bytes
+        let Ok(suffix) = end.parse::<u64>() else {
+            return RangeRequest::Full;
+        };
+        if suffix == 0 || len == 0 {
+            return RangeRequest::Unsatisfiable;
+        }
+        ByteRange {
+            start: len.saturating_sub(suffix),
+            end: len - 1,
+        }
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return RangeRequest::Full;
+        };
+        let end = if end.is_empty() {
+            len.saturating_sub(1)
+        } else {
+            match end.parse::<u64>() {
+                Ok(end) if end >= start => end.min(len.saturating_sub(1)),
+                _ => return RangeRequest::Full,
+            }
+        };
+        if start >= len {
+            return RangeRequest::Unsatisfiable;
+        }
+        ByteRange { start, end }
+    };
+
+    RangeRequest::Partial(range)
+}
+
+fn read_slice(path: &Path, range: &ByteRange) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(range.start))?;
+    let mut buffer = vec![0; range.len() as usize];
+    file.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn base_response(status: StatusCode) -> tauri::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            "Accept-Ranges, Content-Length, Content-Range",
+        )
+}
+
+fn empty_response(status: StatusCode) -> Response<Vec<u8>> {
+    base_response(status).body(Vec::new()).unwrap()
+}
+
+/// Build the response for a document request, honouring `Range` and `HEAD`.
+/// Whatever is asked for, at most [`MAX_RESPONSE_LEN`] bytes are sent.
+pub(crate) fn document_response(
+    path: &Path,
+    method: &Method,
+    range_header: Option<&str>,
+) -> Response<Vec<u8>> {
+    let len = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => return empty_response(StatusCode::NOT_FOUND),
+    };
+
+    let range = range_header
+        .map(|value| parse_range(value, len))
+        .unwrap_or(RangeRequest::Full);
+
+    let (status, range) = match range {
+        RangeRequest::Unsatisfiable => {
+            return base_response(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .unwrap();
+        }
+        RangeRequest::Partial(range) => {
+            (StatusCode::PARTIAL_CONTENT, range.capped(MAX_RESPONSE_LEN))
+        }
+        RangeRequest::Full if len == 0 => {
+            return base_response(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/pdf")
+                .header(header::ACCEPT_RANGES, "bytes")
+                .header(header::CONTENT_LENGTH, 0)
+                .body(Vec::new())
+                .unwrap();
+        }
+        RangeRequest::Full if len > MAX_RESPONSE_LEN => (
+            StatusCode::PARTIAL_CONTENT,
+            ByteRange {
+                start: 0,
+                end: MAX_RESPONSE_LEN - 1,
+            },
+        ),
+        RangeRequest::Full => (
+            StatusCode::OK,
+            ByteRange {
+                start: 0,
+                end: len - 1,
+            },
+        ),
+    };
+
+    let mut builder = base_response(status)
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, range.len());
+    if status == StatusCode::PARTIAL_CONTENT {
+        builder = builder.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.end, len),
+        );
+    }
+
+    if method == Method::HEAD {
+        return builder.body(Vec::new()).unwrap();
+    }
+
+    match read_slice(path, &range) {
+        Ok(bytes) => builder.body(bytes).unwrap(),
+        Err(_) => empty_response(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Entry point for the `monight://` scheme handler.
+pub(crate) fn handle_request(
+    registry: &DocumentRegistry,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let method = request.method();
+    if method == Method::OPTIONS {
+        return base_response(StatusCode::NO_CONTENT)
+            .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, HEAD, OPTIONS")
+            .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "Range")
+            .body(Vec::new())
+            .unwrap();
+    }
+    if method != Method::GET && method != Method::HEAD {
+        return empty_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let token = request.uri().path().trim_start_matches('/');
+    let path = {
+        let guard = registry.0.lock().unwrap();
+        guard.get(token).map(|document| document.path.clone())
+    };
+    let Some(path) = path else {
+        return empty_response(StatusCode::NOT_FOUND);
+    };
+
+    let range_header = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+
+    document_response(&path, method, range_header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pdf")
+    }
+
+    fn registry_with_fixture() -> (DocumentRegistry, String) {
+        let registry = DocumentRegistry(Mutex::new(HashMap::new()));
+        let token = register_document_inner(&registry, fixture_path());
+        (registry, token)
+    }
+
+    fn request(token: &str, range: Option<&str>) -> Request<Vec<u8>> {
+        let mut builder = Request::builder().uri(format!("monight://localhost/{}", token));
+        if let Some(range) = range {
+            builder = builder.header(header::RANGE, range);
+        }
+        builder.body(Vec::new()).unwrap()
+    }
+
+    fn header_value(response: &Response<Vec<u8>>, name: header::HeaderName) -> &str {
+        response.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn test_parse_range_forms() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            RangeRequest::Partial(ByteRange { start: 0, end: 99 })
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            RangeRequest::Partial(ByteRange {
+                start: 900,
+                end: 999
+            })
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            RangeRequest::Partial(ByteRange {
+                start: 900,
+                end: 999
+            })
+        );
+        assert_eq!(
+            parse_range("bytes=990-2000", 1000),
+            RangeRequest::Partial(ByteRange {
+                start: 990,
+                end: 999
+            })
+        );
+        assert_eq!(
+            parse_range("bytes=1000-", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("items=0-1", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=9-2", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_full_request_returns_whole_file() {
+        let (registry, token) = registry_with_fixture();
+        let expected = std::fs::read(fixture_path()).unwrap();
+
+        let response = handle_request(&registry, &request(&token, None));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), &expected);
+        assert_eq!(
+            header_value(&response, header::CONTENT_LENGTH),
+            expected.len().to_string()
+        );
+        assert_eq!(header_value(&response, header::ACCEPT_RANGES), "bytes");
+    }
+
+    #[test]
+    fn test_range_request_returns_partial_content() {
+        let (registry, token) = registry_with_fixture();
+        let expected = std::fs::read(fixture_path()).unwrap();
+
+        let response = handle_request(&registry, &request(&token, Some("bytes=0-7")));
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body().as_slice(), &expected[0..8]);
+        assert_eq!(header_value(&response, header::CONTENT_LENGTH), "8");
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            format!("bytes 0-7/{}", expected.len())
+        );
+
+        let response = handle_request(&registry, &request(&token, Some("bytes=-16")));
+        let tail_start = expected.len() - 16;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body().as_slice(), &expected[tail_start..]);
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            format!(
+                "bytes {}-{}/{}",
+                tail_start,
+                expected.len() - 1,
+                expected.len()
+            )
+        );
+    }
+
+    #[test]
+    fn test_long_responses_are_capped_as_partial_content() {
+        let dir = crate::test_support::temp_dir("protocol", "capped");
+        let path = dir.join("large.pdf");
+        let len = MAX_RESPONSE_LEN + 10;
+        std::fs::write(&path, vec![b'%'; len as usize]).unwrap();
+        let registry = DocumentRegistry(Mutex::new(HashMap::new()));
+        let token = register_document_inner(&registry, path);
+        let capped = format!("bytes 0-{}/{}", MAX_RESPONSE_LEN - 1, len);
+
+        for range in [None, Some("bytes=0-"), Some("bytes=0-99999999")] {
+            let response = handle_request(&registry, &request(&token, range));
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(response.body().len() as u64, MAX_RESPONSE_LEN);
+            assert_eq!(header_value(&response, header::CONTENT_RANGE), capped);
+        }
+
+        // The rest comes with the next request
+        let rest = format!("bytes={}-", MAX_RESPONSE_LEN);
+        let response = handle_request(&registry, &request(&token, Some(&rest)));
+        assert_eq!(response.body().len(), 10);
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            format!("bytes {}-{}/{}", MAX_RESPONSE_LEN, len - 1, len)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unsatisfiable_range_reports_length() {
+        let (registry, token) = registry_with_fixture();
+        let len = std::fs::metadata(fixture_path()).unwrap().len();
+
+        let response = handle_request(
+            &registry,
+            &request(&token, Some(&format!("bytes={}-", len))),
+        );
+
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            format!("bytes */{}", len)
+        );
+    }
+
+    #[test]
+    fn test_head_request_has_headers_only() {
+        let (registry, token) = registry_with_fixture();
+        let len = std::fs::metadata(fixture_path()).unwrap().len();
+        let head = Request::builder()
+            .method(Method::HEAD)
+            .uri(format!("monight://localhost/{}", token))
+            .body(Vec::new())
+            .unwrap();
+
+        let response = handle_request(&registry, &head);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.body().is_empty());
+        assert_eq!(
+            header_value(&response, header::CONTENT_LENGTH),
+            len.to_string()
+        );
+    }
+
+    #[test]
+    fn test_unknown_token_is_not_found() {
+        let (registry, token) = registry_with_fixture();
+        assert!(unregister_document_inner(&registry, &token));
+
+        let response = handle_request(&registry, &request(&token, None));
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_register_reuses_token_for_same_path() {
+        let (registry, token) = registry_with_fixture();
+        assert_eq!(register_document_inner(&registry, fixture_path()), token);
+
+        // Closing one of two tabs keeps the document served for the other
+        assert!(unregister_document_inner(&registry, &token));
+        let response = handle_request(&registry, &request(&token, Some("bytes=0-7")));
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+        assert!(unregister_document_inner(&registry, &token));
+        let response = handle_request(&registry, &request(&token, None));
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(!unregister_document_inner(&registry, &token));
+    }
+}
@@ -0,0 +1,391 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::error::MonightError;
+use crate::open_target::ViewMode;
+
+/// File in the app data directory that holds reading positions.
+pub const READING_POSITIONS_FILE: &str = "reading-positions.json";
+/// Documents remembered; the least recently read are forgotten first.
+const MAX_POSITIONS: usize = 500;
+/// Bytes hashed from each end of a document. The trailer with `/ID` sits in the tail.
+const SAMPLE_LEN: u64 = 64 * 1024;
+
+/// Identifies a document by what it contains rather than where it is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fingerprint {
+    /// First element of the trailer `/ID`, which survives edits, as hex
+    pub document_id: Option<String>,
+    /// SHA-256 of the length, head and tail of the file
+    pub content: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn skip_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// The first string of the last `/ID [<…> <…>]` in `tail`.
+fn trailer_id(tail: &[u8]) -> Option<Vec<u8>> {
+    let at = tail.windows(3).rposition(|window| window == b"/ID")?;
+    let rest = skip_whitespace(&tail[at + 3..]);
+    let rest = skip_whitespace(rest.strip_prefix(b"[")?);
+
+    let id = if let Some(rest) = rest.strip_prefix(b"<") {
+        let end = rest.iter().position(|&byte| byte == b'>')?;
+        let digits: Vec<u8> = rest[..end]
+            .iter()
+            .copied()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        digits
+            .chunks(2)
+            .map(|pair| {
+                let pair = std::str::from_utf8(pair).ok()?;
+                // An odd final digit is followed by an implied 0
+                u8::from_str_radix(&format!("{:0<2}", pair), 16).ok()
+            })
+            .collect::<Option<Vec<u8>>>()?
+    } else {
+        let rest = rest.strip_prefix(b"(")?;
+        let mut escaped = false;
+        let end = rest.iter().position(|&byte| {
+            let close = byte == b')' && !escaped;
+            escaped = byte == b'\\' && !escaped;
+            close
+        })?;
+        rest[..end].to_vec()
+    };
+    (!id.is_empty()).then_some(id)
+}
+
+/// Fingerprint `path` from its size and the bytes at either end, so large
+/// documents are never read in full.
+pub fn fingerprint(path: &Path) -> Result<Fingerprint, MonightError> {
+    let io_error = |e| MonightError::from_io(path, e);
+    let mut file = std::fs::File::open(path).map_err(io_error)?;
+    let len = file.metadata().map_err(io_error)?.len();
+
+    let mut head = Vec::new();
+    (&mut file)
+        .take(SAMPLE_LEN)
+        .read_to_end(&mut head)
+        .map_err(io_error)?;
+    let mut tail = Vec::new();
+    if len > SAMPLE_LEN {
+        file.seek(SeekFrom::Start(
+            len.saturating_sub(SAMPLE_LEN).max(SAMPLE_LEN),
+        ))
+        .map_err(io_error)?;
+        file.read_to_end(&mut tail).map_err(io_error)?;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+    hasher.update(&head);
+    hasher.update(&tail);
+
+    // Small files fit in the head
+    let end = if tail.is_empty() { &head } else { &tail };
+    Ok(Fingerprint {
+        document_id: trailer_id(end).map(|id| hex(&id)),
+        content: format!("{:x}", hasher.finalize()),
+    })
+}
+
+/// Colour filter applied to pages, as the frontend's `FilterSettings`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterSettings {
+    pub brightness: f64,
+    pub grayscale: f64,
+    pub invert: f64,
+    pub sepia: f64,
+    pub hue: f64,
+    pub extra_brightness: f64,
+}
+
+/// Where a document was left and how it was shown.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingPosition {
+    /// One-based page
+    pub page: u32,
+    pub zoom: f64,
+    pub view_mode: ViewMode,
+    pub filter_settings: Option<FilterSettings>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct StoredPosition {
+    content: String,
+    document_id: Option<String>,
+    position: ReadingPosition,
+}
+
+/// Reading positions, most recently read first.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ReadingPositions {
+    entries: Vec<StoredPosition>,
+}
+
+impl ReadingPositions {
+    /// The position saved for these exact contents, or else the latest one
+    /// saved for another revision of the same document.
+    pub fn get(&self, fingerprint: &Fingerprint) -> Option<&ReadingPosition> {
+        self.entries
+            .iter()
+            .find(|entry| entry.content == fingerprint.content)
+            .or_else(|| {
+                let id = fingerprint.document_id.as_ref()?;
+                self.entries
+                    .iter()
+                    .find(|entry| entry.document_id.as_ref() == Some(id))
+            })
+            .map(|entry| &entry.position)
+    }
+
+    /// Save `position` for these contents, returning whether anything changed.
+    pub fn set(&mut self, fingerprint: &Fingerprint, position: ReadingPosition) -> bool {
+        if self
+            .entries
+            .first()
+            .is_some_and(|entry| entry.content == fingerprint.content && entry.position == position)
+        {
+            return false;
+        }
+        self.entries
+            .retain(|entry| entry.content != fingerprint.content);
+        self.entries.insert(
+            0,
+            StoredPosition {
+                content: fingerprint.content.clone(),
+                document_id: fingerprint.document_id.clone(),
+                position,
+            },
+        );
+        self.entries.truncate(MAX_POSITIONS);
+        true
+    }
+}
+
+/// Size and modification time, which tell whether something derived from a
+/// file's contents still holds.
+pub(crate) type FileStamp = (u64, Option<SystemTime>);
+
+pub(crate) fn file_stamp(path: &Path) -> Result<FileStamp, MonightError> {
+    let metadata = std::fs::metadata(path).map_err(|e| MonightError::from_io(path, e))?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+/// Fingerprints of the documents seen this session, so that repeated lookups
+/// do not sample a file again until it changes.
+#[derive(Default)]
+pub struct FingerprintCache(Mutex<HashMap<PathBuf, (FileStamp, Fingerprint)>>);
+
+impl FingerprintCache {
+    /// [`fingerprint`] of `document`, sampled again only once its size or
+    /// modification time changes.
+    pub fn fingerprint(&self, document: &Path) -> Result<Fingerprint, MonightError> {
+        let stamp = file_stamp(document)?;
+        let mut fingerprints = self.0.lock().unwrap();
+        if let Some((cached, fingerprint)) = fingerprints.get(document) {
+            if *cached == stamp {
+                return Ok(fingerprint.clone());
+            }
+        }
+
+        let fingerprint = fingerprint(document)?;
+        fingerprints.insert(document.to_path_buf(), (stamp, fingerprint.clone()));
+        Ok(fingerprint)
+    }
+}
+
+/// Reading positions persisted in the app data directory.
+pub struct ReadingPositionStore {
+    path: PathBuf,
+    positions: Mutex<ReadingPositions>,
+    fingerprints: FingerprintCache,
+}
+
+impl ReadingPositionStore {
+    pub fn load(path: PathBuf) -> Self {
+        let positions = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ReadingPositions>(&bytes).ok())
+            .unwrap_or_default();
+
+        ReadingPositionStore {
+            path,
+            positions: Mutex::new(positions),
+            fingerprints: FingerprintCache::default(),
+        }
+    }
+
+    /// Where `document` was left, wherever it was when it was read.
+    pub fn get(&self, document: &Path) -> Result<Option<ReadingPosition>, MonightError> {
+        let fingerprint = self.fingerprints.fingerprint(document)?;
+        Ok(self.positions.lock().unwrap().get(&fingerprint).cloned())
+    }
+
+    pub fn set(&self, document: &Path, position: ReadingPosition) -> Result<(), MonightError> {
+        let fingerprint = self.fingerprints.fingerprint(document)?;
+        let mut positions = self.positions.lock().unwrap();
+        if !positions.set(&fingerprint, position) {
+            return Ok(());
+        }
+        crate::persist::save_json(&self.path, &*positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{copy_fixture, temp_dir};
+
+    fn position(page: u32) -> ReadingPosition {
+        ReadingPosition {
+            page,
+            zoom: 1.25,
+            view_mode: ViewMode::Continuous,
+            filter_settings: Some(FilterSettings {
+                brightness: 7.0,
+                grayscale: 95.0,
+                invert: 95.0,
+                sepia: 55.0,
+                hue: 180.0,
+                extra_brightness: 0.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_trailer_id_reads_hex_and_literal_strings() {
+        let tail = b"trailer\n<< /Size 4 /ID [ <0A1b 2c3> <FFFF> ] >>\nstartxref";
+        assert_eq!(trailer_id(tail), Some(vec![0x0a, 0x1b, 0x2c, 0x30]));
+
+        let tail = b"/ID[(a\\)b)(c)] /ID [(second)]";
+        assert_eq!(trailer_id(tail), Some(b"second".to_vec()));
+        assert_eq!(trailer_id(b"/ID [(a\\)b)(c)]"), Some(b"a\\)b".to_vec()));
+
+        assert_eq!(trailer_id(b"trailer << /Size 4 >>"), None);
+        assert_eq!(trailer_id(b"/ID [<>]"), None);
+    }
+
+    #[test]
+    fn test_moved_documents_keep_their_position() {
+        let dir = temp_dir("reading-position", "moved");
+        let original = copy_fixture("notes/paper.pdf", &dir.join("paper.pdf"));
+
+        let store = ReadingPositionStore::load(dir.join("data").join(READING_POSITIONS_FILE));
+        assert_eq!(store.get(&original).unwrap(), None);
+        store.set(&original, position(2)).unwrap();
+
+        let moved = dir.join("renamed.pdf");
+        std::fs::rename(&original, &moved).unwrap();
+        assert_eq!(store.get(&moved).unwrap(), Some(position(2)));
+
+        // Positions survive a restart
+        let reloaded = ReadingPositionStore::load(dir.join("data").join(READING_POSITIONS_FILE));
+        assert_eq!(reloaded.get(&moved).unwrap(), Some(position(2)));
+        assert!(reloaded.get(&original).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_positions_are_not_written_again() {
+        let dir = temp_dir("reading-position", "unchanged");
+        let document = copy_fixture("notes/paper.pdf", &dir.join("paper.pdf"));
+        let file = dir.join(READING_POSITIONS_FILE);
+        let store = ReadingPositionStore::load(file.clone());
+
+        store.set(&document, position(2)).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        store.set(&document, position(2)).unwrap();
+        assert!(!file.exists());
+
+        // Edits to the document are noticed despite the cached fingerprint
+        let mut bytes = std::fs::read(&document).unwrap();
+        bytes.extend_from_slice(b"\n");
+        std::fs::write(&document, bytes).unwrap();
+        store.set(&document, position(2)).unwrap();
+        assert!(file.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_edited_documents_fall_back_to_their_id() {
+        let original = Fingerprint {
+            document_id: Some("ab".to_string()),
+            content: "1".to_string(),
+        };
+        let edited = Fingerprint {
+            content: "2".to_string(),
+            ..original.clone()
+        };
+        let unrelated = Fingerprint {
+            document_id: None,
+            content: "3".to_string(),
+        };
+
+        let mut positions = ReadingPositions::default();
+        positions.set(&original, position(4));
+        assert_eq!(positions.get(&edited), Some(&position(4)));
+        assert_eq!(positions.get(&unrelated), None);
+
+        // Exact contents win over a shared id
+        positions.set(&edited, position(9));
+        assert_eq!(positions.get(&original), Some(&position(4)));
+        assert_eq!(positions.get(&edited), Some(&position(9)));
+
+        for page in 0..MAX_POSITIONS as u32 {
+            let fingerprint = Fingerprint {
+                document_id: None,
+                content: format!("filler-{}", page),
+            };
+            positions.set(&fingerprint, position(page));
+        }
+        assert_eq!(positions.entries.len(), MAX_POSITIONS);
+        assert_eq!(positions.get(&original), None);
+    }
+
+    #[test]
+    fn test_fingerprint_samples_both_ends_of_large_files() {
+        let dir = temp_dir("reading-position", "large");
+        let path = dir.join("large.pdf");
+        let mut bytes = vec![b'%'; 3 * SAMPLE_LEN as usize];
+        bytes.extend_from_slice(b"trailer << /ID [<c0ffee> <c0ffee>] >>\n%%EOF\n");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let before = fingerprint(&path).unwrap();
+        assert_eq!(before.document_id.as_deref(), Some("c0ffee"));
+
+        // The middle is not sampled, but the ends and the length are
+        bytes[SAMPLE_LEN as usize + 10] = b'x';
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(fingerprint(&path).unwrap(), before);
+
+        bytes[10] = b'x';
+        std::fs::write(&path, &bytes).unwrap();
+        let edited = fingerprint(&path).unwrap();
+        assert_ne!(edited.content, before.content);
+        assert_eq!(edited.document_id, before.document_id);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
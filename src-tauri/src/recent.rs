@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::MonightError;
+
+/// File in the app data directory that holds the list.
+pub const RECENT_FILE: &str = "recent-documents.json";
+/// Unpinned entries kept; pinned entries never count against the cap.
+const MAX_RECENT: usize = 10;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RecentDocument {
+    pub path: String,
+    pub pinned: bool,
+}
+
+/// Recently opened documents: pinned entries first in the order they were
+/// pinned, then the rest, most recent first.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct RecentDocuments {
+    entries: Vec<RecentDocument>,
+}
+
+impl RecentDocuments {
+    pub fn entries(&self) -> &[RecentDocument] {
+        &self.entries
+    }
+
+    fn pinned_count(&self) -> usize {
+        self.entries.iter().take_while(|entry| entry.pinned).count()
+    }
+
+    fn truncate_unpinned(&mut self) {
+        self.entries.truncate(self.pinned_count() + MAX_RECENT);
+    }
+
+    /// Note that `path` (already canonical) was opened.
+    pub fn record(&mut self, path: &str) {
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.pinned && entry.path == path)
+        {
+            // Pinned entries keep their place
+            return;
+        }
+
+        self.entries.retain(|entry| entry.path != path);
+        let position = self.pinned_count();
+        self.entries.insert(
+            position,
+            RecentDocument {
+                path: path.to_string(),
+                pinned: false,
+            },
+        );
+        self.truncate_unpinned();
+    }
+
+    /// Pin or unpin an entry, returning false if it is not in the list.
+    pub fn set_pinned(&mut self, path: &str, pinned: bool) -> bool {
+        let Some(index) = self.entries.iter().position(|entry| entry.path == path) else {
+            return false;
+        };
+
+        let mut entry = self.entries.remove(index);
+        entry.pinned = pinned;
+        // Both newly pinned and newly unpinned entries land at the pinned/unpinned boundary
+        let position = self.pinned_count();
+        self.entries.insert(position, entry);
+        self.truncate_unpinned();
+        true
+    }
+
+    /// Drop entries whose file no longer exists, returning true if any were removed.
+    pub fn prune<F: Fn(&Path) -> bool>(&mut self, exists: F) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| exists(Path::new(&entry.path)));
+        self.entries.len() != before
+    }
+
+    /// Forget everything except pinned entries.
+    pub fn clear(&mut self) {
+        self.entries.retain(|entry| entry.pinned);
+    }
+}
+
+/// Menu labels for the entries: the file name, plus the parent folder when
+/// two entries share a name.
+pub(crate) fn menu_labels(entries: &[RecentDocument]) -> Vec<String> {
+    let name = |path: &str| {
+        Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            let file_name = name(&entry.path);
+            let shared = entries
+                .iter()
+                .filter(|other| name(&other.path) == file_name)
+                .count()
+                > 1;
+            match Path::new(&entry.path).parent().and_then(Path::file_name) {
+                Some(parent) if shared => {
+                    format!("{} — {}", file_name, parent.to_string_lossy())
+                }
+                _ => file_name,
+            }
+        })
+        .collect()
+}
+
+/// Recent documents persisted in the app data directory.
+pub struct RecentStore {
+    path: PathBuf,
+    documents: Mutex<RecentDocuments>,
+}
+
+impl RecentStore {
+    /// Load the list, dropping files that disappeared since the last run.
+    pub fn load(path: PathBuf) -> Self {
+        let mut documents = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<RecentDocuments>(&bytes).ok())
+            .unwrap_or_default();
+        documents.prune(Path::exists);
+
+        RecentStore {
+            path,
+            documents: Mutex::new(documents),
+        }
+    }
+
+    pub fn snapshot(&self) -> RecentDocuments {
+        self.documents.lock().unwrap().clone()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut RecentDocuments) -> T) -> Result<T, MonightError> {
+        let mut documents = self.documents.lock().unwrap();
+        let result = f(&mut documents);
+        crate::persist::save_json(&self.path, &*documents)?;
+        Ok(result)
+    }
+}
+
+/// Change the recent list, persist it and rebuild the "Open Recent" menu.
+pub fn change<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut RecentDocuments) -> T,
+) -> Result<T, MonightError> {
+    let result = app.state::<RecentStore>().update(f)?;
+    if let Err(e) = crate::menu::refresh_recent_menu(app) {
+        eprintln!("Failed to rebuild Open Recent menu: {}", e);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(documents: &RecentDocuments) -> Vec<(&str, bool)> {
+        documents
+            .entries()
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.pinned))
+            .collect()
+    }
+
+    #[test]
+    fn test_record_deduplicates_and_moves_to_front() {
+        let mut documents = RecentDocuments::default();
+        documents.record("/docs/a.pdf");
+        documents.record("/docs/b.pdf");
+        documents.record("/docs/a.pdf");
+
+        assert_eq!(
+            paths(&documents),
+            vec![("/docs/a.pdf", false), ("/docs/b.pdf", false)]
+        );
+    }
+
+    #[test]
+    fn test_record_caps_unpinned_entries() {
+        let mut documents = RecentDocuments::default();
+        documents.record("/docs/pinned.pdf");
+        documents.set_pinned("/docs/pinned.pdf", true);
+        for index in 0..MAX_RECENT + 5 {
+            documents.record(&format!("/docs/{}.pdf", index));
+        }
+
+        assert_eq!(documents.entries().len(), MAX_RECENT + 1);
+        assert_eq!(documents.entries()[0].path, "/docs/pinned.pdf");
+        assert_eq!(
+            documents.entries()[1].path,
+            format!("/docs/{}.pdf", MAX_RECENT + 4)
+        );
+    }
+
+    #[test]
+    fn test_pinning_keeps_entries_above_recent_ones() {
+        let mut documents = RecentDocuments::default();
+        documents.record("/docs/a.pdf");
+        documents.record("/docs/b.pdf");
+        documents.record("/docs/c.pdf");
+
+        assert!(documents.set_pinned("/docs/a.pdf", true));
+        documents.record("/docs/d.pdf");
+        documents.record("/docs/a.pdf");
+        assert_eq!(
+            paths(&documents),
+            vec![
+                ("/docs/a.pdf", true),
+                ("/docs/d.pdf", false),
+                ("/docs/c.pdf", false),
+                ("/docs/b.pdf", false),
+            ]
+        );
+
+        assert!(documents.set_pinned("/docs/a.pdf", false));
+        assert_eq!(
+            documents.entries()[0],
+            RecentDocument {
+                path: "/docs/a.pdf".to_string(),
+                pinned: false,
+            }
+        );
+        assert!(!documents.set_pinned("/docs/missing.pdf", true));
+    }
+
+    #[test]
+    fn test_prune_and_clear() {
+        let mut documents = RecentDocuments::default();
+        documents.record("/docs/gone.pdf");
+        documents.record("/docs/kept.pdf");
+        documents.record("/docs/pinned.pdf");
+        documents.set_pinned("/docs/pinned.pdf", true);
+
+        assert!(documents.prune(|path| path != Path::new("/docs/gone.pdf")));
+        assert!(!documents.prune(|_| true));
+        assert_eq!(documents.entries().len(), 2);
+
+        documents.clear();
+        assert_eq!(paths(&documents), vec![("/docs/pinned.pdf", true)]);
+    }
+
+    #[test]
+    fn test_menu_labels_disambiguate_shared_names() {
+        let mut documents = RecentDocuments::default();
+        documents.record("/work/report.pdf");
+        documents.record("/home/report.pdf");
+        documents.record("/home/notes.pdf");
+
+        assert_eq!(
+            menu_labels(documents.entries()),
+            vec!["notes.pdf", "report.pdf — home", "report.pdf — work"]
+        );
+    }
+}
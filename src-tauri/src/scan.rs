@@ -0,0 +1,430 @@
+use lopdf::{Dictionary, Document, Object};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::MonightError;
+use crate::pdf::{parse_document, read_pdf_bytes};
+use crate::reading_position::{file_stamp, FileStamp};
+
+/// How much a document can do beyond showing its pages.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    None,
+    /// Links out of the document
+    Low,
+    /// Actions that run on open, attachments or XFA forms
+    Medium,
+    /// Scripts, program launches or embedded media players
+    High,
+}
+
+impl fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            RiskLevel::None => "none",
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        };
+        f.write_str(level)
+    }
+}
+
+/// Active content found by `monight scan` and when a document is opened.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskReport {
+    pub path: String,
+    pub risk: RiskLevel,
+    /// JavaScript actions, from `/JS` entries or `/S /JavaScript`
+    pub javascript: usize,
+    /// The catalog runs an action when the document opens
+    pub open_action: bool,
+    /// `/AA` dictionaries triggering actions on page, field or document events
+    pub additional_actions: usize,
+    pub launch: usize,
+    pub embedded_files: usize,
+    pub rich_media: usize,
+    pub uris: usize,
+    pub xfa: bool,
+    pub encrypted: bool,
+}
+
+/// Deepest nesting of dictionaries and arrays looked into.
+const MAX_WALK_DEPTH: usize = 64;
+
+#[derive(Default)]
+struct Findings {
+    javascript: usize,
+    additional_actions: usize,
+    launch: usize,
+    embedded_files: usize,
+    rich_media: usize,
+    uris: usize,
+    xfa: bool,
+}
+
+fn is_name(dict: &Dictionary, key: &[u8], name: &[u8]) -> bool {
+    dict.get(key)
+        .and_then(Object::as_name)
+        .is_ok_and(|value| value == name)
+}
+
+impl Findings {
+    fn check(&mut self, dict: &Dictionary) {
+        if dict.has(b"JS") || is_name(dict, b"S", b"JavaScript") {
+            self.javascript += 1;
+        }
+        if is_name(dict, b"S", b"Launch") {
+            self.launch += 1;
+        }
+        if is_name(dict, b"S", b"URI") {
+            self.uris += 1;
+        }
+        if is_name(dict, b"Subtype", b"RichMedia") {
+            self.rich_media += 1;
+        }
+        if dict.has(b"AA") {
+            self.additional_actions += 1;
+        }
+        if dict.has(b"XFA") {
+            self.xfa = true;
+        }
+    }
+
+    /// Look at every dictionary within `object`, down to `MAX_WALK_DEPTH`.
+    /// References are not followed; the objects they point at are walked on their own.
+    fn walk(&mut self, object: &Object, depth: usize) {
+        if depth > MAX_WALK_DEPTH {
+            return;
+        }
+
+        match object {
+            Object::Dictionary(dict) => {
+                self.check(dict);
+                dict.iter()
+                    .for_each(|(_, value)| self.walk(value, depth + 1));
+            }
+            Object::Stream(stream) => {
+                if is_name(&stream.dict, b"Type", b"EmbeddedFile") {
+                    self.embedded_files += 1;
+                }
+                self.check(&stream.dict);
+                stream
+                    .dict
+                    .iter()
+                    .for_each(|(_, value)| self.walk(value, depth + 1));
+            }
+            Object::Array(items) => items.iter().for_each(|item| self.walk(item, depth + 1)),
+            _ => {}
+        }
+    }
+}
+
+fn risk_level(report: &RiskReport) -> RiskLevel {
+    if report.javascript > 0 || report.launch > 0 || report.rich_media > 0 {
+        RiskLevel::High
+    } else if report.embedded_files > 0
+        || report.xfa
+        || report.open_action
+        || report.additional_actions > 0
+    {
+        RiskLevel::Medium
+    } else if report.uris > 0 {
+        RiskLevel::Low
+    } else {
+        RiskLevel::None
+    }
+}
+
+fn scan(path: &Path, mut doc: Document) -> RiskReport {
+    // Object streams of encrypted files are only readable once decrypted;
+    // most such files open with an empty password.
+    let encrypted = doc.is_encrypted();
+    if encrypted {
+        let _ = doc.decrypt("");
+    }
+
+    let mut findings = Findings::default();
+    doc.objects
+        .values()
+        .for_each(|object| findings.walk(object, 0));
+
+    let open_action = doc
+        .catalog()
+        .is_ok_and(|catalog| catalog.has(b"OpenAction"));
+
+    let mut report = RiskReport {
+        path: path.to_string_lossy().to_string(),
+        risk: RiskLevel::None,
+        javascript: findings.javascript,
+        open_action,
+        additional_actions: findings.additional_actions,
+        launch: findings.launch,
+        embedded_files: findings.embedded_files,
+        rich_media: findings.rich_media,
+        uris: findings.uris,
+        xfa: findings.xfa,
+        encrypted,
+    };
+    report.risk = risk_level(&report);
+    report
+}
+
+/// Scan PDF bytes that were read from `path`.
+pub fn scan_bytes(path: &Path, bytes: &[u8]) -> Result<RiskReport, MonightError> {
+    Ok(scan(path, parse_document(path, bytes)?))
+}
+
+/// Scan the PDF at `path` for active content.
+pub fn scan_file(path: &Path) -> Result<RiskReport, MonightError> {
+    let bytes = std::fs::read(path).map_err(|e| MonightError::from_io(path, e))?;
+    scan_bytes(path, &bytes)
+}
+
+/// Reports of the documents scanned since launch, by canonical path, so that
+/// reopening a document does not scan it again until it changes.
+#[derive(Default)]
+pub struct ScanCache(Mutex<HashMap<PathBuf, (FileStamp, RiskReport)>>);
+
+impl ScanCache {
+    /// Report of the document at `path`, scanned again only once its size or
+    /// modification time changes. `bytes` are its contents when already read.
+    pub fn report(&self, path: &Path, bytes: Option<&[u8]>) -> Result<RiskReport, MonightError> {
+        let stamp = file_stamp(path)?;
+        if let Some((cached, report)) = self.0.lock().unwrap().get(path) {
+            if *cached == stamp {
+                return Ok(report.clone());
+            }
+        }
+
+        let report = match bytes {
+            Some(bytes) => scan_bytes(path, bytes)?,
+            None => scan_bytes(path, &read_pdf_bytes(path.to_string_lossy().to_string())?)?,
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (stamp, report.clone()));
+        Ok(report)
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+impl fmt::Display for RiskReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "File:               {}", self.path)?;
+        writeln!(f, "Risk:               {}", self.risk)?;
+        writeln!(f, "JavaScript:         {}", self.javascript)?;
+        writeln!(f, "Open action:        {}", yes_no(self.open_action))?;
+        writeln!(f, "Additional actions: {}", self.additional_actions)?;
+        writeln!(f, "Launch actions:     {}", self.launch)?;
+        writeln!(f, "Embedded files:     {}", self.embedded_files)?;
+        writeln!(f, "Rich media:         {}", self.rich_media)?;
+        writeln!(f, "URI links:          {}", self.uris)?;
+        writeln!(f, "XFA form:           {}", yes_no(self.xfa))?;
+        write!(f, "Encrypted:          {}", yes_no(self.encrypted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use lopdf::{dictionary, Stream};
+
+    fn fixtures() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+    }
+
+    /// A one-page document whose catalog and page carry the given extra entries.
+    fn document(catalog: Dictionary, page: Dictionary) -> Document {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let mut page_dict = dictionary! { "Type" => "Page", "Parent" => pages_id };
+        page_dict.extend(&page);
+        let page_id = doc.add_object(page_dict);
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let mut catalog_dict = dictionary! { "Type" => "Catalog", "Pages" => pages_id };
+        catalog_dict.extend(&catalog);
+        let catalog_id = doc.add_object(catalog_dict);
+        doc.trailer.set("Root", catalog_id);
+        doc
+    }
+
+    fn saved(mut doc: Document) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn scan_saved(doc: Document) -> RiskReport {
+        scan_bytes(Path::new("test.pdf"), &saved(doc)).unwrap()
+    }
+
+    #[test]
+    fn test_plain_documents_carry_no_risk() {
+        let report = scan_file(&fixtures().join("outline.pdf")).unwrap();
+        assert_eq!(report.risk, RiskLevel::None);
+        assert_eq!(report.javascript, 0);
+        assert!(!report.open_action);
+
+        let report = scan_saved(document(Dictionary::new(), Dictionary::new()));
+        assert_eq!(report.risk, RiskLevel::None);
+    }
+
+    #[test]
+    fn test_scripts_and_launch_actions_are_high_risk() {
+        let script = dictionary! {
+            "S" => "JavaScript",
+            "JS" => Object::string_literal("app.alert(1)"),
+        };
+        let launch = dictionary! {
+            "S" => "Launch",
+            "F" => Object::string_literal("setup.exe"),
+        };
+        let link = |action: Dictionary| {
+            Object::Dictionary(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "A" => action,
+            })
+        };
+        let page = dictionary! {
+            "Annots" => vec![
+                link(launch),
+                link(dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") }),
+            ],
+            "AA" => dictionary! { "O" => script.clone() },
+        };
+        let catalog = dictionary! { "OpenAction" => script };
+
+        let report = scan_saved(document(catalog, page));
+        assert_eq!(report.javascript, 2);
+        assert!(report.open_action);
+        assert_eq!(report.additional_actions, 1);
+        assert_eq!(report.launch, 1);
+        assert_eq!(report.uris, 1);
+        assert_eq!(report.risk, RiskLevel::High);
+    }
+
+    #[test]
+    fn test_attachments_and_xfa_are_medium_risk() {
+        let mut doc = document(Dictionary::new(), Dictionary::new());
+        let file = doc.add_object(Stream::new(
+            dictionary! { "Type" => "EmbeddedFile" },
+            b"payload".to_vec(),
+        ));
+        let spec = dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::string_literal("payload.bin"),
+            "EF" => dictionary! { "F" => file },
+        };
+        let form = doc.add_object(dictionary! {
+            "Fields" => Vec::<Object>::new(),
+            "XFA" => Object::string_literal("<xdp:xdp/>"),
+        });
+        let catalog_id = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+        let catalog = doc.get_dictionary_mut(catalog_id).unwrap();
+        catalog.set("AcroForm", form);
+        catalog.set(
+            "Names",
+            dictionary! {
+                "EmbeddedFiles" => dictionary! {
+                    "Names" => vec![Object::string_literal("payload.bin"), spec.into()],
+                },
+            },
+        );
+
+        let report = scan_saved(doc);
+        assert_eq!(report.embedded_files, 1);
+        assert!(report.xfa);
+        assert_eq!(report.javascript, 0);
+        assert_eq!(report.risk, RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_links_alone_are_low_risk() {
+        let page = dictionary! {
+            "Annots" => vec![Object::Dictionary(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") },
+            })],
+        };
+        let report = scan_saved(document(Dictionary::new(), page));
+        assert_eq!(report.uris, 1);
+        assert_eq!(report.risk, RiskLevel::Low);
+        assert!(report.to_string().contains("Risk:               low"));
+    }
+
+    #[test]
+    fn test_walk_stops_at_the_depth_limit() {
+        let script = Object::Dictionary(dictionary! { "S" => "JavaScript" });
+        let nested =
+            |depth: usize| (0..depth).fold(script.clone(), |inner, _| Object::Array(vec![inner]));
+
+        let mut findings = Findings::default();
+        findings.walk(&nested(MAX_WALK_DEPTH), 0);
+        assert_eq!(findings.javascript, 1);
+
+        let mut findings = Findings::default();
+        findings.walk(&nested(10 * MAX_WALK_DEPTH), 0);
+        assert_eq!(findings.javascript, 0);
+    }
+
+    #[test]
+    fn test_cache_scans_again_once_the_file_changes() {
+        let dir = temp_dir("scan", "cache");
+        let path = dir.join("report.pdf");
+        std::fs::write(&path, saved(document(Dictionary::new(), Dictionary::new()))).unwrap();
+
+        let cache = ScanCache::default();
+        assert_eq!(cache.report(&path, None).unwrap().risk, RiskLevel::None);
+        // The cached report answers without looking at the bytes again
+        assert_eq!(
+            cache.report(&path, Some(b"ignored")).unwrap().risk,
+            RiskLevel::None
+        );
+
+        let script = dictionary! {
+            "S" => "JavaScript",
+            "JS" => Object::string_literal("app.alert(1)"),
+        };
+        let scripted = saved(document(
+            dictionary! { "OpenAction" => script },
+            Dictionary::new(),
+        ));
+        std::fs::write(&path, &scripted).unwrap();
+        assert_eq!(
+            cache.report(&path, Some(&scripted)).unwrap().risk,
+            RiskLevel::High
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_scanning_rejects_non_pdfs() {
+        let err = scan_file(&fixtures().join("not_a_pdf.pdf")).unwrap_err();
+        assert!(matches!(err, MonightError::InvalidPdf { .. }));
+    }
+}
@@ -0,0 +1,560 @@
+use aes::cipher::{block_padding::NoPadding, BlockEncryptMut, KeyIvInit};
+use lopdf::{Dictionary, Document, Object};
+use md5::Md5;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::MonightError;
+use crate::pdf::{dict_get, load_document};
+
+/// Padding for passwords of the RC4 and AES-128 handlers (ISO 32000-1, 7.6.3.3).
+const PASSWORD_PAD: [u8; 32] = [
+    0x28, 0xbf, 0x4e, 0x5e, 0x4e, 0x75, 0x8a, 0x41, 0x64, 0x00, 0x4e, 0x56, 0xff, 0xfa, 0x01, 0x08,
+    0x2e, 0x2e, 0x00, 0xb6, 0xd0, 0x68, 0x3e, 0x80, 0x2f, 0x0c, 0xa9, 0xfe, 0x64, 0x53, 0x69, 0x7a,
+];
+/// `/P` bits for printing, changing the document and copying text.
+const PERMISSION_PRINT: i64 = 1 << 2;
+const PERMISSION_MODIFY: i64 = 1 << 3;
+const PERMISSION_COPY: i64 = 1 << 4;
+/// Bytes read from each end of a file when looking for its trailer.
+const TRAILER_WINDOW: u64 = 64 * 1024;
+
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+/// Cipher protecting strings and streams.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+pub enum EncryptionMethod {
+    #[serde(rename = "RC4")]
+    Rc4,
+    #[serde(rename = "AES-128")]
+    Aes128,
+    #[serde(rename = "AES-256")]
+    Aes256,
+    /// Not the standard handler, or a crypt filter it does not define
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+/// What the document allows, from its `/P` flags.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+pub struct Permissions {
+    pub print: bool,
+    pub copy: bool,
+    pub modify: bool,
+}
+
+impl Permissions {
+    /// Everything is allowed to the owner and in unencrypted documents.
+    pub const ALL: Permissions = Permissions {
+        print: true,
+        copy: true,
+        modify: true,
+    };
+
+    fn from_flags(flags: i64) -> Self {
+        Permissions {
+            print: flags & PERMISSION_PRINT != 0,
+            copy: flags & PERMISSION_COPY != 0,
+            modify: flags & PERMISSION_MODIFY != 0,
+        }
+    }
+}
+
+/// The `/Encrypt` dictionary of a document, as reported to the frontend.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSecurity {
+    /// Security handler, `Standard` for password protection
+    pub handler: String,
+    pub version: i64,
+    pub revision: i64,
+    pub method: EncryptionMethod,
+    pub key_bits: i64,
+    pub permissions: Permissions,
+    /// The empty user password does not open the document
+    pub password_required: bool,
+}
+
+/// Which password a document was unlocked with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PasswordKind {
+    User,
+    Owner,
+}
+
+/// The standard security handler's entries needed to check a password.
+struct StandardHandler<'a> {
+    revision: i64,
+    key_len: usize,
+    owner: &'a [u8],
+    user: &'a [u8],
+    flags: i64,
+    encrypt_metadata: bool,
+    id: &'a [u8],
+}
+
+fn string<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a [u8]> {
+    dict_get(doc, dict, key).and_then(|value| value.as_str().ok())
+}
+
+fn integer(doc: &Document, dict: &Dictionary, key: &[u8]) -> Option<i64> {
+    dict_get(doc, dict, key).and_then(|value| value.as_i64().ok())
+}
+
+fn encryption_method(doc: &Document, encrypt: &Dictionary, version: i64) -> EncryptionMethod {
+    match version {
+        1 | 2 => EncryptionMethod::Rc4,
+        4 | 5 => {
+            // The crypt filter named by `/StmF` says which cipher V4 and V5 use
+            let filter = dict_get(doc, encrypt, b"StmF")
+                .and_then(|name| name.as_name().ok())
+                .unwrap_or(b"Identity");
+            let method = dict_get(doc, encrypt, b"CF")
+                .and_then(|filters| filters.as_dict().ok())
+                .and_then(|filters| dict_get(doc, filters, filter))
+                .and_then(|filter| filter.as_dict().ok())
+                .and_then(|filter| dict_get(doc, filter, b"CFM"))
+                .and_then(|method| method.as_name().ok());
+            match method {
+                Some(b"V2") => EncryptionMethod::Rc4,
+                Some(b"AESV2") => EncryptionMethod::Aes128,
+                Some(b"AESV3") => EncryptionMethod::Aes256,
+                _ => EncryptionMethod::Unknown,
+            }
+        }
+        _ => EncryptionMethod::Unknown,
+    }
+}
+
+fn encrypt_dictionary(doc: &Document) -> Option<&Dictionary> {
+    dict_get(doc, &doc.trailer, b"Encrypt").and_then(|encrypt| encrypt.as_dict().ok())
+}
+
+fn standard_handler<'a>(doc: &'a Document, encrypt: &'a Dictionary) -> Option<StandardHandler<'a>> {
+    let filter = dict_get(doc, encrypt, b"Filter").and_then(|name| name.as_name().ok());
+    if filter != Some(b"Standard") {
+        return None;
+    }
+    let id = doc
+        .trailer
+        .get(b"ID")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|id| id.first())
+        .and_then(|id| id.as_str().ok())
+        .unwrap_or_default();
+
+    Some(StandardHandler {
+        revision: integer(doc, encrypt, b"R")?,
+        key_len: integer(doc, encrypt, b"Length")
+            .map_or(5, |bits| (bits / 8).clamp(5, 16) as usize),
+        owner: string(doc, encrypt, b"O")?,
+        user: string(doc, encrypt, b"U")?,
+        flags: integer(doc, encrypt, b"P")?,
+        encrypt_metadata: dict_get(doc, encrypt, b"EncryptMetadata")
+            .and_then(|value| value.as_bool().ok())
+            .unwrap_or(true),
+        id,
+    })
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            let k = state[state[i as usize].wrapping_add(state[j as usize]) as usize];
+            byte ^ k
+        })
+        .collect()
+}
+
+/// `key` with every byte XORed with `round`, for the RC4 rounds of revision 3 and later.
+fn round_key(key: &[u8], round: u8) -> Vec<u8> {
+    key.iter().map(|byte| byte ^ round).collect()
+}
+
+fn pad_password(password: &[u8]) -> Vec<u8> {
+    let len = password.len().min(32);
+    [&password[..len], &PASSWORD_PAD[..32 - len]].concat()
+}
+
+impl StandardHandler<'_> {
+    /// Repeated MD5 of revisions 3 and 4, keeping the key length each time.
+    fn stretch(&self, mut digest: Vec<u8>) -> Vec<u8> {
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = Md5::digest(&digest[..self.key_len]).to_vec();
+            }
+        }
+        digest.truncate(if self.revision == 2 { 5 } else { self.key_len });
+        digest
+    }
+
+    /// Algorithm 2: the file key for a padded user password.
+    fn file_key(&self, padded: &[u8]) -> Vec<u8> {
+        let mut md5 = Md5::new();
+        md5.update(padded);
+        md5.update(&self.owner[..self.owner.len().min(32)]);
+        md5.update((self.flags as u32).to_le_bytes());
+        md5.update(self.id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            md5.update([0xff; 4]);
+        }
+        self.stretch(md5.finalize().to_vec())
+    }
+
+    /// Algorithms 4 and 5: is `padded` the user password?
+    fn is_user_password(&self, padded: &[u8]) -> bool {
+        let key = self.file_key(padded);
+        if self.revision == 2 {
+            return self.user.get(..32) == Some(&rc4(&key, &PASSWORD_PAD)[..]);
+        }
+
+        let mut md5 = Md5::new();
+        md5.update(PASSWORD_PAD);
+        md5.update(self.id);
+        let mut hash = rc4(&key, &md5.finalize());
+        for round in 1..=19 {
+            hash = rc4(&round_key(&key, round), &hash);
+        }
+        // Only the first 16 bytes are significant
+        self.user.get(..16) == Some(&hash[..])
+    }
+
+    /// Algorithm 7: recover the user password from `/O` and check it.
+    fn is_owner_password(&self, password: &[u8]) -> bool {
+        let key = self.stretch(Md5::digest(pad_password(password)).to_vec());
+        let owner = &self.owner[..self.owner.len().min(32)];
+        let user_password = if self.revision == 2 {
+            rc4(&key, owner)
+        } else {
+            (0..=19).rev().fold(owner.to_vec(), |data, round| {
+                rc4(&round_key(&key, round), &data)
+            })
+        };
+        self.is_user_password(&user_password)
+    }
+
+    /// Algorithm 2.B of ISO 32000-2, the AES-256 password hash of revision 6.
+    /// Revision 5 is the plain SHA-256 of the same inputs.
+    fn hash(&self, password: &[u8], salt: &[u8], user: &[u8]) -> Vec<u8> {
+        let mut hash = Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .chain_update(user)
+            .finalize()
+            .to_vec();
+        if self.revision == 5 {
+            return hash;
+        }
+
+        let mut round = 0;
+        loop {
+            let block = [password, &hash, user].concat().repeat(64);
+            let mut encrypted = block.clone();
+            Aes128CbcEnc::new(hash[..16].into(), hash[16..32].into())
+                .encrypt_padded_mut::<NoPadding>(&mut encrypted, block.len())
+                .expect("64 repetitions are a whole number of blocks");
+
+            let sum: u32 = encrypted[..16].iter().map(|&byte| u32::from(byte)).sum();
+            hash = match sum % 3 {
+                0 => Sha256::digest(&encrypted).to_vec(),
+                1 => Sha384::digest(&encrypted).to_vec(),
+                _ => Sha512::digest(&encrypted).to_vec(),
+            };
+
+            round += 1;
+            if round >= 64 && u32::from(*encrypted.last().unwrap()) <= round - 32 {
+                break;
+            }
+        }
+        hash.truncate(32);
+        hash
+    }
+
+    /// Check `password` against the handler, owner first so it lifts the restrictions.
+    fn authenticate(&self, password: &[u8]) -> Option<PasswordKind> {
+        match self.revision {
+            2..=4 => {
+                if self.is_owner_password(password) {
+                    Some(PasswordKind::Owner)
+                } else if self.is_user_password(&pad_password(password)) {
+                    Some(PasswordKind::User)
+                } else {
+                    None
+                }
+            }
+            5 | 6 => {
+                // UTF-8 passwords are limited to 127 bytes
+                let password = &password[..password.len().min(127)];
+                let (user, owner) = (self.user.get(..48)?, self.owner.get(..48)?);
+                if self.hash(password, &owner[32..40], user) == owner[..32] {
+                    Some(PasswordKind::Owner)
+                } else if self.hash(password, &user[32..40], &[]) == user[..32] {
+                    Some(PasswordKind::User)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn document_security(doc: &Document) -> Option<DocumentSecurity> {
+    let encrypt = encrypt_dictionary(doc)?;
+    let handler = dict_get(doc, encrypt, b"Filter")
+        .and_then(|name| name.as_name().ok())
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .unwrap_or_default();
+    let version = integer(doc, encrypt, b"V").unwrap_or(0);
+    let method = match handler.as_str() {
+        "Standard" => encryption_method(doc, encrypt, version),
+        _ => EncryptionMethod::Unknown,
+    };
+    let key_bits = match method {
+        EncryptionMethod::Aes256 => 256,
+        EncryptionMethod::Aes128 => 128,
+        _ => integer(doc, encrypt, b"Length").unwrap_or(40),
+    };
+    // Documents with other handlers can only be opened by software that has them
+    let password_required = standard_handler(doc, encrypt)
+        .map_or(true, |standard| standard.authenticate(b"").is_none());
+
+    Some(DocumentSecurity {
+        handler,
+        version,
+        revision: integer(doc, encrypt, b"R").unwrap_or(0),
+        method,
+        key_bits,
+        permissions: integer(doc, encrypt, b"P").map_or(Permissions::ALL, Permissions::from_flags),
+        password_required,
+    })
+}
+
+/// Whether the trailer of the file at `path` may point to an encryption
+/// dictionary. Trailers sit at the end of a file, or near the start of a
+/// linearized one, so only those two windows are read.
+fn trailer_mentions_encrypt(path: &Path) -> std::io::Result<bool> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut head = Vec::new();
+    (&mut file).take(TRAILER_WINDOW).read_to_end(&mut head)?;
+    let mut tail = Vec::new();
+    if len > TRAILER_WINDOW {
+        file.seek(SeekFrom::Start(
+            len.saturating_sub(TRAILER_WINDOW).max(TRAILER_WINDOW),
+        ))?;
+        file.read_to_end(&mut tail)?;
+    }
+
+    let mentions = |bytes: &[u8]| {
+        bytes
+            .windows(b"/Encrypt".len())
+            .any(|window| window == b"/Encrypt")
+    };
+    Ok(mentions(&head) || mentions(&tail))
+}
+
+/// The security settings of the PDF at `path`, or `None` when it is not encrypted.
+pub fn read_security(path: &Path) -> Result<Option<DocumentSecurity>, MonightError> {
+    // Most documents are not encrypted; spare them reading and parsing the whole file
+    if !trailer_mentions_encrypt(path).map_err(|e| MonightError::from_io(path, e))? {
+        return Ok(None);
+    }
+    let doc = load_document(path)?;
+    Ok(document_security(&doc))
+}
+
+/// Check `password` for the PDF at `path`. The owner password lifts all
+/// restrictions. Documents that are not encrypted have nothing to unlock.
+pub fn unlock(path: &Path, password: &str) -> Result<Option<DocumentSecurity>, MonightError> {
+    let doc = load_document(path)?;
+    let Some(mut security) = document_security(&doc) else {
+        return Ok(None);
+    };
+
+    let standard = encrypt_dictionary(&doc)
+        .and_then(|encrypt| standard_handler(&doc, encrypt))
+        .ok_or_else(|| MonightError::UnsupportedEncryption {
+            path: path.to_string_lossy().to_string(),
+            handler: security.handler.clone(),
+        })?;
+    match standard.authenticate(password.as_bytes()) {
+        Some(PasswordKind::Owner) => security.permissions = Permissions::ALL,
+        Some(PasswordKind::User) => {}
+        None => {
+            return Err(MonightError::IncorrectPassword {
+                path: path.to_string_lossy().to_string(),
+            })
+        }
+    }
+    Ok(Some(security))
+}
+
+/// Permissions of the open encrypted documents, by canonical path.
+#[derive(Default)]
+pub struct DocumentPermissions(Mutex<HashMap<PathBuf, Permissions>>);
+
+impl DocumentPermissions {
+    pub fn set(&self, path: PathBuf, permissions: Permissions) {
+        self.0.lock().unwrap().insert(path, permissions);
+    }
+
+    /// Forget a document that was closed.
+    pub fn remove(&self, path: &Path) {
+        self.0.lock().unwrap().remove(path);
+    }
+
+    /// What `path` allows; documents that were never encrypted allow everything.
+    pub fn get(&self, path: &Path) -> Permissions {
+        self.0
+            .lock()
+            .unwrap()
+            .get(path)
+            .copied()
+            .unwrap_or(Permissions::ALL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/encrypted")
+            .join(name)
+    }
+
+    #[test]
+    fn test_reads_handler_revision_and_permissions() {
+        let security = read_security(&fixture("rc4.pdf")).unwrap().unwrap();
+        assert_eq!(security.handler, "Standard");
+        assert_eq!((security.version, security.revision), (2, 3));
+        assert_eq!(security.method, EncryptionMethod::Rc4);
+        assert_eq!(security.key_bits, 128);
+        assert_eq!(
+            security.permissions,
+            Permissions {
+                print: true,
+                copy: false,
+                modify: true,
+            }
+        );
+        assert!(security.password_required);
+
+        let security = read_security(&fixture("aes256.pdf")).unwrap().unwrap();
+        assert_eq!((security.version, security.revision), (5, 6));
+        assert_eq!(security.method, EncryptionMethod::Aes256);
+        assert!(!security.permissions.print);
+
+        let plain = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline.pdf");
+        assert_eq!(read_security(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_user_and_owner_passwords_unlock_every_handler() {
+        for name in ["rc4.pdf", "aes256.pdf"] {
+            let path = fixture(name);
+            let user = unlock(&path, "user").unwrap().unwrap();
+            assert_eq!(
+                user.permissions,
+                read_security(&path).unwrap().unwrap().permissions
+            );
+
+            let owner = unlock(&path, "owner").unwrap().unwrap();
+            assert_eq!(owner.permissions, Permissions::ALL, "{}", name);
+
+            for wrong in ["", "User", "owner "] {
+                let err = unlock(&path, wrong).unwrap_err();
+                assert!(
+                    matches!(err, MonightError::IncorrectPassword { .. }),
+                    "{:?} should not unlock {}",
+                    wrong,
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_user_passwords_open_with_restrictions() {
+        let path = fixture("aes128.pdf");
+        let security = read_security(&path).unwrap().unwrap();
+        assert_eq!(security.method, EncryptionMethod::Aes128);
+        assert!(!security.password_required);
+        assert_eq!(
+            security.permissions,
+            Permissions {
+                print: false,
+                copy: true,
+                modify: false,
+            }
+        );
+
+        assert_eq!(unlock(&path, "").unwrap(), Some(security.clone()));
+        let owner = unlock(&path, "owner").unwrap().unwrap();
+        assert_eq!(owner.permissions, Permissions::ALL);
+
+        let plain = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline.pdf");
+        assert_eq!(unlock(&plain, "anything").unwrap(), None);
+    }
+
+    #[test]
+    fn test_only_the_ends_of_a_file_are_searched_for_the_trailer() {
+        let dir = std::env::temp_dir().join(format!("monight-security-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let window = TRAILER_WINDOW as usize;
+
+        let middle = dir.join("middle.pdf");
+        let mut bytes = vec![b' '; 3 * window];
+        bytes[window + 10..window + 18].copy_from_slice(b"/Encrypt");
+        std::fs::write(&middle, &bytes).unwrap();
+        assert!(!trailer_mentions_encrypt(&middle).unwrap());
+
+        let end = dir.join("end.pdf");
+        bytes.extend_from_slice(b"trailer << /Encrypt 7 0 R >>");
+        std::fs::write(&end, &bytes).unwrap();
+        assert!(trailer_mentions_encrypt(&end).unwrap());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_closed_documents_allow_everything_again() {
+        let permissions = DocumentPermissions::default();
+        let path = fixture("aes128.pdf");
+        let security = read_security(&path).unwrap().unwrap();
+        permissions.set(path.clone(), security.permissions);
+        assert!(!permissions.get(&path).print);
+
+        permissions.remove(&path);
+        assert_eq!(permissions.get(&path), Permissions::ALL);
+    }
+
+    #[test]
+    fn test_rc4_matches_the_reference_vectors() {
+        assert_eq!(
+            rc4(b"Key", b"Plaintext"),
+            [0xbb, 0xf3, 0x16, 0xe8, 0xd9, 0x40, 0xaf, 0x0a, 0xd3]
+        );
+        assert_eq!(rc4(b"Wiki", b"pedia"), [0x10, 0x21, 0xbf, 0x04, 0x20]);
+    }
+}
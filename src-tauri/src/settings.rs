@@ -0,0 +1,14 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// Store the frontend keeps its settings in, under the `settings` key.
+const SETTINGS_STORE: &str = "settings.json";
+
+/// A top-level entry of the frontend's settings; unset and `null` entries are `None`.
+pub fn setting(app: &AppHandle, key: &str) -> Option<serde_json::Value> {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get("settings"))
+        .and_then(|settings| settings.get(key).cloned())
+        .filter(|value| !value.is_null())
+}
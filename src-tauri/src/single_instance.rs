@@ -0,0 +1,261 @@
+use interprocess::local_socket::{prelude::*, Listener, ListenerOptions, Name, Stream};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader, Read, Write};
+use tauri::{AppHandle, Manager};
+
+use crate::synctex::SynctexEditor;
+use crate::CliPayload;
+
+/// Upper bound on a forwarded message; a list of paths never gets close.
+const MAX_MESSAGE_LEN: u32 = 1024 * 1024;
+
+/// What a later launch hands over to the running instance.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Launch {
+    pub payload: CliPayload,
+    /// `--synctex-editor` of the later launch, replacing the running instance's
+    #[serde(default)]
+    pub synctex_editor: Option<String>,
+}
+
+/// Outcome of trying to become the primary Monight process.
+pub enum Instance {
+    /// No other instance is running; this process owns the socket.
+    Primary(Listener),
+    /// The arguments were handed to the running instance; this process should exit.
+    Forwarded,
+}
+
+/// A socket file in a folder only this user may enter, so other accounts can
+/// neither connect to the running instance nor claim the socket first.
+#[cfg(unix)]
+fn socket_name() -> io::Result<Name<'static>> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    // `$XDG_RUNTIME_DIR` where there is one, otherwise the user's cache folder
+    let base = dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no per-user directory"))?;
+    let dir = base.join(crate::cli::APP_IDENTIFIER);
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    // A folder left by an older version may be more permissive
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    dir.join("instance.sock")
+        .to_fs_name::<interprocess::local_socket::GenericFilePath>()
+}
+
+#[cfg(unix)]
+fn listener_options(name: Name<'_>) -> io::Result<ListenerOptions<'_>> {
+    Ok(ListenerOptions::new().name(name))
+}
+
+/// Named pipes are global, so the user name only keeps accounts apart; the
+/// pipe's access list is what keeps other accounts out.
+#[cfg(windows)]
+fn socket_name() -> io::Result<Name<'static>> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    format!("{}.{}.sock", crate::cli::APP_IDENTIFIER, user)
+        .to_ns_name::<interprocess::local_socket::GenericNamespaced>()
+}
+
+#[cfg(windows)]
+fn listener_options(name: Name<'_>) -> io::Result<ListenerOptions<'_>> {
+    use interprocess::os::windows::local_socket::ListenerOptionsExt;
+    use interprocess::os::windows::security_descriptor::SecurityDescriptor;
+
+    // Full access for the pipe's owner and the system, nobody else
+    let descriptor =
+        SecurityDescriptor::deserialize(widestring::u16cstr!("D:P(A;;GA;;;OW)(A;;GA;;;SY)"))?;
+    Ok(ListenerOptions::new()
+        .name(name)
+        .security_descriptor(descriptor))
+}
+
+/// Frame a launch as a big-endian `u32` length followed by its JSON encoding.
+pub(crate) fn write_message<W: Write>(writer: &mut W, launch: &Launch) -> io::Result<()> {
+    let body = serde_json::to_vec(launch)?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Read one framed launch written by [`write_message`].
+pub(crate) fn read_message<R: Read>(reader: &mut R) -> io::Result<Launch> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message exceeds maximum length",
+        ));
+    }
+
+    let mut body = vec![0; len as usize];
+    reader.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Forward the launch to a running instance, or claim the socket if there is none.
+pub fn acquire(launch: &Launch) -> io::Result<Instance> {
+    let name = socket_name()?;
+
+    if let Ok(mut stream) = Stream::connect(name.borrow()) {
+        write_message(&mut stream, launch)?;
+        return Ok(Instance::Forwarded);
+    }
+
+    // Nobody answered, so any leftover socket file belongs to a crashed instance
+    let listener = listener_options(name)?.try_overwrite(true).create_sync()?;
+    Ok(Instance::Primary(listener))
+}
+
+/// Open the files of one forwarded launch, or bring the window forward.
+fn handle(app: &AppHandle, stream: Stream) {
+    let Launch {
+        payload,
+        synctex_editor,
+    } = match read_message(&mut BufReader::new(stream)) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("Ignoring malformed single-instance message: {}", e);
+            return;
+        }
+    };
+
+    // Editors pass their command on every launch, so the latest one wins
+    if let Some(editor) = synctex_editor {
+        *app.state::<SynctexEditor>().0.lock().unwrap() = Some(editor);
+    }
+
+    if payload.files.is_empty() {
+        // A bare relaunch just brings the existing window forward
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    } else if let Some(payload) = payload.into_openable() {
+        // The sender is another process, so check its files as if it were the frontend
+        crate::dispatch_open_payload(app, payload);
+    }
+}
+
+/// Accept forwarded launches for the lifetime of the app. Each connection is
+/// read on its own thread, so a client that never writes blocks only itself.
+pub fn serve(app: AppHandle, listener: Listener) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            let app = app.clone();
+            std::thread::spawn(move || handle(&app, stream));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(payload: CliPayload) -> Launch {
+        Launch {
+            payload,
+            synctex_editor: None,
+        }
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let payload = CliPayload {
+            files: vec!["/tmp/a.pdf".to_string(), "/tmp/b c.pdf".to_string()],
+            targets: vec![crate::open_target::FileTarget {
+                file: "/tmp/b c.pdf".to_string(),
+                page: Some(12),
+                zoom: Some(1.25),
+                ..crate::open_target::FileTarget::default()
+            }],
+        };
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &launch(payload.clone())).unwrap();
+        let body_len = u32::from_be_bytes(buffer[..4].try_into().unwrap()) as usize;
+        assert_eq!(body_len, buffer.len() - 4);
+
+        let decoded = read_message(&mut buffer.as_slice()).unwrap();
+        assert_eq!(decoded, launch(payload));
+    }
+
+    #[test]
+    fn test_launch_carries_the_synctex_editor() {
+        let editor = Launch {
+            payload: CliPayload {
+                files: Vec::new(),
+                targets: Vec::new(),
+            },
+            synctex_editor: Some("nvim --headless -c %{line} %{input}".to_string()),
+        };
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &editor).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&buffer[4..]).unwrap();
+        assert_eq!(body["synctexEditor"], "nvim --headless -c %{line} %{input}");
+        assert_eq!(read_message(&mut buffer.as_slice()).unwrap(), editor);
+
+        // Launches without an editor leave the field out entirely
+        let body = br#"{"payload":{"files":["/tmp/a.pdf"]}}"#;
+        let mut framed = (body.len() as u32).to_be_bytes().to_vec();
+        framed.extend_from_slice(body);
+        let decoded = read_message(&mut framed.as_slice()).unwrap();
+        assert_eq!(decoded.synctex_editor, None);
+        assert_eq!(decoded.payload.files, vec!["/tmp/a.pdf".to_string()]);
+    }
+
+    #[test]
+    fn test_consecutive_messages_are_framed_independently() {
+        let first = CliPayload {
+            files: vec!["/tmp/one.pdf".to_string()],
+            targets: Vec::new(),
+        };
+        let second = CliPayload {
+            files: Vec::new(),
+            targets: Vec::new(),
+        };
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &launch(first.clone())).unwrap();
+        write_message(&mut buffer, &launch(second.clone())).unwrap();
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap().payload, first);
+        assert_eq!(read_message(&mut reader).unwrap().payload, second);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_read_message_rejects_truncated_and_oversized_frames() {
+        let mut buffer = Vec::new();
+        write_message(
+            &mut buffer,
+            &launch(CliPayload {
+                files: vec!["/tmp/a.pdf".to_string()],
+                targets: Vec::new(),
+            }),
+        )
+        .unwrap();
+        buffer.truncate(buffer.len() - 1);
+        let err = read_message(&mut buffer.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let oversized = (MAX_MESSAGE_LEN + 1).to_be_bytes();
+        let err = read_message(&mut oversized.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,540 @@
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::error::MonightError;
+
+/// Scaled points per PostScript (PDF) point: 65536 sp/pt * 72.27 pt/in / 72 bp/in.
+const SP_PER_BP: f64 = 65781.76;
+
+/// Editor command used for inverse search when the frontend does not supply one.
+pub struct SynctexEditor(pub Mutex<Option<String>>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RecordKind {
+    /// `[`/`(` boxes and their void `v`/`h` counterparts
+    Box,
+    /// `x`, `k`, `g` and `$` records, which only carry a position
+    Point,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Record {
+    kind: RecordKind,
+    tag: u32,
+    line: u32,
+    page: u32,
+    /// Left edge in PDF points from the page's left side
+    x: f64,
+    /// Baseline in PDF points from the page's top
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64,
+}
+
+impl Record {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let (left, right) = if self.width >= 0.0 {
+            (self.x, self.x + self.width)
+        } else {
+            (self.x + self.width, self.x)
+        };
+        x >= left && x <= right && y >= self.y - self.height && y <= self.y + self.depth
+    }
+
+    fn area(&self) -> f64 {
+        self.width.abs() * (self.height + self.depth)
+    }
+
+    fn distance_to(&self, x: f64, y: f64) -> f64 {
+        ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt()
+    }
+}
+
+/// Parsed contents of a `.synctex` / `.synctex.gz` file.
+#[derive(Debug)]
+pub struct SynctexData {
+    /// Directory of the synctex file, used to resolve relative input names
+    base_dir: PathBuf,
+    inputs: HashMap<u32, String>,
+    records: Vec<Record>,
+}
+
+/// A location in the PDF produced by forward search (1-based page, PDF points from top-left).
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SynctexPosition {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A location in the TeX sources produced by inverse search.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+/// `--synctex-forward` argument in Zathura's `LINE:COLUMN:FILE` form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForwardSearch {
+    pub line: u32,
+    pub column: Option<u32>,
+    pub file: String,
+}
+
+impl FromStr for ForwardSearch {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(3, ':');
+        let (Some(line), Some(column), Some(file)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err("expected LINE:COLUMN:FILE".to_string());
+        };
+
+        let line = line
+            .parse::<u32>()
+            .map_err(|_| format!("invalid line number: {}", line))?;
+        // Editors pass 0 or -1 when they don't know the column
+        let column = column
+            .parse::<i64>()
+            .ok()
+            .filter(|c| *c > 0)
+            .map(|c| c as u32);
+        if file.is_empty() {
+            return Err("missing source file".to_string());
+        }
+
+        Ok(ForwardSearch {
+            line,
+            column,
+            file: file.to_string(),
+        })
+    }
+}
+
+pub(crate) fn synctex_error(message: impl Into<String>) -> MonightError {
+    MonightError::Synctex {
+        message: message.into(),
+    }
+}
+
+/// Locate the synctex file produced alongside a PDF.
+pub(crate) fn synctex_path_for(pdf: &Path) -> Option<PathBuf> {
+    let stem = pdf.file_stem()?.to_string_lossy();
+    let dir = pdf.parent().unwrap_or(Path::new(""));
+    [format!("{}.synctex.gz", stem), format!("{}.synctex", stem)]
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+struct Header {
+    unit: f64,
+    magnification: f64,
+    x_offset: f64,
+    y_offset: f64,
+}
+
+impl Header {
+    fn to_points(&self, value: f64, offset: f64) -> f64 {
+        (value * self.unit + offset) * self.magnification / 1000.0 / SP_PER_BP
+    }
+
+    fn to_length(&self, value: f64) -> f64 {
+        value * self.unit * self.magnification / 1000.0 / SP_PER_BP
+    }
+}
+
+/// Parse `tag,line[,column]:h,v[:W[,H,D]]` into its numeric fields.
+fn parse_record_fields(body: &str) -> Option<(u32, u32, Vec<f64>)> {
+    let mut sections = body.split(':');
+    let mut link = sections.next()?.split(',');
+    let tag = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+
+    let numbers = sections
+        .flat_map(|section| section.split(','))
+        .map(|value| value.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if numbers.len() < 2 {
+        return None;
+    }
+
+    Some((tag, line, numbers))
+}
+
+impl SynctexData {
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Self, MonightError> {
+        let mut header = Header {
+            unit: 1.0,
+            magnification: 1000.0,
+            x_offset: 0.0,
+            y_offset: 0.0,
+        };
+        let mut inputs = HashMap::new();
+        let mut records = Vec::new();
+        let mut page = 0;
+        let mut in_content = false;
+
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("Input:") {
+                if let Some((tag, name)) = rest.split_once(':') {
+                    if let Ok(tag) = tag.parse::<u32>() {
+                        inputs.insert(tag, name.to_string());
+                    }
+                }
+                continue;
+            }
+
+            if !in_content {
+                let parse = |value: &str| value.trim().parse::<f64>().ok();
+                if let Some(value) = line.strip_prefix("Unit:").and_then(parse) {
+                    header.unit = value;
+                } else if let Some(value) = line.strip_prefix("Magnification:").and_then(parse) {
+                    header.magnification = value;
+                } else if let Some(value) = line.strip_prefix("X Offset:").and_then(parse) {
+                    header.x_offset = value;
+                } else if let Some(value) = line.strip_prefix("Y Offset:").and_then(parse) {
+                    header.y_offset = value;
+                } else if line.starts_with("Content:") {
+                    in_content = true;
+                }
+                continue;
+            }
+
+            if line.starts_with("Postamble:") {
+                break;
+            }
+
+            let Some(marker) = line.chars().next() else {
+                continue;
+            };
+            let body = &line[marker.len_utf8()..];
+            let kind = match marker {
+                '{' => {
+                    page = body.parse().unwrap_or(page + 1);
+                    continue;
+                }
+                '[' | '(' | 'v' | 'h' => RecordKind::Box,
+                'x' | 'k' | 'g' | '$' => RecordKind::Point,
+                _ => continue,
+            };
+
+            let Some((tag, source_line, numbers)) = parse_record_fields(body) else {
+                continue;
+            };
+            let is_box = kind == RecordKind::Box;
+            let length = |index: usize| {
+                numbers
+                    .get(index)
+                    .filter(|_| is_box)
+                    .map(|value| header.to_length(*value))
+                    .unwrap_or(0.0)
+            };
+
+            records.push(Record {
+                kind,
+                tag,
+                line: source_line,
+                page,
+                x: header.to_points(numbers[0], header.x_offset),
+                y: header.to_points(numbers[1], header.y_offset),
+                width: length(2),
+                height: length(3),
+                depth: length(4),
+            });
+        }
+
+        if !in_content {
+            return Err(synctex_error("SyncTeX file has no content section"));
+        }
+
+        Ok(SynctexData {
+            base_dir: base_dir.to_path_buf(),
+            inputs,
+            records,
+        })
+    }
+
+    /// Load the synctex file that belongs to `pdf`, decompressing `.gz` files.
+    pub fn load_for_pdf(pdf: &Path) -> Result<Self, MonightError> {
+        let path = synctex_path_for(pdf).ok_or_else(|| {
+            synctex_error(format!("No SyncTeX data found next to {}", pdf.display()))
+        })?;
+
+        let raw = std::fs::read(&path).map_err(|e| MonightError::from_io(&path, e))?;
+        let text = if path.extension().is_some_and(|ext| ext == "gz") {
+            let mut text = String::new();
+            GzDecoder::new(raw.as_slice())
+                .read_to_string(&mut text)
+                .map_err(|e| synctex_error(format!("Failed to decompress SyncTeX data: {}", e)))?;
+            text
+        } else {
+            String::from_utf8_lossy(&raw).into_owned()
+        };
+
+        Self::parse(&text, path.parent().unwrap_or(Path::new("")))
+    }
+
+    fn resolve_input(&self, name: &str) -> PathBuf {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+
+    /// Find the input tag whose file matches `source`.
+    fn tag_for_source(&self, source: &str) -> Option<u32> {
+        let wanted = normalize(&self.resolve_input(source));
+
+        // Prefer an exact path match, then fall back to the file name alone so a bare
+        // `chapter.tex` still finds `./sections/chapter.tex`.
+        self.inputs
+            .iter()
+            .find(|(_, name)| normalize(&self.resolve_input(name)) == wanted)
+            .or_else(|| {
+                let file_name = Path::new(source).file_name()?;
+                self.inputs
+                    .iter()
+                    .find(|(_, name)| Path::new(name).file_name() == Some(file_name))
+            })
+            .map(|(tag, _)| *tag)
+    }
+
+    /// Map a source line to the PDF region typeset from it.
+    pub fn forward(&self, source: &str, line: u32) -> Option<SynctexPosition> {
+        let tag = self.tag_for_source(source)?;
+        let candidates = self
+            .records
+            .iter()
+            .filter(|record| record.tag == tag)
+            .collect::<Vec<_>>();
+
+        // Lines without typeset material (comments, blank lines) map to the next line that has some
+        let best_line = candidates
+            .iter()
+            .map(|record| record.line)
+            .filter(|candidate| *candidate >= line)
+            .min()
+            .or_else(|| candidates.iter().map(|record| record.line).max())?;
+
+        let matches = candidates
+            .into_iter()
+            .filter(|record| record.line == best_line)
+            .collect::<Vec<_>>();
+        let page = matches.iter().map(|record| record.page).min()?;
+        let on_page = matches
+            .iter()
+            .filter(|record| record.page == page)
+            .collect::<Vec<_>>();
+
+        // Report the innermost box; points alone only give a caret position
+        let region = on_page
+            .iter()
+            .filter(|record| record.kind == RecordKind::Box && record.area() > 0.0)
+            .min_by(|a, b| a.area().total_cmp(&b.area()))
+            .copied()
+            .or_else(|| on_page.first().copied())?;
+
+        Some(SynctexPosition {
+            page,
+            x: region.x.min(region.x + region.width),
+            y: region.y - region.height,
+            width: region.width.abs(),
+            height: region.height + region.depth,
+        })
+    }
+
+    /// Map a point on a page (PDF points from top-left) to the source line that produced it.
+    pub fn inverse(&self, page: u32, x: f64, y: f64) -> Option<SourceLocation> {
+        let on_page = self
+            .records
+            .iter()
+            .filter(|record| record.page == page)
+            .collect::<Vec<_>>();
+
+        let innermost = on_page
+            .iter()
+            .filter(|record| record.kind == RecordKind::Box && record.contains(x, y))
+            .min_by(|a, b| a.area().total_cmp(&b.area()));
+
+        // Within the innermost box, the closest glyph-level record pins down the line best
+        let record = match innermost {
+            Some(container) => on_page
+                .iter()
+                .filter(|record| {
+                    record.kind == RecordKind::Point && container.contains(record.x, record.y)
+                })
+                .min_by(|a, b| a.distance_to(x, y).total_cmp(&b.distance_to(x, y)))
+                .unwrap_or(container),
+            None => on_page
+                .iter()
+                .min_by(|a, b| a.distance_to(x, y).total_cmp(&b.distance_to(x, y)))?,
+        };
+
+        let name = self.inputs.get(&record.tag)?;
+        Some(SourceLocation {
+            file: normalize(&self.resolve_input(name))
+                .to_string_lossy()
+                .to_string(),
+            line: record.line,
+        })
+    }
+}
+
+/// Lexically clean a path (drop `.` and resolve `..`) without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Build the inverse-search editor invocation from a template such as
+/// `code --goto %{input}:%{line}`.
+pub(crate) fn editor_command(
+    template: &str,
+    location: &SourceLocation,
+) -> Result<Command, MonightError> {
+    let words = shell_words::split(template)
+        .map_err(|e| synctex_error(format!("Invalid editor command: {}", e)))?;
+    let mut words = words.into_iter().map(|word| {
+        word.replace("%{input}", &location.file)
+            .replace("%{line}", &location.line.to_string())
+    });
+
+    let program = words
+        .next()
+        .ok_or_else(|| synctex_error("Editor command is empty"))?;
+    let mut command = Command::new(program);
+    command.args(words);
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/synctex")
+    }
+
+    fn load() -> SynctexData {
+        SynctexData::load_for_pdf(&fixtures().join("paper.pdf")).expect("fixture should parse")
+    }
+
+    #[test]
+    fn test_parse_forward_search_argument() {
+        assert_eq!(
+            "42:7:chapter.tex".parse::<ForwardSearch>(),
+            Ok(ForwardSearch {
+                line: 42,
+                column: Some(7),
+                file: "chapter.tex".to_string(),
+            })
+        );
+        assert_eq!(
+            "3:-1:C:\\thesis\\main.tex".parse::<ForwardSearch>(),
+            Ok(ForwardSearch {
+                line: 3,
+                column: None,
+                file: "C:\\thesis\\main.tex".to_string(),
+            })
+        );
+        assert!("main.tex".parse::<ForwardSearch>().is_err());
+        assert!("x:1:main.tex".parse::<ForwardSearch>().is_err());
+    }
+
+    #[test]
+    fn test_gzip_and_plain_files_parse_identically() {
+        let compressed = load();
+        let plain = SynctexData::load_for_pdf(&fixtures().join("plain.pdf")).unwrap();
+
+        assert_eq!(compressed.inputs, plain.inputs);
+        assert_eq!(compressed.records, plain.records);
+        assert_eq!(compressed.inputs.len(), 2);
+    }
+
+    #[test]
+    fn test_forward_search_finds_line_box() {
+        let data = load();
+
+        let position = data.forward("main.tex", 5).expect("line 5 is typeset");
+
+        assert_eq!(position.page, 1);
+        assert!((position.x - 72.0).abs() < 0.01, "x = {}", position.x);
+        assert!((position.y - 90.0).abs() < 0.01, "y = {}", position.y);
+        assert!((position.width - 468.0).abs() < 0.01);
+        assert!((position.height - 12.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_forward_search_resolves_relative_inputs_and_skips_blank_lines() {
+        let data = load();
+
+        let position = data
+            .forward(&fixtures().join("chapter.tex").to_string_lossy(), 9)
+            .expect("chapter.tex should resolve against the synctex directory");
+        assert_eq!(position.page, 2);
+        assert!((position.y - 90.0).abs() < 0.01);
+
+        assert!(data.forward("missing.tex", 1).is_none());
+    }
+
+    #[test]
+    fn test_inverse_search_picks_innermost_line() {
+        let data = load();
+
+        let location = data
+            .inverse(1, 150.0, 115.0)
+            .expect("point is inside a line box");
+        assert_eq!(location.line, 6);
+        assert!(location.file.ends_with("main.tex"));
+
+        let location = data.inverse(2, 100.0, 95.0).unwrap();
+        assert_eq!(location.line, 10);
+        assert_eq!(PathBuf::from(location.file), fixtures().join("chapter.tex"));
+    }
+
+    #[test]
+    fn test_missing_synctex_file_is_reported() {
+        let err = SynctexData::load_for_pdf(&fixtures().join("absent.pdf")).unwrap_err();
+        assert!(matches!(err, MonightError::Synctex { .. }));
+    }
+
+    #[test]
+    fn test_editor_command_substitutes_placeholders() {
+        let location = SourceLocation {
+            file: "/home/me/my thesis/main.tex".to_string(),
+            line: 12,
+        };
+
+        let command = editor_command("code --goto '%{input}:%{line}'", &location).unwrap();
+
+        assert_eq!(command.get_program(), "code");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["--goto", "/home/me/my thesis/main.tex:12"]
+        );
+        assert!(editor_command("", &location).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use std::path::{Path, PathBuf};
+
+/// A canonical scratch directory for a test, unique to `module`, `name` and this process.
+pub fn temp_dir(module: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "monight-{}-{}-{}",
+        module,
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::canonicalize(dir).unwrap()
+}
+
+/// A file under `tests/fixtures`.
+pub fn fixture(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(relative)
+}
+
+/// Copy a fixture to `destination`, creating its folder, for tests that change it.
+pub fn copy_fixture(relative: &str, destination: &Path) -> PathBuf {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::copy(fixture(relative), destination).unwrap();
+    destination.to_path_buf()
+}
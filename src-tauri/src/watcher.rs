@@ -0,0 +1,379 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Quiet period after the last filesystem event before a change is reported.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// How long a watched file may be missing (e.g. mid atomic save) before we stop waiting for it.
+const MISSING_GRACE: Duration = Duration::from_secs(5);
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+/// Size and modification time used to tell real changes from no-op events.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct FileSnapshot {
+    size: u64,
+    modified_ms: u64,
+}
+
+impl FileSnapshot {
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        Some(FileSnapshot {
+            size: metadata.len(),
+            modified_ms,
+        })
+    }
+}
+
+/// Payload of the `document-changed` event
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentChanged {
+    path: String,
+    size: u64,
+    modified_ms: u64,
+}
+
+#[derive(Debug)]
+struct PendingChange {
+    last_event: Instant,
+    missing_since: Option<Instant>,
+}
+
+/// Coalesces bursts of filesystem events per path and waits out files that
+/// briefly disappear while an editor replaces them.
+pub(crate) struct Debouncer {
+    quiet: Duration,
+    grace: Duration,
+    pending: HashMap<PathBuf, PendingChange>,
+}
+
+impl Debouncer {
+    pub(crate) fn new(quiet: Duration, grace: Duration) -> Self {
+        Debouncer {
+            quiet,
+            grace,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, path: PathBuf, now: Instant) {
+        self.pending
+            .entry(path)
+            .and_modify(|change| change.last_event = now)
+            .or_insert(PendingChange {
+                last_event: now,
+                missing_since: None,
+            });
+    }
+
+    /// When the next pending change becomes due, if any.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|change| change.last_event + self.quiet)
+            .min()
+    }
+
+    /// Release every change that has been quiet long enough and whose file exists.
+    /// Missing files are retried until the grace period runs out.
+    pub(crate) fn poll<F>(&mut self, now: Instant, stat: F) -> Vec<(PathBuf, FileSnapshot)>
+    where
+        F: Fn(&Path) -> Option<FileSnapshot>,
+    {
+        let mut ready = Vec::new();
+        let quiet = self.quiet;
+        let grace = self.grace;
+
+        self.pending.retain(|path, change| {
+            if now.duration_since(change.last_event) < quiet {
+                return true;
+            }
+
+            match stat(path) {
+                Some(snapshot) => {
+                    ready.push((path.clone(), snapshot));
+                    false
+                }
+                None => {
+                    let missing_since = *change.missing_since.get_or_insert(now);
+                    change.last_event = now;
+                    now.duration_since(missing_since) < grace
+                }
+            }
+        });
+
+        ready
+    }
+}
+
+/// Watched documents and the directories observed on their behalf.
+///
+/// Directories rather than files are watched so that replace-by-rename saves,
+/// which swap out the inode, keep being reported.
+#[derive(Default)]
+pub(crate) struct WatchRegistry {
+    documents: HashMap<PathBuf, Option<FileSnapshot>>,
+    directories: HashMap<PathBuf, usize>,
+}
+
+impl WatchRegistry {
+    /// Track a document, returning its directory if that directory is newly watched.
+    pub(crate) fn add(&mut self, path: PathBuf) -> Option<PathBuf> {
+        if self.documents.contains_key(&path) {
+            return None;
+        }
+        let dir = path.parent()?.to_path_buf();
+        self.documents
+            .insert(path.clone(), FileSnapshot::read(&path));
+
+        let count = self.directories.entry(dir.clone()).or_insert(0);
+        *count += 1;
+        (*count == 1).then_some(dir)
+    }
+
+    /// Stop tracking a document, returning its directory if nothing else needs it.
+    pub(crate) fn remove(&mut self, path: &Path) -> Option<PathBuf> {
+        self.documents.remove(path)?;
+        let dir = path.parent()?.to_path_buf();
+
+        let count = self.directories.get_mut(&dir)?;
+        *count -= 1;
+        if *count == 0 {
+            self.directories.remove(&dir);
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn is_watched(&self, path: &Path) -> bool {
+        self.documents.contains_key(path)
+    }
+
+    /// Store the latest snapshot, returning true if it differs from the previous one.
+    pub(crate) fn update(&mut self, path: &Path, snapshot: FileSnapshot) -> bool {
+        match self.documents.get_mut(path) {
+            Some(previous) if *previous != Some(snapshot) => {
+                *previous = Some(snapshot);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// File-watcher subsystem that reports on-disk changes of open documents.
+pub struct DocumentWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    registry: Mutex<WatchRegistry>,
+}
+
+impl DocumentWatcher {
+    /// Create the platform watcher and the thread that debounces its events.
+    pub fn start(app: &AppHandle) -> Self {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        });
+
+        let watcher = match watcher {
+            Ok(watcher) => {
+                let app = app.clone();
+                std::thread::spawn(move || run_debounce_loop(app, rx));
+                Some(watcher)
+            }
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("File watching unavailable: {}", _e);
+                None
+            }
+        };
+
+        DocumentWatcher {
+            watcher: Mutex::new(watcher),
+            registry: Mutex::new(WatchRegistry::default()),
+        }
+    }
+
+    pub fn watch(&self, path: &Path) -> notify::Result<()> {
+        let dir = self.registry.lock().unwrap().add(path.to_path_buf());
+        if let (Some(dir), Some(watcher)) = (dir, self.watcher.lock().unwrap().as_mut()) {
+            if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                self.registry.lock().unwrap().remove(path);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unwatch(&self, path: &Path) -> notify::Result<()> {
+        let dir = self.registry.lock().unwrap().remove(path);
+        if let (Some(dir), Some(watcher)) = (dir, self.watcher.lock().unwrap().as_mut()) {
+            watcher.unwatch(&dir)?;
+        }
+        Ok(())
+    }
+}
+
+fn run_debounce_loop(app: AppHandle, rx: Receiver<PathBuf>) {
+    let mut debouncer = Debouncer::new(DEBOUNCE, MISSING_GRACE);
+
+    loop {
+        let timeout = debouncer
+            .next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or(IDLE_WAIT);
+
+        match rx.recv_timeout(timeout) {
+            Ok(path) => {
+                let state = app.state::<DocumentWatcher>();
+                if state.registry.lock().unwrap().is_watched(&path) {
+                    debouncer.record(path, Instant::now());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        for (path, snapshot) in debouncer.poll(Instant::now(), FileSnapshot::read) {
+            let state = app.state::<DocumentWatcher>();
+            if !state.registry.lock().unwrap().update(&path, snapshot) {
+                continue;
+            }
+
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.emit(
+                    "document-changed",
+                    DocumentChanged {
+                        path: path.to_string_lossy().to_string(),
+                        size: snapshot.size,
+                        modified_ms: snapshot.modified_ms,
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(size: u64) -> FileSnapshot {
+        FileSnapshot {
+            size,
+            modified_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_debouncer_coalesces_bursts() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(300), Duration::from_secs(5));
+        let start = Instant::now();
+        let path = PathBuf::from("/docs/report.pdf");
+
+        debouncer.record(path.clone(), start);
+        debouncer.record(path.clone(), start + Duration::from_millis(200));
+
+        let ready = debouncer.poll(start + Duration::from_millis(400), |_| Some(snapshot(10)));
+        assert!(ready.is_empty(), "change should wait for a quiet period");
+
+        let ready = debouncer.poll(start + Duration::from_millis(500), |_| Some(snapshot(10)));
+        assert_eq!(ready, vec![(path, snapshot(10))]);
+        assert!(debouncer.next_deadline().is_none());
+    }
+
+    #[test]
+    fn test_debouncer_waits_for_missing_file() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(300), Duration::from_secs(5));
+        let start = Instant::now();
+        let path = PathBuf::from("/docs/report.pdf");
+
+        debouncer.record(path.clone(), start);
+        let ready = debouncer.poll(start + Duration::from_millis(300), |_| None);
+        assert!(ready.is_empty());
+        assert!(
+            debouncer.next_deadline().is_some(),
+            "missing file should be retried"
+        );
+
+        let ready = debouncer.poll(start + Duration::from_millis(700), |_| Some(snapshot(42)));
+        assert_eq!(ready, vec![(path, snapshot(42))]);
+    }
+
+    #[test]
+    fn test_debouncer_gives_up_after_grace_period() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(300), Duration::from_secs(5));
+        let start = Instant::now();
+
+        debouncer.record(PathBuf::from("/docs/deleted.pdf"), start);
+        debouncer.poll(start + Duration::from_millis(300), |_| None);
+        debouncer.poll(start + Duration::from_secs(6), |_| None);
+
+        assert!(debouncer.next_deadline().is_none());
+    }
+
+    #[test]
+    fn test_registry_shares_directory_watches() {
+        let mut registry = WatchRegistry::default();
+        let a = PathBuf::from("/docs/a.pdf");
+        let b = PathBuf::from("/docs/b.pdf");
+
+        assert_eq!(registry.add(a.clone()), Some(PathBuf::from("/docs")));
+        assert_eq!(registry.add(b.clone()), None);
+        assert_eq!(registry.add(a.clone()), None);
+
+        assert_eq!(registry.remove(&a), None);
+        assert!(!registry.is_watched(&a));
+        assert_eq!(registry.remove(&b), Some(PathBuf::from("/docs")));
+        assert_eq!(registry.remove(&b), None);
+    }
+
+    #[test]
+    fn test_registry_reports_only_real_changes() {
+        let mut registry = WatchRegistry::default();
+        let path = PathBuf::from("/docs/missing.pdf");
+        registry.add(path.clone());
+
+        assert!(registry.update(&path, snapshot(10)));
+        assert!(!registry.update(&path, snapshot(10)));
+        assert!(registry.update(&path, snapshot(11)));
+        assert!(!registry.update(Path::new("/docs/other.pdf"), snapshot(1)));
+    }
+
+    #[test]
+    fn test_change_events_use_camel_case() {
+        let change = DocumentChanged {
+            path: "/docs/report.pdf".to_string(),
+            size: 10,
+            modified_ms: 1_700_000_000_000,
+        };
+        let json = serde_json::to_value(change).unwrap();
+        assert_eq!(json["modifiedMs"], 1_700_000_000_000u64);
+    }
+}
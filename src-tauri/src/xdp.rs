@@ -0,0 +1,188 @@
+use base64::Engine;
+use roxmltree::Node;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::document_kind::{detect_document_kind_bytes, DocumentKind};
+use crate::error::MonightError;
+use crate::form_data::{invalid_form_data, resolve_target};
+
+/// Where the PDF of an XDP package lives.
+#[derive(Debug, PartialEq)]
+pub(crate) enum XdpPdf {
+    /// Decoded from the base64 `<chunk>` elements
+    Embedded(Vec<u8>),
+    /// Canonical path of the PDF named by `<pdf href>`
+    Referenced(PathBuf),
+}
+
+/// An unpacked XDP package.
+#[derive(Debug)]
+pub(crate) struct XdpPackage {
+    pub pdf: XdpPdf,
+    /// Contents of `<xfa:datasets><xfa:data>`, if the package carries any
+    pub data: Option<Value>,
+}
+
+impl XdpPackage {
+    /// Bytes of the PDF, reading a referenced one from disk.
+    pub fn into_pdf_bytes(self) -> Result<Vec<u8>, MonightError> {
+        match self.pdf {
+            XdpPdf::Embedded(bytes) => Ok(bytes),
+            XdpPdf::Referenced(path) => {
+                std::fs::read(&path).map_err(|e| MonightError::from_io(&path, e))
+            }
+        }
+    }
+}
+
+fn element<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Convert an XFA data element to JSON: leaves become strings, groups become
+/// objects, and repeated siblings become arrays.
+fn data_value(node: Node) -> Value {
+    let mut children = node.children().filter(Node::is_element).peekable();
+    if children.peek().is_none() {
+        return Value::String(node.text().unwrap_or_default().to_string());
+    }
+
+    let mut object = Map::new();
+    for child in children {
+        let name = child.tag_name().name().to_string();
+        let value = data_value(child);
+        match object.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                object.insert(name, value);
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+fn embedded_pdf(path: &Path, document: Node) -> Result<Vec<u8>, MonightError> {
+    let encoded: String = document
+        .children()
+        .filter(|child| child.is_element() && child.tag_name().name() == "chunk")
+        .flat_map(|chunk| chunk.text().unwrap_or_default().chars())
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| invalid_form_data(path, format!("embedded PDF: {}", e)))?;
+    if detect_document_kind_bytes(&bytes) != Some(DocumentKind::Pdf) {
+        return Err(invalid_form_data(path, "embedded document is not a PDF"));
+    }
+    Ok(bytes)
+}
+
+/// Parse XDP bytes that were read from `path`.
+pub(crate) fn parse_xdp(path: &Path, bytes: &[u8]) -> Result<XdpPackage, MonightError> {
+    let text = std::str::from_utf8(bytes).map_err(|e| invalid_form_data(path, e.to_string()))?;
+    let xml =
+        roxmltree::Document::parse(text).map_err(|e| invalid_form_data(path, e.to_string()))?;
+    let root = xml.root_element();
+    if root.tag_name().name() != "xdp" {
+        return Err(invalid_form_data(path, "root element is not <xdp:xdp>"));
+    }
+
+    let pdf = element(root, "pdf").ok_or_else(|| invalid_form_data(path, "no <pdf> packet"))?;
+    let pdf = match (pdf.attribute("href"), element(pdf, "document")) {
+        (_, Some(document)) => XdpPdf::Embedded(embedded_pdf(path, document)?),
+        (Some(href), None) => XdpPdf::Referenced(resolve_target(path, href)?),
+        (None, None) => {
+            return Err(invalid_form_data(
+                path,
+                "<pdf> packet has neither a document nor an href",
+            ))
+        }
+    };
+
+    let data = element(root, "datasets")
+        .and_then(|datasets| element(datasets, "data"))
+        .map(data_value);
+
+    Ok(XdpPackage { pdf, data })
+}
+
+/// Read and unpack an XDP file from disk.
+pub(crate) fn read_xdp(path: &Path) -> Result<XdpPackage, MonightError> {
+    let bytes = std::fs::read(path).map_err(|e| MonightError::from_io(path, e))?;
+    parse_xdp(path, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn forms() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms")
+    }
+
+    fn expected_data() -> Value {
+        json!({
+            "form1": {
+                "name": "Ada Lovelace",
+                "address": { "street": "12 St James\u{2019}s Sq", "city": "London" },
+                "subscribe": "1",
+                "color": ["red", "blue"],
+                "notes": "",
+            }
+        })
+    }
+
+    #[test]
+    fn test_embedded_pdf_is_decoded_from_chunks() {
+        let package = read_xdp(&forms().join("embedded.xdp")).unwrap();
+
+        assert_eq!(package.data, Some(expected_data()));
+        assert_eq!(
+            package.into_pdf_bytes().unwrap(),
+            std::fs::read(forms().join("form.pdf")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_referenced_pdf_is_resolved_next_to_the_package() {
+        let package = read_xdp(&forms().join("referenced.xdp")).unwrap();
+
+        assert_eq!(package.data, Some(expected_data()));
+        assert_eq!(
+            package.pdf,
+            XdpPdf::Referenced(std::fs::canonicalize(forms().join("form.pdf")).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_invalid_packages_are_rejected() {
+        let path = Path::new("broken.xdp");
+        let parse = |xml: &str| parse_xdp(path, xml.as_bytes()).unwrap_err().code();
+
+        assert_eq!(parse("<xdp:xdp xmlns:xdp=\"x\"/>"), "INVALID_FORM_DATA");
+        assert_eq!(
+            parse("<xdp><pdf><document><chunk>not base64!</chunk></document></pdf></xdp>"),
+            "INVALID_FORM_DATA"
+        );
+        assert_eq!(
+            parse("<xdp><pdf><document><chunk>aGVsbG8=</chunk></document></pdf></xdp>"),
+            "INVALID_FORM_DATA"
+        );
+        assert_eq!(parse("<xfdf/>"), "INVALID_FORM_DATA");
+
+        let package = parse_xdp(
+            path,
+            b"<xdp><pdf><document><chunk>JVBERi0xLjc=</chunk></document></pdf></xdp>",
+        )
+        .unwrap();
+        assert_eq!(package.data, None);
+    }
+}
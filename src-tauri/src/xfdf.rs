@@ -0,0 +1,398 @@
+use roxmltree::Node;
+use std::path::Path;
+
+use crate::error::MonightError;
+use crate::form_data::{
+    field_tree, invalid_form_data, Annotation, FieldTree, FieldValue, FormValue, ParsedFormData,
+};
+use crate::pdf::{format_pdf_date, to_pdf_date};
+
+/// XFDF annotation element names and the PDF subtypes they stand for.
+pub(crate) const ANNOTATION_ELEMENTS: &[(&str, &str)] = &[
+    ("text", "Text"),
+    ("freetext", "FreeText"),
+    ("line", "Line"),
+    ("square", "Square"),
+    ("circle", "Circle"),
+    ("polygon", "Polygon"),
+    ("polyline", "PolyLine"),
+    ("highlight", "Highlight"),
+    ("underline", "Underline"),
+    ("squiggly", "Squiggly"),
+    ("strikeout", "StrikeOut"),
+    ("stamp", "Stamp"),
+    ("caret", "Caret"),
+    ("ink", "Ink"),
+    ("fileattachment", "FileAttachment"),
+    ("sound", "Sound"),
+];
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Comma or whitespace separated numbers, as used by `rect` and `coords`.
+fn numbers(text: &str) -> Vec<f64> {
+    text.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// `#RRGGBB` to components in `0.0..=1.0`.
+fn color(text: &str) -> Option<[f64; 3]> {
+    let hex = text.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let component = |index: usize| {
+        let value = u8::from_str_radix(hex.get(index..index + 2)?, 16).ok()?;
+        Some(f64::from(value) / 255.0)
+    };
+    Some([component(0)?, component(2)?, component(4)?])
+}
+
+fn collect_fields(node: Node, parent: Option<&str>, out: &mut Vec<FieldValue>) {
+    for field in children(node, "field") {
+        let Some(partial) = field.attribute("name") else {
+            continue;
+        };
+        let name = match parent {
+            Some(parent) => format!("{}.{}", parent, partial),
+            None => partial.to_string(),
+        };
+
+        let mut values: Vec<String> = children(field, "value")
+            .map(|value| value.text().unwrap_or_default().to_string())
+            .collect();
+        let value = match values.len() {
+            0 => None,
+            1 => values.pop().map(FormValue::Single),
+            _ => Some(FormValue::Multiple(values)),
+        };
+        if let Some(value) = value {
+            out.push(FieldValue {
+                name: name.clone(),
+                value,
+            });
+        }
+
+        collect_fields(field, Some(&name), out);
+    }
+}
+
+fn annotation(node: Node) -> Option<Annotation> {
+    let element = node.tag_name().name();
+    let (_, subtype) = ANNOTATION_ELEMENTS
+        .iter()
+        .find(|(name, _)| *name == element)?;
+    let attribute = |name: &str| node.attribute(name).map(str::to_string);
+
+    Some(Annotation {
+        subtype: subtype.to_string(),
+        page: node.attribute("page")?.trim().parse().ok()?,
+        rect: numbers(node.attribute("rect")?).try_into().ok()?,
+        contents: children(node, "contents")
+            .next()
+            .map(|contents| contents.text().unwrap_or_default().to_string()),
+        author: attribute("title"),
+        color: node.attribute("color").and_then(color),
+        name: attribute("name"),
+        modified: node
+            .attribute("date")
+            .map(|raw| format_pdf_date(raw).unwrap_or_else(|| raw.to_string())),
+        quad_points: node.attribute("coords").map(numbers).unwrap_or_default(),
+        ink_list: children(node, "inklist")
+            .flat_map(|list| children(list, "gesture"))
+            .map(|gesture| numbers(gesture.text().unwrap_or_default()))
+            .collect(),
+    })
+}
+
+/// Parse an XFDF document.
+pub(crate) fn parse_xfdf(path: &Path, bytes: &[u8]) -> Result<ParsedFormData, MonightError> {
+    let text = std::str::from_utf8(bytes).map_err(|e| invalid_form_data(path, e.to_string()))?;
+    let xml =
+        roxmltree::Document::parse(text).map_err(|e| invalid_form_data(path, e.to_string()))?;
+    let root = xml.root_element();
+    if root.tag_name().name() != "xfdf" {
+        return Err(invalid_form_data(path, "root element is not <xfdf>"));
+    }
+
+    let mut fields = Vec::new();
+    for list in children(root, "fields") {
+        collect_fields(list, None, &mut fields);
+    }
+
+    let annotations = children(root, "annots")
+        .flat_map(|annots| annots.children())
+        .filter(Node::is_element)
+        .filter_map(annotation)
+        .collect();
+    let target = children(root, "f")
+        .next()
+        .and_then(|f| f.attribute("href"))
+        .map(str::to_string);
+    let document_id = children(root, "ids")
+        .next()
+        .and_then(|ids| ids.attribute("original"))
+        .map(str::to_string);
+
+    Ok(ParsedFormData {
+        target,
+        document_id,
+        fields,
+        annotations,
+    })
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\r' => escaped.push_str("&#13;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_fields(out: &mut String, nodes: &[FieldTree], indent: usize) {
+    let pad = "  ".repeat(indent);
+    for node in nodes {
+        out.push_str(&format!("{}<field name=\"{}\">", pad, escape(node.name)));
+        let values: &[String] = match node.value {
+            Some(FormValue::Single(value)) => std::slice::from_ref(value),
+            Some(FormValue::Multiple(values)) => values,
+            None => &[],
+        };
+        for value in values {
+            out.push_str(&format!("<value>{}</value>", escape(value)));
+        }
+        if !node.kids.is_empty() {
+            out.push('\n');
+            write_fields(out, &node.kids, indent + 1);
+            out.push_str(&pad);
+        }
+        out.push_str("</field>\n");
+    }
+}
+
+fn join_numbers(numbers: &[f64]) -> String {
+    numbers
+        .iter()
+        .map(f64::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// An RGB colour as `#RRGGBB`.
+pub(crate) fn hex_color(color: [f64; 3]) -> String {
+    let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+fn write_annotation(out: &mut String, annotation: &Annotation) {
+    // Subtypes XFDF cannot express are left out rather than written wrongly
+    let Some((element, _)) = ANNOTATION_ELEMENTS
+        .iter()
+        .find(|(_, subtype)| *subtype == annotation.subtype)
+    else {
+        return;
+    };
+
+    let mut attributes = vec![
+        ("page", annotation.page.to_string()),
+        ("rect", join_numbers(&annotation.rect)),
+    ];
+    if let Some(color) = annotation.color {
+        attributes.push(("color", hex_color(color)));
+    }
+    if let Some(name) = &annotation.name {
+        attributes.push(("name", name.clone()));
+    }
+    if let Some(author) = &annotation.author {
+        attributes.push(("title", author.clone()));
+    }
+    if let Some(date) = annotation.modified.as_deref().and_then(to_pdf_date) {
+        attributes.push(("date", date));
+    }
+    if !annotation.quad_points.is_empty() {
+        attributes.push(("coords", join_numbers(&annotation.quad_points)));
+    }
+
+    out.push_str(&format!("    <{}", element));
+    for (key, value) in attributes {
+        out.push_str(&format!(" {}=\"{}\"", key, escape(&value)));
+    }
+    out.push_str(">\n");
+    if let Some(contents) = &annotation.contents {
+        out.push_str(&format!(
+            "      <contents>{}</contents>\n",
+            escape(contents)
+        ));
+    }
+    if !annotation.ink_list.is_empty() {
+        out.push_str("      <inklist>");
+        for gesture in &annotation.ink_list {
+            let points: Vec<String> = gesture.chunks(2).map(join_numbers).collect();
+            out.push_str(&format!("<gesture>{}</gesture>", points.join(";")));
+        }
+        out.push_str("</inklist>\n");
+    }
+    out.push_str(&format!("    </{}>\n", element));
+}
+
+/// Write field values and annotations as an XFDF document for `target`.
+/// `document_id` identifies the target's contents.
+pub(crate) fn write_xfdf(
+    target: &str,
+    document_id: Option<&str>,
+    fields: &[FieldValue],
+    annotations: &[Annotation],
+) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n",
+    );
+    out.push_str(&format!("  <f href=\"{}\"/>\n", escape(target)));
+    if let Some(id) = document_id {
+        let id = escape(id);
+        out.push_str(&format!(
+            "  <ids original=\"{}\" modified=\"{}\"/>\n",
+            id, id
+        ));
+    }
+    if !fields.is_empty() {
+        out.push_str("  <fields>\n");
+        write_fields(&mut out, &field_tree(fields), 2);
+        out.push_str("  </fields>\n");
+    }
+    if !annotations.is_empty() {
+        out.push_str("  <annots>\n");
+        for annotation in annotations {
+            write_annotation(&mut out, annotation);
+        }
+        out.push_str("  </annots>\n");
+    }
+    out.push_str("</xfdf>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> ParsedFormData {
+        parse_xfdf(Path::new("test.xfdf"), xml.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_fields_annotations_and_target() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms/form.xfdf");
+        let data = parse_xfdf(&path, &std::fs::read(&path).unwrap()).unwrap();
+
+        assert_eq!(data.target.as_deref(), Some("form.pdf"));
+        let names: Vec<&str> = data.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "name",
+                "address.street",
+                "address.city",
+                "subscribe",
+                "colors"
+            ]
+        );
+        assert_eq!(
+            data.fields[4].value,
+            FormValue::Multiple(vec!["red".to_string(), "blue".to_string()])
+        );
+
+        let note = &data.annotations[0];
+        assert_eq!(note.subtype, "Text");
+        assert_eq!(note.rect, [100.0, 100.0, 120.0, 120.0]);
+        assert_eq!(note.color, Some([1.0, 128.0 / 255.0, 0.0]));
+        assert_eq!(note.modified.as_deref(), Some("2024-01-02T03:04:05Z"));
+
+        let ink = &data.annotations[2];
+        assert_eq!(ink.subtype, "Ink");
+        assert_eq!(
+            ink.ink_list,
+            vec![
+                vec![10.0, 10.0, 20.0, 30.0, 50.0, 50.0],
+                vec![12.0, 40.0, 40.0, 12.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_incomplete_annotations_are_skipped() {
+        let data = parse(
+            r#"<xfdf><annots>
+                <link page="0" rect="0,0,1,1"/>
+                <square rect="0,0,1,1"/>
+                <circle page="2" rect="1 2 3 4"/>
+            </annots></xfdf>"#,
+        );
+
+        assert_eq!(data.annotations.len(), 1);
+        assert_eq!(data.annotations[0].subtype, "Circle");
+        assert_eq!(data.annotations[0].rect, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(data.target, None);
+    }
+
+    #[test]
+    fn test_written_xfdf_parses_back() {
+        let fields = vec![
+            FieldValue {
+                name: "address.street".to_string(),
+                value: FormValue::Single("Smith & <Sons> \"Ltd\"".to_string()),
+            },
+            FieldValue {
+                name: "address.city".to_string(),
+                value: FormValue::Single("Zoë\r\nLondon".to_string()),
+            },
+            FieldValue {
+                name: "colors".to_string(),
+                value: FormValue::Multiple(vec!["red".to_string(), "blue".to_string()]),
+            },
+        ];
+
+        let xml = write_xfdf("a&b.pdf", None, &fields, &[]);
+
+        let data = parse(&xml);
+        assert_eq!(data.target.as_deref(), Some("a&b.pdf"));
+        assert_eq!(data.fields, fields);
+    }
+
+    #[test]
+    fn test_annotations_and_ids_round_trip() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/forms/form.xfdf");
+        let original = parse_xfdf(&path, &std::fs::read(&path).unwrap()).unwrap();
+
+        let xml = write_xfdf("form.pdf", Some("abc123"), &[], &original.annotations);
+
+        let data = parse(&xml);
+        assert_eq!(data.document_id.as_deref(), Some("abc123"));
+        assert!(data.fields.is_empty());
+        assert_eq!(data.annotations, original.annotations);
+    }
+
+    #[test]
+    fn test_rejects_other_xml() {
+        let err = parse_xfdf(Path::new("x.xfdf"), b"<xdp/>").unwrap_err();
+        assert_eq!(err.code(), "INVALID_FORM_DATA");
+
+        let err = parse_xfdf(Path::new("x.xfdf"), b"<xfdf>").unwrap_err();
+        assert_eq!(err.code(), "INVALID_FORM_DATA");
+    }
+}
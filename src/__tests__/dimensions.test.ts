@@ -1,5 +1,5 @@
 import { describe, expect, it } from 'vitest';
-import { deriveScaledDimensions } from '../lib/dimensions';
+import { deriveScaledDimensions, rotatePagePoint, unrotatePagePoint } from '../lib/dimensions';
 
 describe('deriveScaledDimensions', () => {
   it('returns base dimensions at zoom=1 and rotation=0', () => {
@@ -62,3 +62,27 @@ describe('deriveScaledDimensions', () => {
     expect(result.height).toBe(792);
   });
 });
+
+describe('rotatePagePoint', () => {
+  it('moves the top-left corner clockwise with the page', () => {
+    expect(rotatePagePoint({ x: 0, y: 0 }, 0)).toEqual({ x: 0, y: 0 });
+    expect(rotatePagePoint({ x: 0, y: 0 }, 90)).toEqual({ x: 1, y: 0 });
+    expect(rotatePagePoint({ x: 0, y: 0 }, 180)).toEqual({ x: 1, y: 1 });
+    expect(rotatePagePoint({ x: 0, y: 0 }, 270)).toEqual({ x: 0, y: 1 });
+  });
+
+  it('normalizes negative rotations', () => {
+    expect(rotatePagePoint({ x: 0.25, y: 0.5 }, -90)).toEqual(
+      rotatePagePoint({ x: 0.25, y: 0.5 }, 270),
+    );
+  });
+});
+
+describe('unrotatePagePoint', () => {
+  it('undoes rotatePagePoint at every rotation', () => {
+    const point = { x: 0.25, y: 0.75 };
+    for (const rotation of [0, 90, 180, 270]) {
+      expect(unrotatePagePoint(rotatePagePoint(point, rotation), rotation)).toEqual(point);
+    }
+  });
+});
@@ -0,0 +1,58 @@
+import { describe, expect, it } from 'vitest';
+import { fetchRange } from '../lib/document-stream';
+
+const FILE = Uint8Array.from({ length: 100 }, (_, i) => i);
+
+/** Serve FILE like the backend: ranges capped at `cap` bytes, or the whole file */
+function server(cap: number, honourRanges = true) {
+  const ranges: string[] = [];
+  const fetchImpl = async (_url: RequestInfo | URL, init?: RequestInit) => {
+    const range = (init?.headers as Record<string, string>).Range;
+    ranges.push(range);
+    if (!honourRanges) {
+      return new Response(FILE, { status: 200 });
+    }
+    const [, start, end] = /bytes=(\d+)-(\d+)/.exec(range) ?? [];
+    const last = Math.min(Number(end), Number(start) + cap - 1);
+    return new Response(FILE.slice(Number(start), last + 1), { status: 206 });
+  };
+  return { fetchImpl: fetchImpl as typeof fetch, ranges };
+}
+
+describe('fetchRange', () => {
+  it('asks for the range it needs', async () => {
+    const { fetchImpl, ranges } = server(64);
+
+    const bytes = await fetchRange('monight://localhost/t', 10, 20, fetchImpl);
+
+    expect(Array.from(bytes)).toEqual(Array.from(FILE.slice(10, 20)));
+    expect(ranges).toEqual(['bytes=10-19']);
+  });
+
+  it('continues where a capped response stopped', async () => {
+    const { fetchImpl, ranges } = server(30);
+
+    const bytes = await fetchRange('monight://localhost/t', 5, 75, fetchImpl);
+
+    expect(Array.from(bytes)).toEqual(Array.from(FILE.slice(5, 75)));
+    expect(ranges).toEqual(['bytes=5-74', 'bytes=35-74', 'bytes=65-74']);
+  });
+
+  it('cuts the range out of a full response', async () => {
+    const { fetchImpl } = server(0, false);
+
+    const bytes = await fetchRange('monight://localhost/t', 40, 50, fetchImpl);
+
+    expect(Array.from(bytes)).toEqual(Array.from(FILE.slice(40, 50)));
+  });
+
+  it('fails on errors and short documents', async () => {
+    const failing = (async () => new Response(null, { status: 404 })) as typeof fetch;
+    await expect(fetchRange('monight://localhost/t', 0, 10, failing)).rejects.toThrow('404');
+
+    const { fetchImpl } = server(64);
+    await expect(fetchRange('monight://localhost/t', 90, 120, fetchImpl)).rejects.toThrow(
+      'ended at byte 100',
+    );
+  });
+});
@@ -0,0 +1,29 @@
+import { describe, expect, it } from 'vitest';
+import { storageValue } from '../lib/form-values';
+
+describe('storageValue', () => {
+  it('turns buttons on when the value names their on state', () => {
+    const agree = { id: '12R', type: 'checkbox', exportValues: 'Yes' };
+    const express = { id: '13R', type: 'radiobutton', exportValues: 'Express' };
+
+    expect(storageValue(agree, 'Yes')).toEqual({ value: true });
+    expect(storageValue(agree, 'Off')).toEqual({ value: false });
+    expect(storageValue(express, 'Express')).toEqual({ value: true });
+    expect(storageValue(express, 'Standard')).toEqual({ value: false });
+  });
+
+  it('keeps every selection of a list', () => {
+    const colours = { id: '14R', type: 'listbox' };
+
+    expect(storageValue(colours, ['Red', 'Blue'])).toEqual({ value: ['Red', 'Blue'] });
+    expect(storageValue({ id: '15R', type: 'combobox' }, 'Red')).toEqual({ value: 'Red' });
+  });
+
+  it('shows text as it is, or the first of several values', () => {
+    const city = { id: '16R', type: 'text' };
+
+    expect(storageValue(city, 'Lisbon')).toEqual({ value: 'Lisbon' });
+    expect(storageValue(city, ['Lisbon', 'Porto'])).toEqual({ value: 'Lisbon' });
+    expect(storageValue(city, [])).toEqual({ value: '' });
+  });
+});
@@ -1,5 +1,9 @@
 import { describe, expect, it } from 'vitest';
-import { buildPdfLinkDomAttributes, INERT_PDF_LINK_HREF } from '../lib/pdf-links';
+import {
+  buildPdfLinkDomAttributes,
+  INERT_PDF_LINK_HREF,
+  isDocumentLinkTarget,
+} from '../lib/pdf-links';
 
 describe('buildPdfLinkDomAttributes', () => {
   it('keeps external annotation URLs out of rendered hrefs', () => {
@@ -19,4 +23,27 @@ describe('buildPdfLinkDomAttributes', () => {
     expect(attributes.title).toBe('Internal PDF link');
     expect(attributes.ariaLabel).toBe('Open internal PDF link');
   });
+
+  it('labels attachments without exposing their file name', () => {
+    const attributes = buildPdfLinkDomAttributes({ attachment: 'javascript:run.log' });
+
+    expect(attributes.href).toBe(INERT_PDF_LINK_HREF);
+    expect(attributes.title).toBe('Attached file');
+    expect(attributes.ariaLabel).toBe('Save attached file');
+  });
+});
+
+describe('isDocumentLinkTarget', () => {
+  it('treats relative paths, file URLs and drive letters as documents', () => {
+    expect(isDocumentLinkTarget('chapters/one.pdf#Intro')).toBe(true);
+    expect(isDocumentLinkTarget('../shared/ref.pdf')).toBe(true);
+    expect(isDocumentLinkTarget('file:///docs/a.pdf')).toBe(true);
+    expect(isDocumentLinkTarget('C:\\docs\\a.pdf')).toBe(true);
+  });
+
+  it('leaves web and other scheme links to the link policy', () => {
+    expect(isDocumentLinkTarget('https://example.com/a.pdf')).toBe(false);
+    expect(isDocumentLinkTarget('javascript:alert(1)')).toBe(false);
+    expect(isDocumentLinkTarget('zotero://select/items/ABC')).toBe(false);
+  });
 });
@@ -1,7 +1,10 @@
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
+import type { DocumentSource, DocumentStream } from '../lib/document-stream';
+import type { FormFieldValue } from '../lib/form-values';
 import type { FilterSettings } from '../scripts/filters';
-import type { TabManager } from '../scripts/tabs';
+import type { TabData, TabManager } from '../scripts/tabs';
+import { restoreReadingPosition } from './tab-state';
 import { withActiveViewer } from './viewer-helpers';
 
 interface OpenFilesOptions {
@@ -12,6 +15,144 @@ interface OpenFilesOptions {
   initialViewMode?: 'single' | 'continuous';
 }
 
+interface DocumentSecurity {
+  handler: string;
+  version: number;
+  revision: number;
+  method: 'RC4' | 'AES-128' | 'AES-256' | 'unknown';
+  keyBits: number;
+  permissions: { print: boolean; copy: boolean; modify: boolean };
+  passwordRequired: boolean;
+}
+
+interface ValidatedDocument {
+  path: string;
+  // PDFs are streamed; XDP packages are unpacked by the backend and read whole
+  kind: 'pdf' | 'xdp';
+  security: DocumentSecurity | null;
+}
+
+/**
+ * Ask for the password of an encrypted document until the backend accepts one.
+ * Returns `null` when the user gives up.
+ */
+async function askForPassword(path: string): Promise<string | null> {
+  let message = `${path} is password protected. Enter the password to open it:`;
+  for (;;) {
+    const password = prompt(message);
+    if (password === null) {
+      return null;
+    }
+    try {
+      await invoke('unlock_document', { path, password });
+      return password;
+    } catch (error) {
+      if ((error as { code?: string })?.code !== 'INCORRECT_PASSWORD') {
+        throw error;
+      }
+      message = `Incorrect password for ${path}. Try again:`;
+    }
+  }
+}
+
+/** Active content the backend found while opening a document */
+interface RiskReport {
+  path: string;
+  risk: 'none' | 'low' | 'medium' | 'high';
+  javascript: number;
+  openAction: boolean;
+  additionalActions: number;
+  launch: number;
+  embeddedFiles: number;
+  richMedia: number;
+  uris: number;
+  xfa: boolean;
+  encrypted: boolean;
+}
+
+function describeRisks(report: RiskReport): string[] {
+  const found = [
+    [report.javascript, 'JavaScript action(s)'],
+    [report.launch, 'launch action(s)'],
+    [report.richMedia, 'embedded media player(s)'],
+    [report.embeddedFiles, 'attached file(s)'],
+    [report.additionalActions, 'automatic action trigger(s)'],
+  ] as const;
+  return [
+    ...found.filter(([count]) => count > 0).map(([count, what]) => `${count} ${what}`),
+    ...(report.openAction ? ['an action that runs on open'] : []),
+    ...(report.xfa ? ['an XFA form'] : []),
+  ];
+}
+
+/** Point out active content before the user starts trusting a document. */
+function warnAboutRisks(path: string, report: RiskReport | null): void {
+  if (!report || report.risk === 'none' || report.risk === 'low') {
+    return;
+  }
+  console.warn(`Active content in ${path}:`, report);
+  if (report.risk === 'high') {
+    const risks = describeRisks(report).join(', ');
+    alert(`${path} contains ${risks}. Only trust it if you know where it came from.`);
+  }
+}
+
+/**
+ * Serve a PDF through the `monight://` scheme so the viewer loads it in ranges.
+ * XDP packages wrap their PDF, so the backend unpacks it and sends the bytes.
+ * Either way the backend scans the document and returns what it found.
+ */
+async function openDocumentSource(
+  kind: ValidatedDocument['kind'],
+  path: string,
+): Promise<{ source: DocumentSource; report: RiskReport | null }> {
+  if (kind === 'pdf') {
+    const { report, ...stream } = await invoke<DocumentStream & { report: RiskReport | null }>(
+      'open_document_stream',
+      { path },
+    );
+    return { source: { stream }, report };
+  }
+  // Received as binary ArrayBuffer via Tauri's IPC
+  const pdfData: ArrayBuffer = await invoke('read_pdf_file', { path });
+  // Scanned while it was read, so this is answered from the backend's cache
+  const report = await invoke<RiskReport>('scan_document', { path }).catch((error) => {
+    console.error('Failed to scan document:', error);
+    return null;
+  });
+  return { source: { data: new Uint8Array(pdfData) }, report };
+}
+
+/** Close the stream of a source no tab took over, since no tab will close it */
+function releaseSource(source: DocumentSource): void {
+  if (!('stream' in source)) return;
+  invoke('close_document_stream', { token: source.stream.token }).catch((error) => {
+    console.error('Failed to close document stream:', error);
+  });
+}
+
+/** Values of the FDF or XFDF file a document was opened through */
+interface PendingFormData {
+  target: string | null;
+  fields: FormFieldValue[];
+}
+
+/** Fill the form fields of a tab with the values of the data file that opened it. */
+async function fillForm(
+  tabManager: TabManager,
+  tab: TabData | null,
+  formData: PendingFormData | null,
+): Promise<void> {
+  if (!tab || !formData?.fields.length) return;
+  try {
+    const viewer = tabManager.getViewerForTab(tab.id);
+    const filled = (await viewer?.applyFormValues(formData.fields)) ?? 0;
+    console.log(`Filled ${filled} of ${formData.fields.length} form field(s) in ${tab.title}`);
+  } catch (error) {
+    console.error('Failed to fill in form data:', error);
+  }
+}
+
 interface EnsureViewingSizeOptions {
   fillAvailableHeight?: boolean;
 }
@@ -30,26 +171,51 @@ export async function openFiles(
 
   for (const filePath of filePaths) {
     try {
-      const canonicalPath: string = await invoke('validate_open_path', { path: filePath });
+      const validated: ValidatedDocument = await invoke('validate_open_path', { path: filePath });
+      const canonicalPath = validated.path;
+      // Taken right away so that documents which never open leave nothing behind
+      const formData = await invoke<PendingFormData | null>('take_form_data', {
+        path: canonicalPath,
+      });
 
       // Check if already open
       if (tabManager.isFileOpen(canonicalPath)) {
         console.log(`File already open: ${canonicalPath}`);
+        await fillForm(tabManager, tabManager.getActiveTab(), formData);
         continue;
       }
 
-      // Load PDF data (received as binary ArrayBuffer via Tauri's IPC)
-      const pdfData: ArrayBuffer = await invoke('read_pdf_file', { path: canonicalPath });
+      let password: string | undefined;
+      if (validated.security?.passwordRequired) {
+        const unlocked = await askForPassword(canonicalPath);
+        if (unlocked === null) {
+          console.log(`Not opening locked PDF: ${canonicalPath}`);
+          continue;
+        }
+        password = unlocked;
+      }
+
       const fileName: string = await invoke('get_file_name', { path: canonicalPath });
+      const { source, report } = await openDocumentSource(validated.kind, canonicalPath);
+      warnAboutRisks(canonicalPath, report);
 
       // Create tab (TabManager handles viewer creation)
-      await tabManager.createTab(
-        canonicalPath,
-        fileName,
-        new Uint8Array(pdfData),
-        initialFilterSettings,
-        initialViewMode ?? 'single',
-      );
+      let tab: TabData;
+      try {
+        tab = await tabManager.createTab(
+          canonicalPath,
+          fileName,
+          source,
+          initialFilterSettings,
+          initialViewMode ?? 'single',
+          password,
+        );
+      } catch (error) {
+        releaseSource(source);
+        throw error;
+      }
+      await fillForm(tabManager, tab, formData);
+      await restoreReadingPosition(tabManager, tab);
       opened += 1;
 
       console.log(`Opened PDF: ${fileName}`);
@@ -68,6 +234,26 @@ export async function openFiles(
   return opened;
 }
 
+/**
+ * Reload every tab showing `path` after the file changed on disk. Each tab keeps
+ * its page, zoom and view mode.
+ */
+export async function reloadDocument(tabManager: TabManager, path: string): Promise<void> {
+  for (const tab of tabManager.getTabs()) {
+    if (tab.filePath !== path) continue;
+    try {
+      const { source, report } = await openDocumentSource(tab.stream ? 'pdf' : 'xdp', path);
+      warnAboutRisks(path, report);
+      await tabManager.reloadTab(tab.id, source).catch((error) => {
+        releaseSource(source);
+        throw error;
+      });
+    } catch (error) {
+      console.error(`Failed to reload ${path}:`, error);
+    }
+  }
+}
+
 // Open PDF file dialog
 export async function openPDFFile(
   tabManager: TabManager | null,
@@ -108,8 +294,10 @@ export async function openPDFFile(
 // Update print menu state based on whether a PDF is loaded
 export async function updatePrintMenuState(tabManager: TabManager | null): Promise<void> {
   const hasPDF = (tabManager?.size ?? 0) > 0;
+  // Documents that forbid printing keep the menu item disabled
+  const path = tabManager?.getActiveTab()?.filePath ?? null;
   try {
-    await invoke('set_print_enabled', { enabled: hasPDF });
+    await invoke('set_print_enabled', { enabled: hasPDF, path });
     console.log(`Print menu ${hasPDF ? 'enabled' : 'disabled'}`);
   } catch (error) {
     console.error('Failed to update print menu state:', error);
@@ -131,6 +319,20 @@ export async function printCurrentPDF(tabManager: TabManager | null): Promise<vo
     return;
   }
 
+  // The keyboard shortcut bypasses the disabled menu item, so ask again here
+  try {
+    const permissions = await invoke<{ print: boolean }>('document_permissions', {
+      path: activeTab.filePath,
+    });
+    if (!permissions.print) {
+      alert('This document does not allow printing.');
+      return;
+    }
+  } catch (error) {
+    console.error('Failed to read document permissions:', error);
+    return;
+  }
+
   await withActiveViewer(tabManager, async (viewer) => {
     try {
       await viewer.print();
@@ -1,4 +1,5 @@
-import { buildFilterCSS } from '../scripts/filters';
+import { invoke } from '@tauri-apps/api/core';
+import { buildFilterCSS, type FilterSettings } from '../scripts/filters';
 import type { SliderManager } from '../scripts/sliders';
 import type { TabData, TabManager } from '../scripts/tabs';
 import { updateActivePresetButton } from './ui';
@@ -37,6 +38,58 @@ export async function restoreTabState(
   }
 }
 
+interface ReadingPosition {
+  page: number;
+  zoom: number;
+  viewMode: 'single' | 'continuous';
+  filterSettings: FilterSettings | null;
+}
+
+// Positions are saved in batches, so paging through a document writes once a second at most
+const READING_POSITION_SAVE_MS = 1000;
+
+const pendingPositions = new Map<string, ReadingPosition>();
+let positionSaveTimer: number | null = null;
+
+// Save the positions waiting for the next batch right away
+export function flushReadingPositions(): void {
+  if (positionSaveTimer !== null) {
+    clearTimeout(positionSaveTimer);
+    positionSaveTimer = null;
+  }
+
+  for (const [path, position] of pendingPositions) {
+    invoke('set_reading_position', { path, position }).catch((error) => {
+      console.warn('Failed to save reading position:', error);
+    });
+  }
+  pendingPositions.clear();
+}
+
+// Reopen a document where it was last read; positions follow the file's contents,
+// so moved or copied files keep their place
+export async function restoreReadingPosition(
+  tabManager: TabManager | null,
+  tab: TabData,
+): Promise<void> {
+  try {
+    const position = await invoke<ReadingPosition | null>('get_reading_position', {
+      path: tab.filePath,
+    });
+    if (!position) return;
+
+    tab.currentPage = position.page;
+    tab.zoom = position.zoom;
+    tab.viewMode = position.viewMode;
+    if (position.filterSettings) {
+      tab.filterSettings = { ...position.filterSettings };
+    }
+    await restoreTabState(tabManager, null, tab);
+  } catch (error) {
+    console.warn('Failed to restore reading position:', error);
+  }
+}
+
 // Save current tab state
 export function saveCurrentTabState(
   tabManager: TabManager | null,
@@ -59,4 +112,15 @@ export function saveCurrentTabState(
   if (sliderManager?.isInitialized()) {
     activeTab.filterSettings = sliderManager.getCurrentSettings();
   }
+
+  const position: ReadingPosition = {
+    page: activeTab.currentPage,
+    zoom: activeTab.zoom,
+    viewMode: activeTab.viewMode,
+    filterSettings: activeTab.filterSettings,
+  };
+  pendingPositions.set(activeTab.filePath, position);
+  if (positionSaveTimer === null) {
+    positionSaveTimer = window.setTimeout(flushReadingPositions, READING_POSITION_SAVE_MS);
+  }
 }
@@ -3,11 +3,39 @@ import { listen } from '@tauri-apps/api/event';
 import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import type { FilterSettings } from '../scripts/filters';
 import type { KeybindManager } from '../scripts/keybind-manager';
+import type { SynctexPosition } from '../scripts/pdf-viewer';
 import type { SettingsManager } from '../scripts/settings';
 import type { TabManager } from '../scripts/tabs';
-import { openFiles } from './file-actions';
+import { openFiles, reloadDocument } from './file-actions';
 import { withActiveViewer } from './viewer-helpers';
 
+type OpenTarget =
+  | { kind: 'page'; value: number }
+  | { kind: 'pageLabel' | 'destination' | 'search'; value: string };
+
+interface FileTarget {
+  file: string;
+  /** One-based page, already resolved from `target` when one was given */
+  page: number | null;
+  zoom: number | null;
+  viewMode: 'single' | 'continuous' | null;
+  target: OpenTarget | null;
+  /** Region found by a SyncTeX forward search; takes precedence over `page` */
+  synctex?: SynctexPosition | null;
+}
+
+interface CliOpenPayload {
+  files: string[];
+  targets?: FileTarget[];
+}
+
+/** A watched document whose size or modification time changed on disk */
+interface DocumentChanged {
+  path: string;
+  size: number;
+  modifiedMs: number;
+}
+
 interface TauriListenerContext {
   tabManager: TabManager | null;
   settingsManager: SettingsManager | null;
@@ -46,25 +74,42 @@ export async function setupTauriListeners({
     return ext ? ['pdf', 'xdp', 'fdf', 'xfdf'].includes(ext) : false;
   };
 
-  const handleCliOpenPayload = async (payload: { files: string[]; page: number | null }) => {
+  const handleCliOpenPayload = async (payload: CliOpenPayload) => {
     console.log('CLI open files event:', payload);
     if (!tabManager) return;
 
-    const { files, page } = payload;
+    const { files, targets = [] } = payload;
 
     try {
       const initialFilterSettings = getInitialFilterSettings();
       const initialViewMode = getInitialViewMode();
-      // Open each file
-      await openFiles(files, { tabManager, initialFilterSettings, initialViewMode });
-
-      // Navigate to specific page if provided (applies to first/active tab)
-      if (page && page > 0) {
-        await withActiveViewer(tabManager, async (viewer) => {
-          await viewer.goToPage(page);
-          updateUI();
-          console.log(`Navigated to page ${page}`);
-        });
+      // Open each file, then show it where it was asked to open
+      for (const file of files) {
+        await openFiles([file], { tabManager, initialFilterSettings, initialViewMode });
+
+        const fileTarget = targets.find((candidate) => candidate.file === file);
+        if (fileTarget) {
+          await withActiveViewer(tabManager, async (viewer, tab) => {
+            const { page, zoom, viewMode, target, synctex } = fileTarget;
+            if (viewMode) {
+              await viewer.setViewMode(viewMode);
+              tab.viewMode = viewMode;
+            }
+            if (zoom && zoom > 0) {
+              await viewer.setZoom(zoom);
+              tab.zoom = zoom;
+            }
+            if (synctex) {
+              await viewer.highlightSynctexRegion(synctex);
+            } else if (page && page > 0) {
+              await viewer.goToPage(page);
+              console.log(`Navigated to page ${page} of ${file}`);
+            } else if (target) {
+              console.warn(`Could not find ${target.kind} "${target.value}" in ${file}`);
+            }
+            updateUI();
+          });
+        }
       }
 
       // Update UI
@@ -125,18 +170,22 @@ export async function setupTauriListeners({
   });
 
   // Listen for CLI file open events
-  await listen<{ files: string[]; page: number | null }>('cli-open-files', async (event) => {
+  await listen<CliOpenPayload>('cli-open-files', async (event) => {
     await handleCliOpenPayload(event.payload);
   });
 
   // Pull any pending CLI payloads that were emitted before listeners were ready
-  const pendingPayload = await invoke<{ files: string[]; page: number | null } | null>(
-    'take_cli_payload',
-  );
+  const pendingPayload = await invoke<CliOpenPayload | null>('take_cli_payload');
   if (pendingPayload?.files?.length) {
     await handleCliOpenPayload(pendingPayload);
   }
 
+  // Reload documents edited on disk
+  await listen<DocumentChanged>('document-changed', async (event) => {
+    if (!tabManager) return;
+    await reloadDocument(tabManager, event.payload.path);
+  });
+
   // Listen for menu events
   await listen('menu-open', async () => {
     console.log('Menu open event received');
@@ -39,3 +39,47 @@ export function deriveScaledDimensions({
 
   return { width, height };
 }
+
+/**
+ * A point on a page as fractions of its width and height, from the top-left.
+ */
+export interface PagePoint {
+  x: number;
+  y: number;
+}
+
+const normalizeRotation = (rotation: number): number => ((rotation % 360) + 360) % 360;
+
+/**
+ * Where a point of the unrotated page appears once the page is shown rotated
+ * clockwise by `rotation` degrees.
+ */
+export function rotatePagePoint({ x, y }: PagePoint, rotation: number): PagePoint {
+  switch (normalizeRotation(rotation)) {
+    case 90:
+      return { x: 1 - y, y: x };
+    case 180:
+      return { x: 1 - x, y: 1 - y };
+    case 270:
+      return { x: y, y: 1 - x };
+    default:
+      return { x, y };
+  }
+}
+
+/**
+ * The point of the unrotated page shown at `point` on a page rotated
+ * clockwise by `rotation` degrees; the inverse of `rotatePagePoint`.
+ */
+export function unrotatePagePoint({ x, y }: PagePoint, rotation: number): PagePoint {
+  switch (normalizeRotation(rotation)) {
+    case 90:
+      return { x: y, y: 1 - x };
+    case 180:
+      return { x: 1 - x, y: 1 - y };
+    case 270:
+      return { x: 1 - y, y: x };
+    default:
+      return { x, y };
+  }
+}
@@ -0,0 +1,54 @@
+/**
+ * Ranged loading of documents served by the backend's `monight://` scheme.
+ *
+ * `open_document_stream` registers a document under a URL that answers HTTP
+ * range requests, so PDF.js fetches the parts it renders instead of the whole
+ * file crossing IPC before the first page appears.
+ */
+
+/** A document registered with `open_document_stream` */
+export interface DocumentStream {
+  token: string;
+  url: string;
+  // Size of the document in bytes
+  length: number;
+}
+
+/** What a viewer loads: a stream, or bytes for documents that cannot be streamed */
+export type DocumentSource = { stream: DocumentStream } | { data: Uint8Array };
+
+/** Bytes PDF.js asks for at once; each request fits in one backend response */
+export const RANGE_CHUNK_SIZE = 512 * 1024;
+
+/**
+ * Fetch bytes `begin` up to (not including) `end` of a streamed document.
+ *
+ * The backend caps every response, so a long range can take several requests;
+ * each one continues where the previous response stopped.
+ */
+export async function fetchRange(
+  url: string,
+  begin: number,
+  end: number,
+  fetchImpl: typeof fetch = fetch,
+): Promise<Uint8Array> {
+  const bytes = new Uint8Array(end - begin);
+  let received = 0;
+  while (received < bytes.length) {
+    const from = begin + received;
+    const response = await fetchImpl(url, { headers: { Range: `bytes=${from}-${end - 1}` } });
+    if (!response.ok) {
+      throw new Error(`Failed to load bytes ${from}-${end - 1}: HTTP ${response.status}`);
+    }
+    const body = new Uint8Array(await response.arrayBuffer());
+    // A full response starts at the first byte of the file
+    const chunk = response.status === 206 ? body : body.subarray(from);
+    if (chunk.length === 0) {
+      throw new Error(`Document ended at byte ${from}, before ${end}`);
+    }
+    const part = chunk.subarray(0, bytes.length - received);
+    bytes.set(part, received);
+    received += part.length;
+  }
+  return bytes;
+}
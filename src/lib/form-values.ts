@@ -0,0 +1,41 @@
+/**
+ * Field values from FDF and XFDF files, shaped for the PDF.js annotation storage.
+ *
+ * `take_form_data` returns values by fully qualified field name; PDF.js keeps
+ * them per widget, in a shape that depends on the kind of field.
+ */
+
+/** A field value from `take_form_data`: text or a button state, or list selections */
+export interface FormFieldValue {
+  name: string;
+  value: string | string[];
+}
+
+/** A widget of a field, as listed by `PDFDocumentProxy.getFieldObjects` */
+export interface FieldWidget {
+  id: string;
+  type: string;
+  // On state of a checkbox or radio button
+  exportValues?: string;
+}
+
+/**
+ * Annotation storage entry that shows `value` in `widget`. Buttons are on when
+ * the value names their on state; text fields show the first of several values.
+ */
+export function storageValue(
+  widget: FieldWidget,
+  value: string | string[],
+): { value: string | string[] | boolean } {
+  const first = Array.isArray(value) ? (value[0] ?? '') : value;
+  switch (widget.type) {
+    case 'checkbox':
+    case 'radiobutton':
+      return { value: first === widget.exportValues };
+    case 'combobox':
+    case 'listbox':
+      return { value };
+    default:
+      return { value: first };
+  }
+}
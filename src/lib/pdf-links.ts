@@ -3,6 +3,12 @@ export type PdfDestination = string | unknown[];
 export interface PdfLinkTarget {
   url?: string;
   dest?: PdfDestination;
+  // Another document (GoToR or Launch), relative to the linking one
+  file?: string;
+  // File name of an attachment (FileAttachment annotation) to save
+  attachment?: string;
+  // One-based page carrying the attachment, which tells apart files sharing a name
+  attachmentPage?: number;
 }
 
 export interface PdfLinkDomAttributes {
@@ -13,6 +19,15 @@ export interface PdfLinkDomAttributes {
 
 export const INERT_PDF_LINK_HREF = '#';
 
+/**
+ * Whether a link target names a local file rather than a web address:
+ * a relative path, a `file:` URL or a Windows path with a drive letter.
+ */
+export function isDocumentLinkTarget(target: string): boolean {
+  const scheme = /^([a-z][a-z0-9+.-]*):/i.exec(target)?.[1];
+  return !scheme || scheme.length === 1 || scheme.toLowerCase() === 'file';
+}
+
 export function buildPdfLinkDomAttributes(target: PdfLinkTarget): PdfLinkDomAttributes {
   if (target.url) {
     return {
@@ -22,6 +37,22 @@ export function buildPdfLinkDomAttributes(target: PdfLinkTarget): PdfLinkDomAttr
     };
   }
 
+  if (target.file) {
+    return {
+      href: INERT_PDF_LINK_HREF,
+      title: 'Link to another document',
+      ariaLabel: 'Open linked document',
+    };
+  }
+
+  if (target.attachment) {
+    return {
+      href: INERT_PDF_LINK_HREF,
+      title: 'Attached file',
+      ariaLabel: 'Save attached file',
+    };
+  }
+
   return {
     href: INERT_PDF_LINK_HREF,
     title: 'Internal PDF link',
@@ -10,7 +10,7 @@ import {
 } from './app/file-actions';
 import { registerKeybindActions } from './app/keybinds';
 import { captureReadingSession, restoreReadingSession } from './app/session-state';
-import { restoreTabState, saveCurrentTabState } from './app/tab-state';
+import { flushReadingPositions, restoreTabState, saveCurrentTabState } from './app/tab-state';
 import { setupTauriListeners } from './app/tauri-events';
 import {
   showSplash,
@@ -356,6 +356,7 @@ async function initializeApp(): Promise<void> {
 
     window.addEventListener('beforeunload', () => {
       void saveReadingSessionNow();
+      flushReadingPositions();
     });
 
     // Show window after initialization
@@ -1,6 +1,20 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { PDFDocumentProxy, PDFPageProxy, RenderTask, TextLayer } from 'pdfjs-dist';
-import { deriveScaledDimensions } from '../lib/dimensions';
+import { save } from '@tauri-apps/plugin-dialog';
+import type {
+  PDFDataRangeTransport,
+  PDFDocumentProxy,
+  PDFPageProxy,
+  RenderTask,
+  TextLayer,
+} from 'pdfjs-dist';
+import { deriveScaledDimensions, rotatePagePoint, unrotatePagePoint } from '../lib/dimensions';
+import {
+  type DocumentSource,
+  type DocumentStream,
+  fetchRange,
+  RANGE_CHUNK_SIZE,
+} from '../lib/document-stream';
+import { type FieldWidget, type FormFieldValue, storageValue } from '../lib/form-values';
 import { hasValueChanged } from '../lib/guards';
 import {
   computeSafeOutputScale,
@@ -10,6 +24,7 @@ import {
 import { getPdfEngine } from '../lib/pdf-engine';
 import {
   buildPdfLinkDomAttributes,
+  isDocumentLinkTarget,
   type PdfDestination,
   type PdfLinkTarget,
 } from '../lib/pdf-links';
@@ -44,8 +59,25 @@ interface LinkAnnotationData {
   url?: string;
   unsafeUrl?: string;
   dest?: PdfDestination;
+  // Embedded file of a FileAttachment annotation
+  file?: { filename?: string };
+}
+
+// Result of `open_external_url`
+type LinkOutcome = { outcome: 'opened' } | { outcome: 'confirm'; url: string };
+
+/** Region typeset from a TeX line, in PDF points from the page's top-left */
+export interface SynctexPosition {
+  page: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
 }
 
+/** How long a forward search highlight stays on the page */
+const SYNCTEX_HIGHLIGHT_MS = 2000;
+
 interface PageSurface {
   wrapper: HTMLDivElement;
   canvas: HTMLCanvasElement;
@@ -144,6 +176,8 @@ export class PDFViewer {
     viewMode: 'single',
   };
   private renderTask: RenderTask | null = null;
+  // Set once form values were filled in, so pages draw fields from the annotation storage
+  private annotationMode: number | undefined;
   private canvasId: string;
   private currentFilterCSS = '';
   private onPageChange: ((pageNum: number) => void) | null = null;
@@ -212,6 +246,7 @@ export class PDFViewer {
       wrapper.dataset.pageNum = pageNum.toString();
     }
     wrapper.addEventListener('contextmenu', (event) => this.handlePageContextMenu(event));
+    wrapper.addEventListener('click', (event) => void this.handlePageClick(event, wrapper));
 
     const canvas = document.createElement('canvas');
     canvas.id = canvasId;
@@ -277,7 +312,12 @@ export class PDFViewer {
     }
   }
 
-  async loadPDF(pdfData: Uint8Array, fileName: string, filePath: string): Promise<void> {
+  async loadPDF(
+    source: DocumentSource,
+    fileName: string,
+    filePath: string,
+    password?: string,
+  ): Promise<void> {
     try {
       // Cancel any pending render
       if (this.renderTask) {
@@ -288,8 +328,19 @@ export class PDFViewer {
       const pdfjsLib = await getPdfEngine();
 
       // Load PDF document
-      const loadingTask = pdfjsLib.getDocument({ data: pdfData });
+      // Encrypted documents were unlocked with this password by the backend
+      const loadingTask =
+        'stream' in source
+          ? pdfjsLib.getDocument({
+              range: this.createRangeTransport(pdfjsLib, source.stream),
+              password,
+              rangeChunkSize: RANGE_CHUNK_SIZE,
+              // Fetch pages as they are shown instead of the whole file in the background
+              disableAutoFetch: true,
+            })
+          : pdfjsLib.getDocument({ data: source.data, password });
       this.pdfDoc = await loadingTask.promise;
+      this.annotationMode = undefined;
 
       // Update state
       this.state.totalPages = this.pdfDoc.numPages;
@@ -320,6 +371,51 @@ export class PDFViewer {
     }
   }
 
+  /**
+   * Fill form fields with values from an FDF or XFDF file and draw them.
+   * Returns how many of the fields the document has.
+   */
+  async applyFormValues(fields: FormFieldValue[]): Promise<number> {
+    if (!this.pdfDoc || fields.length === 0) return 0;
+
+    const widgets = ((await this.pdfDoc.getFieldObjects()) ?? {}) as Record<string, FieldWidget[]>;
+    let applied = 0;
+    for (const field of fields) {
+      const fieldWidgets = widgets[field.name] ?? [];
+      for (const widget of fieldWidgets) {
+        this.pdfDoc.annotationStorage.setValue(widget.id, storageValue(widget, field.value));
+      }
+      if (fieldWidgets.length > 0) applied += 1;
+    }
+    if (applied === 0) return 0;
+
+    const pdfjsLib = await getPdfEngine();
+    this.annotationMode = pdfjsLib.AnnotationMode.ENABLE_STORAGE;
+    if (this.state.viewMode === 'continuous') {
+      await this.renderVisiblePages(true);
+    } else {
+      await this.renderPage(this.state.currentPage);
+    }
+    return applied;
+  }
+
+  /**
+   * Feed PDF.js from the `monight://` URL of a streamed document, one range
+   * request per chunk it asks for.
+   */
+  private createRangeTransport(
+    pdfjsLib: Awaited<ReturnType<typeof getPdfEngine>>,
+    stream: DocumentStream,
+  ): PDFDataRangeTransport {
+    const transport = new pdfjsLib.PDFDataRangeTransport(stream.length, null);
+    transport.requestDataRange = (begin: number, end: number) => {
+      fetchRange(stream.url, begin, end)
+        .then((chunk) => transport.onDataRange(begin, chunk))
+        .catch((error) => console.error('Failed to load document range:', error));
+    };
+    return transport;
+  }
+
   private async renderInteractiveLayers(
     page: PDFPageProxy,
     viewport: PageViewport,
@@ -388,11 +484,15 @@ export class PDFViewer {
           return;
         }
 
-        if (annotation.annotationType !== pdfjsLib.AnnotationType.LINK || !annotation.rect) {
+        const { LINK, FILEATTACHMENT } = pdfjsLib.AnnotationType;
+        if (
+          (annotation.annotationType !== LINK && annotation.annotationType !== FILEATTACHMENT) ||
+          !annotation.rect
+        ) {
           continue;
         }
 
-        const target = this.getLinkTarget(annotation);
+        const target = this.getLinkTarget(annotation, page.pageNumber);
         if (!target) {
           continue;
         }
@@ -443,16 +543,19 @@ export class PDFViewer {
     }
   }
 
-  private getLinkTarget(annotation: LinkAnnotationData): PdfLinkTarget | null {
-    const url =
-      typeof annotation.url === 'string'
-        ? annotation.url
-        : typeof annotation.unsafeUrl === 'string'
-          ? annotation.unsafeUrl
-          : undefined;
+  private getLinkTarget(annotation: LinkAnnotationData, pageNum: number): PdfLinkTarget | null {
+    if (annotation.file?.filename) {
+      return { attachment: annotation.file.filename, attachmentPage: pageNum };
+    }
 
-    if (url) {
-      return { url };
+    if (typeof annotation.url === 'string' && annotation.url) {
+      return { url: annotation.url };
+    }
+
+    // PDF.js leaves targets it cannot make absolute, such as GoToR files, unsafe
+    const unsafeUrl = annotation.unsafeUrl;
+    if (typeof unsafeUrl === 'string' && unsafeUrl) {
+      return isDocumentLinkTarget(unsafeUrl) ? { file: unsafeUrl } : { url: unsafeUrl };
     }
 
     if (annotation.dest) {
@@ -467,7 +570,11 @@ export class PDFViewer {
 
     if (target.url) {
       try {
-        await invoke('open_external_url', { url: target.url });
+        const result = await invoke<LinkOutcome>('open_external_url', { url: target.url });
+        // Show the real target before opening links the policy wants confirmed
+        if (result.outcome === 'confirm' && confirm(`Open this link?\n\n${result.url}`)) {
+          await invoke('open_external_url', { url: result.url, confirmed: true });
+        }
       } catch (error) {
         console.error('Failed to open external link:', error);
         alert(error instanceof Error ? error.message : String(error));
@@ -475,6 +582,35 @@ export class PDFViewer {
       return;
     }
 
+    if (target.file) {
+      try {
+        // Opens in a new tab through the same path as a CLI launch
+        await invoke('open_document_link', { path: this.state.filePath, target: target.file });
+      } catch (error) {
+        console.error('Failed to open linked document:', error);
+        alert(error instanceof Error ? error.message : String(error));
+      }
+      return;
+    }
+
+    if (target.attachment) {
+      try {
+        const destination = await save({ defaultPath: target.attachment });
+        if (destination) {
+          await invoke('save_attachment', {
+            path: this.state.filePath,
+            name: target.attachment,
+            page: target.attachmentPage ?? null,
+            destination,
+          });
+        }
+      } catch (error) {
+        console.error('Failed to save attachment:', error);
+        alert(error instanceof Error ? error.message : String(error));
+      }
+      return;
+    }
+
     if (target.dest) {
       const pageNum = await this.resolveDestinationPage(target.dest);
       if (pageNum !== null) {
@@ -483,6 +619,77 @@ export class PDFViewer {
     }
   }
 
+  /** Cmd/Ctrl+Shift+click runs an inverse search, opening the TeX source in the editor */
+  private async handlePageClick(event: MouseEvent, wrapper: HTMLElement): Promise<void> {
+    if (!(event.metaKey || event.ctrlKey) || !event.shiftKey || !this.state.filePath) {
+      return;
+    }
+    event.preventDefault();
+
+    const pageNum = wrapper.dataset.pageNum
+      ? Number(wrapper.dataset.pageNum)
+      : this.state.currentPage;
+    const base = this.baseDimensions.get(pageNum);
+    const rect = wrapper.getBoundingClientRect();
+    if (!base || rect.width === 0 || rect.height === 0) return;
+
+    const point = unrotatePagePoint(
+      {
+        x: (event.clientX - rect.left) / rect.width,
+        y: (event.clientY - rect.top) / rect.height,
+      },
+      this.state.rotation,
+    );
+
+    try {
+      await invoke('synctex_edit', {
+        path: this.state.filePath,
+        page: pageNum,
+        x: point.x * base.width,
+        y: point.y * base.height,
+      });
+    } catch (error) {
+      console.error('Inverse search failed:', error);
+      alert(error instanceof Error ? error.message : String(error));
+    }
+  }
+
+  /** Go to a forward search result and briefly highlight it */
+  async highlightSynctexRegion(position: SynctexPosition): Promise<void> {
+    const base = this.baseDimensions.get(position.page);
+    if (!base) return;
+
+    await this.goToPage(position.page);
+    const surface =
+      this.state.viewMode === 'continuous'
+        ? this.pageSurfaces.get(position.page)
+        : this.singlePageSurface;
+    if (!surface) return;
+
+    // Rotate two opposite corners, then span the box between them
+    const corners = [
+      { x: position.x, y: position.y },
+      { x: position.x + position.width, y: position.y + position.height },
+    ].map((corner) =>
+      rotatePagePoint(
+        { x: corner.x / base.width, y: corner.y / base.height },
+        this.state.rotation,
+      ),
+    );
+    const left = Math.min(corners[0].x, corners[1].x);
+    const top = Math.min(corners[0].y, corners[1].y);
+
+    const highlight = document.createElement('div');
+    highlight.className = 'synctex-highlight';
+    highlight.style.left = `${left * 100}%`;
+    highlight.style.top = `${top * 100}%`;
+    highlight.style.width = `${Math.abs(corners[1].x - corners[0].x) * 100}%`;
+    highlight.style.height = `${Math.abs(corners[1].y - corners[0].y) * 100}%`;
+    surface.wrapper.appendChild(highlight);
+    highlight.scrollIntoView({ block: 'center', inline: 'nearest' });
+    window.setTimeout(() => highlight.remove(), SYNCTEX_HIGHLIGHT_MS);
+  }
+
   private async resolveDestinationPage(dest: PdfDestination): Promise<number | null> {
     if (!this.pdfDoc) return null;
 
@@ -705,6 +912,11 @@ export class PDFViewer {
       return;
     }
 
+    if (target.file) {
+      await this.copyText(target.file);
+      return;
+    }
+
     if (target.dest) {
       const pageNum = await this.resolveDestinationPage(target.dest);
       await this.copyText(pageNum ? `#page=${pageNum}` : this.describeDestination(target.dest));
@@ -786,6 +998,7 @@ export class PDFViewer {
           outputScale.sx !== 1 || outputScale.sy !== 1
             ? [outputScale.sx, 0, 0, outputScale.sy, 0, 0]
             : undefined,
+        annotationMode: this.annotationMode,
       } as unknown as Parameters<PDFPageProxy['render']>[0]);
       this.renderTask = renderTask;
 
@@ -1052,8 +1265,11 @@ export class PDFViewer {
     }
 
     try {
-      // Get the raw PDF data
-      const pdfData = await this.pdfDoc.getData();
+      // Get the raw PDF data, with filled-in form values written into it
+      const pdfData =
+        this.annotationMode === undefined
+          ? await this.pdfDoc.getData()
+          : await this.pdfDoc.saveDocument();
 
       // Create a Blob from the PDF data (convert to regular Uint8Array)
       const blob = new Blob([new Uint8Array(pdfData)], { type: 'application/pdf' });
@@ -1436,6 +1652,7 @@ export class PDFViewer {
           outputScale.sx !== 1 || outputScale.sy !== 1
             ? [outputScale.sx, 0, 0, outputScale.sy, 0, 0]
             : undefined,
+        annotationMode: this.annotationMode,
       } as unknown as Parameters<PDFPageProxy['render']>[0]);
 
       this.renderTasks.set(pageNum, renderTask);
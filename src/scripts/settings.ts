@@ -26,6 +26,19 @@ export interface KeybindConfig {
   data?: string;
 }
 
+/**
+ * External link policy, read by the Rust side when a link is opened.
+ * Patterns: `example.com`, `*.example.com`, `zotero:`, `https://*.example.com` or `*`.
+ * When several lists match a link, the strictest wins.
+ */
+export interface LinkPolicy {
+  allow: string[];
+  confirm: string[];
+  block: string[];
+  extraSchemes: string[]; // e.g. 'zotero', 'obsidian'
+  fallback: 'allow' | 'confirm' | 'block';
+}
+
 /**
  * Main settings interface
  */
@@ -42,6 +55,9 @@ export interface MoonightSettings {
   keybinds: Record<string, KeybindConfig>;
   lastFilter?: FilterSettings;
   lastSession?: ReadingSession;
+  linkPolicy?: LinkPolicy;
+  /** Editor run on SyncTeX inverse search when `--synctex-editor` is not given */
+  synctexEditor?: string;
 }
 
 /**
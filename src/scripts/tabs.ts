@@ -1,4 +1,6 @@
-import { type FilterSettings, PRESETS } from './filters';
+import { invoke } from '@tauri-apps/api/core';
+import type { DocumentSource, DocumentStream } from '../lib/document-stream';
+import { buildFilterCSS, type FilterSettings, PRESETS } from './filters';
 import { PDFViewer } from './pdf-viewer';
 
 /**
@@ -8,7 +10,8 @@ export interface TabData {
   id: string; // Unique UUID
   title: string; // Filename for display
   filePath: string; // Full file path
-  pdfData: Uint8Array; // PDF binary data
+  stream: DocumentStream | null; // Served through open_document_stream, unless read whole
+  password?: string; // Unlocks the document again when it is reloaded
   filterSettings: FilterSettings; // Current filter preset
   currentPage: number; // Current page number
   zoom: number; // Current zoom level
@@ -44,9 +47,10 @@ export class TabManager {
   async createTab(
     filePath: string,
     title: string,
-    pdfData: Uint8Array,
+    source: DocumentSource,
     filterSettings?: FilterSettings,
     viewMode: 'single' | 'continuous' = 'single',
+    password?: string,
   ): Promise<TabData> {
     const id = crypto.randomUUID();
     const initialFilterSettings = filterSettings ?? PRESETS.default;
@@ -56,7 +60,8 @@ export class TabManager {
       id,
       title,
       filePath,
-      pdfData,
+      stream: 'stream' in source ? source.stream : null,
+      password,
       filterSettings: { ...initialFilterSettings },
       currentPage: 1,
       zoom: 1.0,
@@ -68,15 +73,10 @@ export class TabManager {
     this.tabs.set(id, tab);
 
     // Create PDF viewer for this tab
-    const canvasId = `pdf-canvas-${id}`;
-    const viewer = new PDFViewer('pdf-container', canvasId);
-    viewer.setOnPageChange(() => {
-      if (this.activeTabId !== id) return;
-      this.onActiveViewerStateChange?.();
-    });
+    const viewer = this.createViewer(id);
 
     // Load PDF
-    await viewer.loadPDF(pdfData, title, filePath);
+    await viewer.loadPDF(source, title, filePath, password);
 
     // Store viewer
     this.pdfViewers.set(id, viewer);
@@ -95,6 +95,57 @@ export class TabManager {
     return tab;
   }
 
+  /**
+   * Load a new version of a tab's document, keeping its page, zoom and view mode.
+   * The old viewer stays on screen until the new one is ready.
+   */
+  async reloadTab(id: string, source: DocumentSource): Promise<void> {
+    const tab = this.tabs.get(id);
+    const oldViewer = this.pdfViewers.get(id);
+    if (!tab || !oldViewer) return;
+    const { currentPage, zoom, viewMode } = oldViewer.getState();
+
+    const viewer = this.createViewer(id);
+    viewer.setVisible(false);
+    try {
+      await viewer.loadPDF(source, tab.title, tab.filePath, tab.password);
+    } catch (error) {
+      viewer.destroy();
+      throw error;
+    }
+    viewer.applyFilter(buildFilterCSS(tab.filterSettings));
+    await viewer.setZoom(zoom);
+
+    oldViewer.destroy();
+    this.pdfViewers.set(id, viewer);
+    const oldStream = tab.stream;
+    tab.stream = 'stream' in source ? source.stream : null;
+    if (oldStream) {
+      invoke('close_document_stream', { token: oldStream.token }).catch((error) => {
+        console.error('Failed to close document stream:', error);
+      });
+    }
+
+    await viewer.setViewMode(viewMode);
+    // Pages may have been removed
+    await viewer.goToPage(Math.min(currentPage, viewer.getState().totalPages));
+    viewer.setVisible(this.activeTabId === id);
+    if (this.activeTabId === id) {
+      this.onActiveViewerStateChange?.();
+    }
+
+    console.log(`Reloaded tab: ${tab.title} (${id})`);
+  }
+
+  private createViewer(id: string): PDFViewer {
+    const viewer = new PDFViewer('pdf-container', `pdf-canvas-${id}`);
+    viewer.setOnPageChange(() => {
+      if (this.activeTabId !== id) return;
+      this.onActiveViewerStateChange?.();
+    });
+    return viewer;
+  }
+
   /**
    * Close a tab
    */
@@ -115,6 +166,26 @@ export class TabManager {
     // Remove tab
     this.tabs.delete(id);
 
+    // Every tab holds its own open of the stream, even for the same file
+    if (tab.stream) {
+      invoke('close_document_stream', { token: tab.stream.token }).catch((error) => {
+        console.error('Failed to close document stream:', error);
+      });
+    }
+
+    // The backend watches open documents and keeps the permissions of encrypted ones
+    const stillOpen = Array.from(this.tabs.values()).some(
+      (other) => other.filePath === tab.filePath,
+    );
+    if (!stillOpen) {
+      invoke('unwatch_document', { path: tab.filePath }).catch((error) => {
+        console.error('Failed to stop watching document:', error);
+      });
+      invoke('forget_document_permissions', { path: tab.filePath }).catch((error) => {
+        console.error('Failed to forget document permissions:', error);
+      });
+    }
+
     // If closing active tab, activate adjacent tab
     if (this.activeTabId === id) {
       const remaining = Array.from(this.tabs.keys());